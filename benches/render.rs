@@ -8,15 +8,77 @@ mod tests {
     use super::*;
 
     use test::Bencher;
-    use abctracer::{render_scene, Environment};
+    use abctracer::{render_scene, Accelerator, Environment, GObject, RenderOptions, Surface, Vector};
     use abctracer::render::backend::NullRenderBackend;
+    use abctracer::integrator::Whitted;
+    use abctracer::accelerator::{Grid, KdTree, Linear};
+    use abctracer::geometry::Mesh;
+    use abctracer::random::rnd;
 
     #[bench]
     fn render_scene_empty_bench(b: &mut test::Bencher) {
         b.iter(|| {
             let mut backend = NullRenderBackend::new();
             let environment = Environment::new();
-            render_scene(&environment, 1.0, 1.0, 100, 100, &mut backend);
+            let integrator = Whitted::new();
+            render_scene(&environment, &integrator, 100, 100, &RenderOptions::new(), &mut backend);
         });
     }
+
+    /// Cloud of small tetrahedra in front of the default camera.
+    fn particles(n: usize) -> Vec<Mesh> {
+        (0..n)
+            .map(|_| {
+                let p = Vector::from((
+                    rnd() * 20.0 - 10.0,
+                    rnd() * 20.0 - 10.0,
+                    rnd() * 20.0 + 5.0,
+                ));
+                let r = 0.2;
+                let vertices = vec![
+                    p + Vector::from((r, 0.0, 0.0)),
+                    p + Vector::from((-r, r, 0.0)),
+                    p + Vector::from((-r, -r, r)),
+                    p + Vector::from((-r, -r, -r)),
+                ];
+                let triangles = vec![[0, 1, 2], [0, 2, 3], [0, 3, 1], [1, 3, 2]];
+                Mesh::new(Surface::new(), vertices, triangles)
+            })
+            .collect()
+    }
+
+    fn render_particles<'a>(
+        b: &mut Bencher,
+        particles: &'a [Mesh],
+        accelerator: Box<Accelerator<'a> + 'a>,
+    ) {
+        let mut environment = Environment::new();
+        for particle in particles {
+            environment.add_solid(particle as &GObject);
+        }
+        environment.set_accelerator(accelerator);
+        let integrator = Whitted::new();
+        b.iter(|| {
+            let mut backend = NullRenderBackend::new();
+            render_scene(&environment, &integrator, 100, 100, &RenderOptions::new(), &mut backend);
+        });
+    }
+
+    #[bench]
+    fn render_particles_linear_bench(b: &mut Bencher) {
+        let particles = particles(1000);
+        render_particles(b, &particles, Box::new(Linear::new()));
+    }
+
+    #[bench]
+    fn render_particles_grid_bench(b: &mut Bencher) {
+        let particles = particles(1000);
+        render_particles(b, &particles, Box::new(Grid::new()));
+    }
+
+    #[bench]
+    fn render_particles_kdtree_bench(b: &mut Bencher) {
+        let particles = particles(1000);
+        render_particles(b, &particles, Box::new(KdTree::new()));
+    }
 }
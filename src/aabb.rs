@@ -0,0 +1,153 @@
+use super::*;
+
+/// Axis aligned bounding box between corners `min` and `max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Smallest box containing all `points`.
+    pub fn from_points(points: &[Vector]) -> Aabb {
+        let mut min = Vector::from(f64::MAX);
+        let mut max = Vector::from(f64::MIN);
+        for p in points {
+            min = Vector::from((min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)));
+            max = Vector::from((max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)));
+        }
+        Aabb { min, max }
+    }
+
+    /// Smallest box containing the disc of `radius` around `center` facing
+    /// `normal`.
+    pub fn disc(center: Vector, normal: Vector, radius: f64) -> Aabb {
+        let n = normal.normalize();
+        let extent = Vector::from((
+            radius * (1.0 - n.x * n.x).max(0.0).sqrt(),
+            radius * (1.0 - n.y * n.y).max(0.0).sqrt(),
+            radius * (1.0 - n.z * n.z).max(0.0).sqrt(),
+        ));
+        Aabb::new(center - extent, center + extent)
+    }
+
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector::from((
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            )),
+            max: Vector::from((
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            )),
+        }
+    }
+
+    /// Box with every side moved outward by `amount`.
+    pub fn grow(&self, amount: f64) -> Aabb {
+        Aabb {
+            min: self.min - Vector::from(amount),
+            max: self.max + Vector::from(amount),
+        }
+    }
+
+    pub fn centroid(&self) -> Vector {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Slab test, returns the part of the ray interval inside the box.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64)> {
+        let mut t0 = ray.t_min;
+        let mut t1 = ray.t_max;
+        for &(o, d, lo, hi) in &[
+            (ray.org.x, ray.dir.x, self.min.x, self.max.x),
+            (ray.org.y, ray.dir.y, self.min.y, self.max.y),
+            (ray.org.z, ray.dir.z, self.min.z, self.max.z),
+        ] {
+            if d == 0.0 {
+                if o < lo || o > hi {
+                    return None;
+                }
+            } else {
+                let (a, b) = ((lo - o) / d, (hi - o) / d);
+                t0 = t0.max(a.min(b));
+                t1 = t1.min(a.max(b));
+                if t0 > t1 {
+                    return None;
+                }
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// Box around this box after applying `matrix`.
+    pub fn transform(&self, matrix: &Matrix) -> Aabb {
+        let corners: Vec<Vector> = (0..8)
+            .map(|i| {
+                *matrix
+                    * Vector::from((
+                        if i & 1 == 0 { self.min.x } else { self.max.x },
+                        if i & 2 == 0 { self.min.y } else { self.max.y },
+                        if i & 4 == 0 { self.min.z } else { self.max.z },
+                    ))
+            })
+            .collect();
+        Aabb::from_points(&corners)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn transformed_box() {
+        let aabb = Aabb::new(Vector::from(-1.0), Vector::from(1.0));
+        let m = Matrix::rotate_z(::std::f64::consts::FRAC_PI_4)
+            * Matrix::translate(&Vector::from((10.0, 0.0, 0.0)));
+        let moved = aabb.transform(&m);
+        let r = 2.0f64.sqrt();
+        assert!(!(moved.min - Vector::from((10.0 - r, -r, -1.0))) < 1e-9);
+        assert!(!(moved.max - Vector::from((10.0 + r, r, 1.0))) < 1e-9);
+    }
+
+    #[test]
+    fn disc_and_area() {
+        let aabb = Aabb::disc(Vector::from(1.0), Vector::from((0.0, 1.0, 1.0)), 2.0);
+        let r = 2.0f64.sqrt();
+        assert!(!(aabb.min - Vector::from((-1.0, 1.0 - r, 1.0 - r))) < 1e-9);
+        assert!(!(aabb.max - Vector::from((3.0, 1.0 + r, 1.0 + r))) < 1e-9);
+
+        let aabb = Aabb::new(Vector::from(0.0), Vector::from((1.0, 2.0, 3.0)));
+        assert_eq!(aabb.surface_area(), 22.0);
+        assert_eq!(aabb.grow(1.0).surface_area(), 2.0 * (12.0 + 20.0 + 15.0));
+    }
+
+    #[test]
+    fn slab_test() {
+        let aabb = Aabb::new(Vector::from(-1.0), Vector::from(1.0));
+        let ray = Ray::from(((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0)));
+        assert_eq!(aabb.intersect(&ray), Some((4.0, 6.0)));
+
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        assert_eq!(aabb.intersect(&ray), Some((0.001, 1.0)));
+
+        let ray = Ray::from(((-5.0, 1.5, 0.0), (1.0, 0.0, 0.0)));
+        assert_eq!(aabb.intersect(&ray), None);
+        let ray = Ray::from(((-5.0, 0.0, 0.0), (-1.0, 0.0, 0.0)));
+        assert_eq!(aabb.intersect(&ray), None);
+    }
+}
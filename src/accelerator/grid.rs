@@ -0,0 +1,212 @@
+use super::*;
+
+/// Cells per axis are limited to this.
+const MAX_RESOLUTION: usize = 128;
+
+/// Uniform grid of cells over the bounds of all objects, every cell lists
+/// the objects overlapping it. Rays step through the cells in order, so a
+/// hit inside of a cell ends the search.
+///
+/// Suits scenes with objects of similar size spread evenly, like particle
+/// clouds.
+pub struct Grid<'a> {
+    /// cells per object
+    density: f64,
+    unbounded: Vec<(usize, &'a GObject)>,
+    /// bounded objects with their positions
+    objects: Vec<(usize, &'a GObject)>,
+    bounds: Aabb,
+    resolution: [usize; 3],
+    cell_size: Vector,
+    /// object indices of cell `(i, j, k)` at `i + nx * (j + ny * k)`
+    cells: Vec<Vec<usize>>,
+    /// last ray testing each bounded object, objects spanning several cells
+    /// are tested once per ray
+    mailboxes: Vec<Cell<u64>>,
+    rays: Cell<u64>,
+}
+
+impl<'a> Grid<'a> {
+    pub fn new() -> Grid<'a> {
+        Grid::with_density(3.0)
+    }
+
+    /// Grid with about `density` cells per object.
+    pub fn with_density(density: f64) -> Grid<'a> {
+        Grid {
+            density,
+            unbounded: Vec::new(),
+            objects: Vec::new(),
+            bounds: Aabb::new(Vector::from(0.0), Vector::from(0.0)),
+            resolution: [0; 3],
+            cell_size: Vector::from(0.0),
+            cells: Vec::new(),
+            mailboxes: Vec::new(),
+            rays: Cell::new(0),
+        }
+    }
+
+    fn cell(&self, p: f64, axis: usize) -> usize {
+        let i = (p - component(&self.bounds.min, axis)) / component(&self.cell_size, axis);
+        (i.max(0.0) as usize).min(self.resolution[axis] - 1)
+    }
+}
+
+impl<'a> Default for Grid<'a> {
+    fn default() -> Grid<'a> {
+        Grid::new()
+    }
+}
+
+impl<'a> Accelerator<'a> for Grid<'a> {
+    fn build(&mut self, objects: &[&'a GObject]) {
+        let (bounded, unbounded) = partition(objects);
+        self.unbounded = unbounded;
+        self.objects = bounded
+            .iter()
+            .map(|&(index, object, _)| (index, object))
+            .collect();
+        self.mailboxes = vec![Cell::new(0); bounded.len()];
+        self.cells.clear();
+        if bounded.is_empty() {
+            return;
+        }
+
+        let mut bounds = bounded[1..]
+            .iter()
+            .fold(bounded[0].2, |aabb, &(_, _, b)| aabb.union(&b));
+        // flat scenes still need cells of some thickness
+        let margin = 1e-9 * (1.0 + !(bounds.max - bounds.min));
+        bounds.min -= Vector::from(margin);
+        bounds.max += Vector::from(margin);
+        self.bounds = bounds;
+
+        let size = bounds.max - bounds.min;
+        let scale = (self.density * bounded.len() as f64 / (size.x * size.y * size.z)).cbrt();
+        for axis in 0..3 {
+            let cells = (component(&size, axis) * scale).round() as usize;
+            self.resolution[axis] = cells.clamp(1, MAX_RESOLUTION);
+        }
+        let [nx, ny, nz] = self.resolution;
+        self.cell_size = size / Vector::from((nx as f64, ny as f64, nz as f64));
+
+        self.cells = vec![Vec::new(); nx * ny * nz];
+        for (index, &(_, _, aabb)) in bounded.iter().enumerate() {
+            let lo: Vec<usize> = (0..3)
+                .map(|axis| self.cell(component(&aabb.min, axis), axis))
+                .collect();
+            let hi: Vec<usize> = (0..3)
+                .map(|axis| self.cell(component(&aabb.max, axis), axis))
+                .collect();
+            for k in lo[2]..hi[2] + 1 {
+                for j in lo[1]..hi[1] + 1 {
+                    for i in lo[0]..hi[0] + 1 {
+                        self.cells[i + nx * (j + ny * k)].push(index);
+                    }
+                }
+            }
+        }
+    }
+
+    fn intersect(&self, ray: &Ray, tests: &[Cell<u64>]) -> Option<(&'a GObject, Hit)> {
+        let mut ray = *ray;
+        let mut nearest = closest(&self.unbounded, &mut ray, tests);
+        if self.cells.is_empty() {
+            return nearest;
+        }
+        let (t0, t1) = match self.bounds.intersect(&ray) {
+            Some(span) => span,
+            None => return nearest,
+        };
+        let stamp = self.rays.get() + 1;
+        self.rays.set(stamp);
+
+        // 3D DDA: next boundary crossing and distance between crossings
+        // along each axis
+        let entry = ray.point(t0);
+        let mut cell = [0; 3];
+        let mut step = [0isize; 3];
+        let mut next = [f64::INFINITY; 3];
+        let mut delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            cell[axis] = self.cell(component(&entry, axis), axis);
+            let d = component(&ray.dir, axis);
+            let o = component(&ray.org, axis);
+            let min = component(&self.bounds.min, axis);
+            let size = component(&self.cell_size, axis);
+            if d > 0.0 {
+                step[axis] = 1;
+                next[axis] = (min + (cell[axis] + 1) as f64 * size - o) / d;
+                delta[axis] = size / d;
+            } else if d < 0.0 {
+                step[axis] = -1;
+                next[axis] = (min + cell[axis] as f64 * size - o) / d;
+                delta[axis] = -size / d;
+            }
+        }
+
+        let [nx, ny, _] = self.resolution;
+        loop {
+            let exit = next[0].min(next[1]).min(next[2]).min(t1);
+            for &index in &self.cells[cell[0] + nx * (cell[1] + ny * cell[2])] {
+                if self.mailboxes[index].replace(stamp) == stamp {
+                    continue;
+                }
+                let (position, object) = self.objects[index];
+                count_test(tests, position);
+                if let Some(hit) = object.intersect(&ray) {
+                    ray.t_max = hit.t;
+                    nearest = Some((object, hit));
+                }
+            }
+            if ray.t_max <= exit || exit >= t1 {
+                return nearest;
+            }
+
+            let axis = if next[0] < next[1] && next[0] < next[2] {
+                0
+            } else if next[1] < next[2] {
+                1
+            } else {
+                2
+            };
+            let moved = cell[axis] as isize + step[axis];
+            if moved < 0 || moved as usize >= self.resolution[axis] {
+                return nearest;
+            }
+            cell[axis] = moved as usize;
+            next[axis] += delta[axis];
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::super::geometry::Sphere;
+    use super::super::tests::{agrees_with_linear, cloud, cubes};
+
+    #[test]
+    fn grid_agrees_with_linear() {
+        let objects = cloud(300);
+        agrees_with_linear(&objects, Grid::new());
+        agrees_with_linear(&objects, Grid::with_density(0.2));
+        agrees_with_linear(&cubes(200), Grid::new());
+    }
+
+    #[test]
+    fn objects_are_tested_once_per_ray() {
+        let sphere = Sphere::new(Surface::new(), Vector::from(0.0), 9.0);
+        let objects: Vec<&GObject> = vec![&sphere];
+        let tests = vec![Cell::new(0)];
+        let mut grid = Grid::with_density(300.0);
+        grid.build(&objects);
+        // through the cells along the box edge, missing the sphere
+        let ray = Ray::from(((-20.0, 8.0, 8.0), (1.0, 0.0, 0.0)));
+        assert!(grid.intersect(&ray, &tests).is_none());
+        assert_eq!(tests[0].get(), 1);
+        assert!(grid.intersect(&ray, &tests).is_none());
+        assert_eq!(tests[0].get(), 2);
+    }
+}
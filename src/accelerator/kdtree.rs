@@ -0,0 +1,292 @@
+use super::*;
+
+/// Estimated cost of one object intersection relative to one traversal
+/// step.
+const INTERSECTION_COST: f64 = 80.0;
+const TRAVERSAL_COST: f64 = 1.0;
+/// Cost reduction for splits with one empty side.
+const EMPTY_BONUS: f64 = 0.5;
+/// Levels of interior nodes, bounding the traversal stack.
+const MAX_DEPTH: usize = 64;
+
+/// Kd-tree splitting space by axis aligned planes chosen with the surface
+/// area heuristic. Objects straddling a plane are listed on both sides.
+///
+/// Suits static scenes with objects of very different sizes, like
+/// architecture.
+pub struct KdTree<'a> {
+    unbounded: Vec<(usize, &'a GObject)>,
+    /// bounded objects with their positions
+    objects: Vec<(usize, &'a GObject)>,
+    bounds: Aabb,
+    nodes: Vec<Node>,
+    /// object indices of all leaves
+    indices: Vec<usize>,
+}
+
+enum Node {
+    Leaf {
+        first: usize,
+        count: usize,
+    },
+    /// the child below the split is the next node
+    Interior {
+        axis: usize,
+        split: f64,
+        above: usize,
+    },
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new() -> KdTree<'a> {
+        KdTree {
+            unbounded: Vec::new(),
+            objects: Vec::new(),
+            bounds: Aabb::new(Vector::from(0.0), Vector::from(0.0)),
+            nodes: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    fn leaf(&mut self, items: &[usize]) {
+        self.nodes.push(Node::Leaf {
+            first: self.indices.len(),
+            count: items.len(),
+        });
+        self.indices.extend_from_slice(items);
+    }
+
+    fn split(&mut self, boxes: &[Aabb], items: Vec<usize>, bounds: Aabb, depth: u32) {
+        if items.len() <= 1 || depth == 0 {
+            return self.leaf(&items);
+        }
+        let (cost, axis, split) = match best_split(boxes, &items, &bounds) {
+            Some(best) => best,
+            None => return self.leaf(&items),
+        };
+        if cost >= INTERSECTION_COST * items.len() as f64 {
+            return self.leaf(&items);
+        }
+
+        let (mut below, mut above) = (Vec::new(), Vec::new());
+        for &i in &items {
+            let (min, max) = (
+                component(&boxes[i].min, axis),
+                component(&boxes[i].max, axis),
+            );
+            if min < split || max <= split {
+                below.push(i);
+            }
+            if max > split {
+                above.push(i);
+            }
+        }
+        let (below_bounds, above_bounds) = split_box(&bounds, axis, split);
+
+        let node = self.nodes.len();
+        self.nodes.push(Node::Interior {
+            axis,
+            split,
+            above: 0,
+        });
+        self.split(boxes, below, below_bounds, depth - 1);
+        let above_node = self.nodes.len();
+        if let Node::Interior { ref mut above, .. } = self.nodes[node] {
+            *above = above_node;
+        }
+        self.split(boxes, above, above_bounds, depth - 1);
+    }
+}
+
+fn split_box(bounds: &Aabb, axis: usize, split: f64) -> (Aabb, Aabb) {
+    let (mut below, mut above) = (*bounds, *bounds);
+    match axis {
+        0 => {
+            below.max.x = split;
+            above.min.x = split;
+        }
+        1 => {
+            below.max.y = split;
+            above.min.y = split;
+        }
+        _ => {
+            below.max.z = split;
+            above.min.z = split;
+        }
+    }
+    (below, above)
+}
+
+/// Cheapest split plane by the surface area heuristic as cost, axis and
+/// position. Candidates are the box faces of the objects.
+fn best_split(boxes: &[Aabb], items: &[usize], bounds: &Aabb) -> Option<(f64, usize, f64)> {
+    let area = bounds.surface_area();
+    if area <= 0.0 {
+        return None;
+    }
+    let mut best: Option<(f64, usize, f64)> = None;
+    for axis in 0..3 {
+        let (lo, hi) = (component(&bounds.min, axis), component(&bounds.max, axis));
+        let mut mins: Vec<f64> = items
+            .iter()
+            .map(|&i| component(&boxes[i].min, axis))
+            .collect();
+        let mut maxs: Vec<f64> = items
+            .iter()
+            .map(|&i| component(&boxes[i].max, axis))
+            .collect();
+        mins.sort_by(|a, b| a.total_cmp(b));
+        maxs.sort_by(|a, b| a.total_cmp(b));
+
+        // sweep the candidates in order, counting objects starting before
+        // and ending after each of them
+        let (mut started, mut ended) = (0, 0);
+        let mut candidates: Vec<f64> = mins.iter().chain(&maxs).cloned().collect();
+        candidates.sort_by(|a, b| a.total_cmp(b));
+        candidates.dedup();
+        for &split in &candidates {
+            while started < mins.len() && mins[started] < split {
+                started += 1;
+            }
+            while ended < maxs.len() && maxs[ended] <= split {
+                ended += 1;
+            }
+            if split <= lo || split >= hi {
+                continue;
+            }
+            let (below, above) = split_box(bounds, axis, split);
+            let (n_below, n_above) = (started as f64, (items.len() - ended) as f64);
+            let bonus = if n_below == 0.0 || n_above == 0.0 {
+                EMPTY_BONUS
+            } else {
+                0.0
+            };
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (1.0 - bonus)
+                    * (below.surface_area() * n_below + above.surface_area() * n_above)
+                    / area;
+            if best.is_none_or(|b| cost < b.0) {
+                best = Some((cost, axis, split));
+            }
+        }
+    }
+    best
+}
+
+impl<'a> Default for KdTree<'a> {
+    fn default() -> KdTree<'a> {
+        KdTree::new()
+    }
+}
+
+impl<'a> Accelerator<'a> for KdTree<'a> {
+    fn build(&mut self, objects: &[&'a GObject]) {
+        let (bounded, unbounded) = partition(objects);
+        self.unbounded = unbounded;
+        self.objects = bounded
+            .iter()
+            .map(|&(index, object, _)| (index, object))
+            .collect();
+        self.nodes.clear();
+        self.indices.clear();
+        if bounded.is_empty() {
+            return;
+        }
+
+        let boxes: Vec<Aabb> = bounded.iter().map(|&(_, _, aabb)| aabb).collect();
+        self.bounds = boxes[1..].iter().fold(boxes[0], |a, b| a.union(b));
+        let depth = (8.0 + 1.3 * (boxes.len() as f64).log2()).round() as u32;
+        let depth = depth.min(MAX_DEPTH as u32);
+        let bounds = self.bounds;
+        self.split(&boxes, (0..boxes.len()).collect(), bounds, depth);
+    }
+
+    fn intersect(&self, ray: &Ray, tests: &[Cell<u64>]) -> Option<(&'a GObject, Hit)> {
+        let mut ray = *ray;
+        let mut nearest = closest(&self.unbounded, &mut ray, tests);
+        if self.nodes.is_empty() {
+            return nearest;
+        }
+        let (mut t_min, mut t_max) = match self.bounds.intersect(&ray) {
+            Some(span) => span,
+            None => return nearest,
+        };
+
+        // front to back, with the far children and their ray spans waiting,
+        // at most one per level
+        let mut stack = [(0, 0.0, 0.0); MAX_DEPTH];
+        let mut waiting = 0;
+        let mut node = 0;
+        loop {
+            // spans beyond the nearest hit are skipped, not ended on, as
+            // spans of rays in a split plane wait out of order
+            if ray.t_max >= t_min {
+                match self.nodes[node] {
+                    Node::Interior { axis, split, above } => {
+                        let o = component(&ray.org, axis);
+                        let d = component(&ray.dir, axis);
+                        let t_split = if d != 0.0 {
+                            (split - o) / d
+                        } else {
+                            f64::INFINITY
+                        };
+                        let (near, far) = if o < split || (o == split && d <= 0.0) {
+                            (node + 1, above)
+                        } else {
+                            (above, node + 1)
+                        };
+                        if o == split && d == 0.0 {
+                            // lying in the plane the ray touches both sides
+                            stack[waiting] = (far, t_min, t_max);
+                            waiting += 1;
+                            node = near;
+                        } else if t_split > t_max || t_split <= 0.0 {
+                            node = near;
+                        } else if t_split < t_min {
+                            node = far;
+                        } else {
+                            stack[waiting] = (far, t_split, t_max);
+                            waiting += 1;
+                            node = near;
+                            t_max = t_split;
+                        }
+                        continue;
+                    }
+                    Node::Leaf { first, count } => {
+                        for &index in &self.indices[first..first + count] {
+                            let (position, object) = self.objects[index];
+                            count_test(tests, position);
+                            if let Some(hit) = object.intersect(&ray) {
+                                ray.t_max = hit.t;
+                                nearest = Some((object, hit));
+                            }
+                        }
+                    }
+                }
+            }
+            if waiting == 0 {
+                return nearest;
+            }
+            waiting -= 1;
+            let (next, t0, t1) = stack[waiting];
+            node = next;
+            t_min = t0;
+            t_max = t1;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::tests::{agrees_with_linear, cloud, cubes};
+
+    #[test]
+    fn kdtree_agrees_with_linear() {
+        let objects = cloud(300);
+        agrees_with_linear(&objects, KdTree::new());
+        agrees_with_linear(&cubes(200), KdTree::new());
+    }
+}
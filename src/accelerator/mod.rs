@@ -0,0 +1,217 @@
+use super::*;
+
+use std::cell::Cell;
+
+pub mod grid;
+pub mod kdtree;
+
+pub use self::grid::Grid;
+pub use self::kdtree::KdTree;
+
+/// Spatial index answering closest hit queries over the objects of an
+/// `Environment`.
+///
+/// Objects without `bounds` cannot be placed in space, implementations test
+/// them against every ray.
+pub trait Accelerator<'a> {
+    /// Indexes `objects`, replacing anything indexed before.
+    fn build(&mut self, objects: &[&'a GObject]);
+
+    /// Closest object hit by `ray` within its interval. Every test of an
+    /// object counts at its position in the built objects in `tests`.
+    fn intersect(&self, ray: &Ray, tests: &[Cell<u64>]) -> Option<(&'a GObject, Hit)>;
+}
+
+/// Tests every object, the default of `Environment`.
+pub struct Linear<'a> {
+    objects: Vec<(usize, &'a GObject)>,
+}
+
+impl<'a> Linear<'a> {
+    pub fn new() -> Linear<'a> {
+        Linear {
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Default for Linear<'a> {
+    fn default() -> Linear<'a> {
+        Linear::new()
+    }
+}
+
+impl<'a> Accelerator<'a> for Linear<'a> {
+    fn build(&mut self, objects: &[&'a GObject]) {
+        self.objects = objects.iter().cloned().enumerate().collect();
+    }
+
+    fn intersect(&self, ray: &Ray, tests: &[Cell<u64>]) -> Option<(&'a GObject, Hit)> {
+        let mut ray = *ray;
+        closest(&self.objects, &mut ray, tests)
+    }
+}
+
+/// Closest hit among `objects` with their positions, shortening `ray` to
+/// it.
+fn closest<'a>(
+    objects: &[(usize, &'a GObject)],
+    ray: &mut Ray,
+    tests: &[Cell<u64>],
+) -> Option<(&'a GObject, Hit)> {
+    let mut closest = None;
+    for &(index, object) in objects {
+        count_test(tests, index);
+        if let Some(hit) = object.intersect(ray) {
+            ray.t_max = hit.t;
+            closest = Some((object, hit));
+        }
+    }
+    closest
+}
+
+fn count_test(tests: &[Cell<u64>], index: usize) {
+    let test = &tests[index];
+    test.set(test.get() + 1);
+}
+
+/// Objects with their positions and boxes.
+type Bounded<'a> = Vec<(usize, &'a GObject, Aabb)>;
+
+/// Objects with their positions and boxes, and the ones without boxes with
+/// their positions.
+fn partition<'a>(objects: &[&'a GObject]) -> (Bounded<'a>, Vec<(usize, &'a GObject)>) {
+    let mut bounded = Vec::new();
+    let mut unbounded = Vec::new();
+    for (index, &object) in objects.iter().enumerate() {
+        match object.bounds() {
+            Some(aabb) => bounded.push((index, object, aabb)),
+            None => unbounded.push((index, object)),
+        }
+    }
+    (bounded, unbounded)
+}
+
+fn component(v: &Vector, axis: usize) -> f64 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::geometry::{self, Mesh, Plane};
+    use super::super::random::rnd;
+
+    /// Cloud of small tetrahedra above a floor plane.
+    pub fn cloud(n: usize) -> Vec<::std::boxed::Box<GObject>> {
+        let mut objects: Vec<::std::boxed::Box<GObject>> = Vec::new();
+        for _ in 0..n {
+            let p = Vector::from((rnd(), rnd(), rnd())) * 20.0 - Vector::from(10.0);
+            let r = 0.1 + 0.4 * rnd();
+            let vertices = vec![
+                p + Vector::from((r, 0.0, 0.0)),
+                p + Vector::from((-r, r, 0.0)),
+                p + Vector::from((-r, -r, r)),
+                p + Vector::from((-r, -r, -r)),
+            ];
+            let triangles = vec![[0, 1, 2], [0, 2, 3], [0, 3, 1], [1, 3, 2]];
+            objects.push(::std::boxed::Box::new(Mesh::new(
+                Surface::new(),
+                vertices,
+                triangles,
+            )));
+        }
+        objects.push(::std::boxed::Box::new(Plane::new(
+            Surface::new(),
+            Vector::from((0.0, 1.0, 0.0)),
+            11.0,
+        )));
+        objects
+    }
+
+    /// Unit cubes on integer coordinates, their faces lie in the planes
+    /// axis aligned rays run along.
+    pub fn cubes(n: usize) -> Vec<::std::boxed::Box<GObject>> {
+        let mut objects: Vec<::std::boxed::Box<GObject>> = Vec::new();
+        for _ in 0..n {
+            let corner = Vector::from((rnd(), rnd(), rnd())) * 20.0 - Vector::from(10.0);
+            objects.push(::std::boxed::Box::new(geometry::Box::new(
+                Surface::new(),
+                Vector::from((corner.x.floor(), corner.y.floor(), corner.z.floor())),
+                Vector::from((1.0, 0.0, 0.0)),
+                Vector::from((0.0, 1.0, 0.0)),
+                Vector::from((0.0, 0.0, 1.0)),
+            )));
+        }
+        objects
+    }
+
+    /// Checks that `accelerator` finds the same hits as testing every
+    /// object.
+    pub fn agrees_with_linear<'a, A: Accelerator<'a>>(
+        objects: &'a [::std::boxed::Box<GObject>],
+        mut accelerator: A,
+    ) {
+        let objects: Vec<&GObject> = objects.iter().map(|o| &**o).collect();
+        let tests = vec![Cell::new(0); objects.len()];
+        let mut linear = Linear::new();
+        linear.build(&objects);
+        accelerator.build(&objects);
+        for i in 0..2000 {
+            let org = if i % 2 == 0 {
+                Vector::from((rnd(), rnd(), rnd())) * 40.0 - Vector::from(20.0)
+            } else {
+                Vector::from((rnd(), rnd(), rnd())) * 10.0 - Vector::from(5.0)
+            };
+            let dir = (Vector::from((rnd(), rnd(), rnd())) - Vector::from(0.5)).normalize();
+            let mut ray = Ray::new(org, dir);
+            if i % 3 == 0 {
+                ray.t_max = 15.0;
+            }
+            let expected = linear.intersect(&ray, &tests).map(|(_, hit)| hit.t);
+            let found = accelerator.intersect(&ray, &tests).map(|(_, hit)| hit.t);
+            assert_eq!(found, expected, "ray {} from {:?} along {:?}", i, org, dir);
+        }
+
+        // axis aligned rays run along cell and split boundaries
+        for a in -10..11 {
+            for b in -10..11 {
+                let (a, b) = (a as f64, b as f64);
+                for &(org, dir) in &[
+                    ((-20.0, a, b), (1.0, 0.0, 0.0)),
+                    ((a, 20.0, b), (0.0, -1.0, 0.0)),
+                    ((a, b, -20.0), (0.0, 0.0, 1.0)),
+                ] {
+                    let ray = Ray::from((org, dir));
+                    let expected = linear.intersect(&ray, &tests).map(|(_, hit)| hit.t);
+                    let found = accelerator.intersect(&ray, &tests).map(|(_, hit)| hit.t);
+                    assert_eq!(found, expected, "ray from {:?} along {:?}", org, dir);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_unbounded() {
+        let objects = cloud(0);
+        for accelerator in vec![
+            ::std::boxed::Box::new(Grid::new()) as ::std::boxed::Box<Accelerator>,
+            ::std::boxed::Box::new(KdTree::new()),
+        ] {
+            let mut accelerator = accelerator;
+            let tests = [Cell::new(0)];
+            let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, -1.0, 0.0)));
+            accelerator.build(&[]);
+            assert!(accelerator.intersect(&ray, &[]).is_none());
+            accelerator.build(&[&*objects[0]]);
+            let (_, hit) = accelerator.intersect(&ray, &tests).unwrap();
+            assert_eq!(hit.t, 11.0);
+            assert_eq!(tests[0].get(), 1);
+        }
+    }
+}
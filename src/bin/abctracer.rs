@@ -0,0 +1,230 @@
+extern crate abctracer;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use abctracer::accelerator::{Grid, KdTree, Linear};
+use abctracer::integrator::Whitted;
+use abctracer::loader::{load_gltf, load_scene, read_pov};
+use abctracer::render::backend::htmlcanvas::HtmlCanvasBackend;
+use abctracer::render::backend::NullRenderBackend;
+use abctracer::render::RenderBackend;
+use abctracer::{
+    render_scene, render_scene_progressive, render_scene_supersampling_grid,
+    render_scene_supersampling_grid_adaptive, Accelerator, ProgressiveTarget, RenderOptions,
+    RenderStats, Scene,
+};
+
+const USAGE: &str = "usage: abctracer [options] SCENE
+
+Renders a native JSON (.json), POV-Ray (.pov) or glTF (.gltf, .glb) scene
+and prints render statistics.
+
+options:
+    -o FILE             write the image as an HTML canvas page
+    -w WIDTH            image width, overrides the scene settings
+    -h HEIGHT           image height, overrides the scene settings
+    -s SAMPLES          supersample SAMPLES x SAMPLES rays per pixel
+    -a VARIANCE         supersample adaptively until the color variance
+                        falls below VARIANCE
+    -t SECONDS          refine the image progressively for SECONDS
+    -n NOISE            refine the image progressively until the pixel noise
+                        falls below NOISE, -s then limits the samples per
+                        pixel
+    --accelerator NAME  linear, grid or kdtree (default)";
+
+struct Options {
+    scene: String,
+    output: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    samples: u32,
+    variance: Option<f64>,
+    time: Option<f64>,
+    noise: Option<f64>,
+    accelerator: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        scene: String::new(),
+        output: None,
+        width: None,
+        height: None,
+        samples: 1,
+        variance: None,
+        time: None,
+        noise: None,
+        accelerator: "kdtree".to_string(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "-o" => options.output = Some(value()?),
+            "-w" => options.width = Some(count(&arg, &value()?)?),
+            "-h" => options.height = Some(count(&arg, &value()?)?),
+            "-s" => options.samples = count(&arg, &value()?)?,
+            "-a" => options.variance = Some(number(&value()?)?),
+            "-t" => options.time = Some(number(&value()?)?),
+            "-n" => options.noise = Some(number(&value()?)?),
+            "--accelerator" => options.accelerator = value()?,
+            "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => options.scene = arg.clone(),
+        }
+    }
+    if options.scene.is_empty() {
+        return Err("no scene given".to_string());
+    }
+    Ok(options)
+}
+
+fn number<T: ::std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("bad number {}", text))
+}
+
+/// Pixels or samples given with `option`, at least one.
+fn count(option: &str, text: &str) -> Result<u32, String> {
+    match number(text)? {
+        0 => Err(format!("{} needs at least 1", option)),
+        n => Ok(n),
+    }
+}
+
+fn load(path: &str) -> io::Result<Scene> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match extension.as_str() {
+        "pov" => {
+            let mut text = String::new();
+            File::open(path)?.read_to_string(&mut text)?;
+            let (scene, warnings) = read_pov(&text)?;
+            for warning in warnings {
+                eprintln!("{}: {}", path, warning);
+            }
+            Ok(scene)
+        }
+        "gltf" | "glb" => load_gltf(path),
+        _ => load_scene(path),
+    }
+}
+
+fn render(options: &Options, scene: &Scene) -> io::Result<RenderStats> {
+    let mut environment = scene.environment();
+    let accelerator: Box<Accelerator> = match options.accelerator.as_str() {
+        "linear" => Box::new(Linear::new()),
+        "grid" => Box::new(Grid::new()),
+        "kdtree" => Box::new(KdTree::new()),
+        name => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown accelerator {}", name),
+            ))
+        }
+    };
+    environment.set_accelerator(accelerator);
+
+    let width = options.width.unwrap_or(scene.settings.width);
+    let height = options.height.unwrap_or(scene.settings.height);
+    let mut backend: Box<RenderBackend> = match options.output {
+        Some(ref output) => Box::new(HtmlCanvasBackend::new(output)),
+        None => Box::new(NullRenderBackend::new()),
+    };
+    let mut render_options = RenderOptions::new();
+    render_options.set_progress(|progress| {
+        let left = progress.remaining().map_or(0, |left| left.as_secs());
+        eprint!(
+            "\rpass {} {:5.1}% {:4} s left",
+            progress.pass,
+            100.0 * progress.fraction(),
+            left
+        );
+        if progress.rows_done == progress.rows {
+            eprintln!();
+        }
+    });
+    let integrator = Whitted::new();
+    let n = options.samples;
+    if options.time.is_some() || options.noise.is_some() {
+        let mut target = ProgressiveTarget::new();
+        target.time = options
+            .time
+            .map(|seconds| Duration::from_millis((seconds * 1e3) as u64));
+        target.noise = options.noise;
+        if n > 1 {
+            target.samples = Some(n);
+        }
+        return render_scene_progressive(
+            &environment,
+            &integrator,
+            width,
+            height,
+            &target,
+            &render_options,
+            &mut *backend,
+        );
+    }
+    match options.variance {
+        Some(variance) => render_scene_supersampling_grid_adaptive(
+            &environment,
+            &integrator,
+            width,
+            height,
+            n,
+            n,
+            variance,
+            &render_options,
+            &mut *backend,
+        ),
+        None if n > 1 => render_scene_supersampling_grid(
+            &environment,
+            &integrator,
+            width,
+            height,
+            n,
+            n,
+            &render_options,
+            &mut *backend,
+        ),
+        None => render_scene(
+            &environment,
+            &integrator,
+            width,
+            height,
+            &render_options,
+            &mut *backend,
+        ),
+    }
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("abctracer: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let result = load(&options.scene).and_then(|scene| render(&options, &scene));
+    match result {
+        Ok(stats) => println!("{}", stats),
+        Err(error) => {
+            eprintln!("abctracer: {}: {}", options.scene, error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::*;
+
+/// 360° panorama: `x` maps to longitude from -180° to 180° around the up
+/// direction, `y` to latitude from -90° to 90°. The image center looks along
+/// `dir`.
+pub struct Equirectangular {
+    eye: Vector,
+    eye_dir: Vector,
+    v_x: Vector,
+    v_y: Vector,
+}
+
+impl Equirectangular {
+    pub fn new(eye: Vector, dir: Vector, up_dir: Vector) -> Equirectangular {
+        let (eye_dir, v_x, v_y) = basis(&dir, &up_dir);
+        Equirectangular {
+            eye,
+            eye_dir,
+            v_x,
+            v_y,
+        }
+    }
+}
+
+impl Camera for Equirectangular {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        let (sin_lon, cos_lon) = (x * PI).sin_cos();
+        let (sin_lat, cos_lat) = (y * 0.5 * PI).sin_cos();
+        Ray::new(
+            self.eye,
+            (self.eye_dir * cos_lon + self.v_x * sin_lon) * cos_lat + self.v_y * sin_lat,
+        )
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("equirectangular")),
+            ("eye", Json::from(self.eye)),
+            ("dir", Json::from(self.eye_dir)),
+            ("up", Json::from(self.v_y)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn covers_full_sphere() {
+        let camera = Equirectangular::new(
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+        );
+        assert!(!(camera.ray(0.0, 0.0).dir - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+        assert!(!(camera.ray(0.5, 0.0).dir - Vector::from((1.0, 0.0, 0.0))) < 1e-9);
+        assert!(!(camera.ray(1.0, 0.0).dir - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+        assert!(!(camera.ray(0.0, 1.0).dir - Vector::from((0.0, 1.0, 0.0))) < 1e-9);
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+/// Equidistant fisheye: the angle from the viewing direction grows linearly
+/// with the distance from the image center, reaching `fov / 2` at the image
+/// edges.
+///
+/// Image corners lie outside of the fisheye circle and look further than
+/// `fov / 2`.
+pub struct Fisheye {
+    eye: Vector,
+    eye_dir: Vector,
+    v_x: Vector,
+    v_y: Vector,
+    half_fov: f64,
+}
+
+impl Fisheye {
+    pub fn new(eye: Vector, dir: Vector, up_dir: Vector, fov: f64) -> Fisheye {
+        let (eye_dir, v_x, v_y) = basis(&dir, &up_dir);
+        Fisheye {
+            eye,
+            eye_dir,
+            v_x,
+            v_y,
+            half_fov: 0.5 * fov,
+        }
+    }
+}
+
+impl Camera for Fisheye {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        let r = (x * x + y * y).sqrt();
+        let theta = r * self.half_fov;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
+        Ray::new(
+            self.eye,
+            self.eye_dir * cos_theta + (self.v_x * cos_phi + self.v_y * sin_phi) * sin_theta,
+        )
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("fisheye")),
+            ("eye", Json::from(self.eye)),
+            ("dir", Json::from(self.eye_dir)),
+            ("up", Json::from(self.v_y)),
+            ("fov", Json::from(2.0 * self.half_fov)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_looks_sideways_with_180_degrees() {
+        let camera = Fisheye::new(
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            PI,
+        );
+        assert!(!(camera.ray(0.0, 0.0).dir - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+        assert!(!(camera.ray(1.0, 0.0).dir - Vector::from((1.0, 0.0, 0.0))) < 1e-9);
+        assert!(!(camera.ray(0.0, -1.0).dir - Vector::from((0.0, -1.0, 0.0))) < 1e-9);
+    }
+}
@@ -0,0 +1,82 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::loader::Json;
+use super::random::rnd;
+
+pub mod perspective;
+pub mod orthographic;
+pub mod fisheye;
+pub mod equirectangular;
+pub mod shutter;
+
+pub use self::perspective::Perspective;
+pub use self::orthographic::Orthographic;
+pub use self::fisheye::Fisheye;
+pub use self::equirectangular::Equirectangular;
+pub use self::shutter::Shutter;
+
+/// Generates primary rays.
+pub trait Camera {
+    /// Ray through image point `(x, y)`. Both coordinates run from `-1.0` to
+    /// `1.0`, left to right and bottom to top.
+    fn ray(&self, x: f64, y: f64) -> Ray;
+
+    /// Description in the native scene format, `None` if the camera cannot
+    /// be written.
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
+}
+
+impl<C: Camera + ?Sized> Camera for &C {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        (**self).ray(x, y)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        (**self).to_json()
+    }
+}
+
+impl<C: Camera + ?Sized> Camera for Box<C> {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        (**self).ray(x, y)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        (**self).to_json()
+    }
+}
+
+/// Orthonormal camera basis: viewing direction, right and up vectors.
+fn basis(&dir: &Vector, &up_dir: &Vector) -> (Vector, Vector, Vector) {
+    let v_x = (up_dir ^ dir).normalize(); // build orthogonal basis of image plane
+    let v_y = (dir ^ v_x).normalize(); // eye_dir orthogonal to this basic (image plane)
+    (dir.normalize(), v_x, v_y)
+}
+
+/// Uniform random point on the unit lens: disc or, with `blades` of 3 or
+/// more, regular polygon.
+fn sample_lens(blades: u32) -> (f64, f64) {
+    if blades < 3 {
+        let r = rnd().sqrt();
+        let phi = 2.0 * PI * rnd();
+        return (r * phi.cos(), r * phi.sin());
+    }
+
+    // pick one of the triangles fanning out of the lens center, then a
+    // uniform point inside it
+    let sector = 2.0 * PI / blades as f64;
+    let phi = sector * (rnd() * blades as f64).floor();
+    let (mut a, mut b) = (rnd(), rnd());
+    if a + b > 1.0 {
+        a = 1.0 - a;
+        b = 1.0 - b;
+    }
+    (
+        a * phi.cos() + b * (phi + sector).cos(),
+        a * phi.sin() + b * (phi + sector).sin(),
+    )
+}
@@ -0,0 +1,58 @@
+use super::*;
+
+/// Parallel projection: all rays share the viewing direction and start on a
+/// `width` by `height` rectangle centered at `eye`.
+pub struct Orthographic {
+    eye: Vector,
+    eye_dir: Vector,
+    v_x: Vector,
+    v_y: Vector,
+}
+
+impl Orthographic {
+    pub fn new(eye: Vector, dir: Vector, up_dir: Vector, width: f64, height: f64) -> Orthographic {
+        let (eye_dir, v_x, v_y) = basis(&dir, &up_dir);
+        Orthographic {
+            eye,
+            eye_dir,
+            v_x: v_x * (0.5 * width),
+            v_y: v_y * (0.5 * height),
+        }
+    }
+}
+
+impl Camera for Orthographic {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        Ray::new(self.eye + self.v_x * x + self.v_y * y, self.eye_dir)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("orthographic")),
+            ("eye", Json::from(self.eye)),
+            ("dir", Json::from(self.eye_dir)),
+            ("up", Json::from(self.v_y)),
+            ("width", Json::from(2.0 * !self.v_x)),
+            ("height", Json::from(2.0 * !self.v_y)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_are_parallel() {
+        let camera = Orthographic::new(
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 2.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            4.0,
+            2.0,
+        );
+        let ray = camera.ray(1.0, -1.0);
+        assert_eq!(ray.org, Vector::from((2.0, -1.0, 0.0)));
+        assert_eq!(ray.dir, Vector::from((0.0, 0.0, 1.0)));
+    }
+}
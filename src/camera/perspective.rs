@@ -0,0 +1,119 @@
+use super::*;
+
+/// Pinhole or thin lens perspective camera.
+pub struct Perspective {
+    eye: Vector,
+    eye_dir: Vector,
+    v_x: Vector,
+    v_y: Vector,
+    half_width: f64,
+    half_height: f64,
+    aperture: f64,
+    focal_distance: f64,
+    blades: u32,
+}
+
+impl Perspective {
+    /// Camera at `eye` looking along `dir`, with horizontal field of view
+    /// `fov` in radians and image `aspect` ratio (width / height).
+    pub fn new(eye: Vector, dir: Vector, up_dir: Vector, fov: f64, aspect: f64) -> Perspective {
+        let (eye_dir, v_x, v_y) = basis(&dir, &up_dir);
+        let half_width = (0.5 * fov).tan();
+        Perspective {
+            eye,
+            eye_dir,
+            v_x,
+            v_y,
+            half_width,
+            half_height: half_width / aspect,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            blades: 0,
+        }
+    }
+
+    /// Turns the pinhole into a thin lens with radius `aperture`, focused at
+    /// `focal_distance` along the viewing direction.
+    ///
+    /// With `blades` of 3 or more the lens is a regular polygon, which gives
+    /// polygonal bokeh; otherwise it is a disc. Zero aperture is a pinhole.
+    pub fn set_lens(&mut self, aperture: f64, focal_distance: f64, blades: u32) {
+        self.aperture = aperture;
+        self.focal_distance = focal_distance;
+        self.blades = blades;
+    }
+}
+
+impl Camera for Perspective {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        let dir =
+            self.eye_dir + self.v_x * (x * self.half_width) + self.v_y * (y * self.half_height);
+
+        if self.aperture <= 0.0 {
+            return Ray::new(self.eye, dir.normalize());
+        }
+
+        // point on the plane of focus seen through (x, y); eye_dir is unit
+        // and (dir, eye_dir) = 1
+        let focus = self.eye + dir * self.focal_distance;
+
+        let (u, v) = sample_lens(self.blades);
+        let org = self.eye + self.v_x * (u * self.aperture) + self.v_y * (v * self.aperture);
+        Ray::new(org, (focus - org).normalize())
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("perspective")),
+            ("eye", Json::from(self.eye)),
+            ("dir", Json::from(self.eye_dir)),
+            ("up", Json::from(self.v_y)),
+            ("fov", Json::from(2.0 * self.half_width.atan())),
+            ("aspect", Json::from(self.half_width / self.half_height)),
+            ("aperture", Json::from(self.aperture)),
+            ("focal_distance", Json::from(self.focal_distance)),
+            ("blades", Json::from(self.blades as f64)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::f64::consts::FRAC_PI_2;
+
+    fn camera() -> Perspective {
+        Perspective::new(
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            FRAC_PI_2,
+            2.0,
+        )
+    }
+
+    #[test]
+    fn field_of_view_and_aspect() {
+        let camera = camera();
+        let ray = camera.ray(1.0, 1.0);
+        assert!(!(ray.dir - Vector::from((1.0, 0.5, 1.0)).normalize()) < 1e-9);
+    }
+
+    #[test]
+    fn thin_lens_rays_converge_at_focal_distance() {
+        let mut camera = camera();
+        camera.set_lens(0.5, 4.0, 6);
+
+        for &(x, y) in &[(0.0, 0.0), (0.3, -0.2), (-0.7, 0.9)] {
+            let focus = Vector::from((x, y * 0.5, 1.0)) * 4.0;
+            for _ in 0..10 {
+                let ray = camera.ray(x, y);
+                assert!(!ray.org < 0.5 + 1e-9);
+                assert!(ray.org.z == 0.0);
+                let p = ray.point((focus.z - ray.org.z) / ray.dir.z);
+                assert!(!(p - focus) < 1e-9);
+            }
+        }
+    }
+}
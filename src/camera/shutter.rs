@@ -0,0 +1,71 @@
+use super::*;
+
+/// Opens the shutter of another camera for the interval `[open, close]`.
+///
+/// Every ray gets a random time within the interval, so supersampling
+/// renderers integrate over time and moving objects blur. The camera itself
+/// may move with constant velocity.
+pub struct Shutter<C: Camera> {
+    camera: C,
+    open: f64,
+    close: f64,
+    velocity: Vector,
+}
+
+impl<C: Camera> Shutter<C> {
+    pub fn new(camera: C, open: f64, close: f64) -> Shutter<C> {
+        Shutter {
+            camera,
+            open,
+            close,
+            velocity: Vector::from(0.0),
+        }
+    }
+
+    /// At time `t` the camera is translated by `velocity * t`.
+    pub fn set_motion(&mut self, velocity: Vector) {
+        self.velocity = velocity;
+    }
+}
+
+impl<C: Camera> Camera for Shutter<C> {
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        let mut ray = self.camera.ray(x, y);
+        ray.time = self.open + (self.close - self.open) * rnd();
+        ray.org += self.velocity * ray.time;
+        ray
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("shutter")),
+            ("camera", self.camera.to_json()?),
+            ("open", Json::from(self.open)),
+            ("close", Json::from(self.close)),
+            ("velocity", Json::from(self.velocity)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_sample_shutter_interval() {
+        let camera = Orthographic::new(
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            2.0,
+            2.0,
+        );
+        let mut shutter = Shutter::new(camera, 0.25, 0.5);
+        shutter.set_motion(Vector::from((1.0, 0.0, 0.0)));
+        for _ in 0..100 {
+            let ray = shutter.ray(0.0, 0.0);
+            assert!(ray.time >= 0.25 && ray.time <= 0.5);
+            assert_eq!(ray.org.x, ray.time);
+        }
+    }
+}
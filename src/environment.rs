@@ -1,49 +1,60 @@
 use super::*;
 
-use super::mediums::AIR;
+use std::cell::{Cell, RefCell, RefMut};
+use std::mem;
+use std::f64::consts::FRAC_PI_2;
 
-const BACKGROUND: Vector = Vector {
+use super::accelerator::{Accelerator, Linear};
+use super::camera::{Camera, Perspective};
+use super::stats::RenderStats;
+
+pub const BACKGROUND: Vector = Vector {
     x: 0.0,
     y: 0.05,
     z: 0.05,
 };
 
-const AMBIENT: Vector = Vector {
-    x: 1.0,
-    y: 1.0,
-    z: 1.0,
-};
+/// Default recursion depth and contribution below which rays are not
+/// traced further.
+pub const MAX_LEVEL: u32 = 10;
+pub const THRESHOLD: f64 = 0.01;
 
 pub struct Environment<'a> {
     lights: Vec<&'a LightSource>,
     solids: Vec<&'a GObject>,
-
-    eye: Vector,
-    eye_dir: Vector,
-    v_x: Vector,
-    v_y: Vector,
+    /// index over `solids`, rebuilt on the first query after they change
+    accelerator: RefCell<Box<Accelerator<'a> + 'a>>,
+    built: Cell<bool>,
+    stats: RefCell<RenderStats>,
+    /// intersection tests of the current render by position in `solids`,
+    /// added to `stats` by `take_stats`
+    tests: Vec<Cell<u64>>,
+
+    camera: Box<Camera + 'a>,
     background: Color,
     max_level: u32,
     threshold: f64,
 }
 
-struct TraceState {
-    level: u32,
-    total_rays: u32,
-}
-
 impl<'a> Environment<'a> {
     pub fn new() -> Environment<'a> {
         Environment {
             lights: Vec::new(),
             solids: Vec::new(),
-            eye: Vector::from(0.0),
-            eye_dir: Vector::from((0.0, 0.0, 1.0)),
-            v_x: Vector::from((1.0, 0.0, 0.0)),
-            v_y: Vector::from((0.0, 1.0, 0.0)),
+            accelerator: RefCell::new(Box::new(Linear::new())),
+            built: Cell::new(false),
+            stats: RefCell::new(RenderStats::new()),
+            tests: Vec::new(),
+            camera: Box::new(Perspective::new(
+                Vector::from(0.0),
+                Vector::from((0.0, 0.0, 1.0)),
+                Vector::from((0.0, 1.0, 0.0)),
+                FRAC_PI_2,
+                1.0,
+            )),
             background: BACKGROUND,
-            max_level: 10,
-            threshold: 0.01,
+            max_level: MAX_LEVEL,
+            threshold: THRESHOLD,
         }
     }
 
@@ -51,174 +62,86 @@ impl<'a> Environment<'a> {
         self.threshold
     }
 
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    pub fn max_level(&self) -> u32 {
+        self.max_level
+    }
+
+    pub fn set_max_level(&mut self, max_level: u32) {
+        self.max_level = max_level;
+    }
+
+    pub fn set_background(&mut self, background: Color) {
+        self.background = background;
+    }
+
+    pub fn lights(&self) -> &[&'a LightSource] {
+        &self.lights
+    }
+
     pub fn add_solid(&mut self, solid: &'a GObject) {
         self.solids.push(solid);
+        self.tests.push(Cell::new(0));
+        self.built.set(false);
+    }
+
+    /// Replaces the spatial index used by `intersect`, testing every object
+    /// by default.
+    pub fn set_accelerator(&mut self, accelerator: Box<Accelerator<'a> + 'a>) {
+        self.accelerator = RefCell::new(accelerator);
+        self.built.set(false);
     }
 
     pub fn add_light(&mut self, light: &'a LightSource) {
         self.lights.push(light);
     }
 
-    pub fn intersect(&self, ray: &Ray, distance: &mut f64) -> Option<&GObject> {
-        let mut closest_object = None;
-        let mut closest_distance = INFINITY;
-        for &solid in &self.solids {
-            if solid.intersect(ray, distance) {
-                if *distance < closest_distance {
-                    closest_distance = *distance;
-                    closest_object = Some(solid);
-                }
+    /// Closest object hit by `ray` within its interval.
+    pub fn intersect(&self, ray: &Ray) -> Option<(&'a GObject, Hit)> {
+        if !self.built.get() {
+            self.accelerator.borrow_mut().build(&self.solids);
+            self.built.set(true);
+        }
+        self.accelerator.borrow().intersect(ray, &self.tests)
+    }
+
+    /// Counters of the current render, without the intersection tests.
+    pub fn stats(&self) -> RefMut<'_, RenderStats> {
+        self.stats.borrow_mut()
+    }
+
+    /// Counters collected so far, starting over from zero.
+    pub fn take_stats(&self) -> RenderStats {
+        let mut stats = mem::take(&mut *self.stats.borrow_mut());
+        for (&solid, tests) in self.solids.iter().zip(&self.tests) {
+            if tests.get() > 0 {
+                stats.add_tests(solid, tests.replace(0));
             }
         }
-        *distance = closest_distance;
-        closest_object
+        stats
     }
 
-    fn shade_background(&self, ray: &Ray) -> Color {
+    pub fn shade_background(&self, ray: &Ray) -> Color {
         self.background
     }
 
+    /// Places a perspective camera with 90° field of view and square image
+    /// at `org`, looking along `dir`.
     pub fn set_camera(&mut self, &org: &Vector, &dir: &Vector, &up_dir: &Vector) {
-        self.eye = org; // eye point
-        self.eye_dir = dir; // viewing direction
-        self.v_x = (up_dir ^ dir).normalize(); // build orthogonal basis of image plane
-        self.v_y = (dir ^ self.v_x).normalize(); // eye_dir orthogonal to this basic (image plane)
+        self.camera = Box::new(Perspective::new(org, dir, up_dir, FRAC_PI_2, 1.0));
     }
 
-    pub fn camera(&self, x: f64, y: f64) -> Ray {
-        Ray {
-            org: self.eye,
-            dir: (self.eye_dir + self.v_x * x + self.v_y * y).normalize(),
-        }
+    pub fn set_camera_model(&mut self, camera: Box<Camera + 'a>) {
+        self.camera = camera;
     }
 
-    pub fn trace(&self, current_medium: &Medium, weight: f64, ray: &mut Ray) -> Color {
-        self.trace_state(
-            &mut TraceState {
-                level: 0,
-                total_rays: 0,
-            },
-            current_medium,
-            weight,
-            ray,
-        )
-    }
-
-    fn trace_state(
-        &self,
-        trace_state: &mut TraceState,
-        current_medium: &Medium,
-        weight: f64,
-        ray: &mut Ray,
-    ) -> Color {
-        let mut t = INFINITY;
-        let color: Color;
-
-        trace_state.level += 1;
-        trace_state.total_rays += 1;
-
-        if let Some(solid) = self.intersect(ray, &mut t) {
-            color = self.shade(
-                trace_state,
-                current_medium,
-                weight,
-                ray.point(t),
-                ray.dir,
-                solid,
-            )
-        } else {
-            color = self.shade_background(ray);
-        }
-        trace_state.level -= 1;
-        color
-    }
-
-    fn shade(
-        &self,
-        trace_state: &mut TraceState,
-        current_medium: &Medium,
-        weight: f64,
-        p: Vector,
-        view: Vector,
-        solid: &GObject,
-    ) -> Color {
-        let mut entering = true; // flag whether we're entering or leaving object
-
-        let mut texture = solid.find_texture(&p);
-
-        let mut vn = view & texture.n; // force (-view, n) > 0
-        if vn > 0.0 {
-            texture.n = -texture.n;
-            vn = -vn;
-            entering = false;
-        }
-
-        let mut ray = Ray::new(p, Vector::from(0.0)); // since all rays will be cast from here
-
-        let mut color = AMBIENT * texture.color * texture.k_r; // get ambient light
-
-        for light in &self.lights {
-            let mut l = Vector::from(0.0); // light vector
-            let shadow = light.shadow(&p, &mut l, &self); // light shadow coeff.
-            if shadow > self.threshold {
-                let ln = l & texture.n;
-                // if light is visible
-                if ln > self.threshold {
-                    // compute direct diffuse light
-                    if texture.k_d > self.threshold {
-                        color += *light.color() * texture.color * (texture.k_d * shadow * ln);
-                    }
-
-                    // compute direct specular light, via Phong shading
-                    if texture.k_s > self.threshold {
-                        // compute half-vector between -view and light vector
-                        let h = (l - view).normalize();
-                        color += *light.color()
-                            * (texture.k_s * shadow * (texture.n & h).powi(texture.p));
-                    }
-                }
-            }
-        }
-
-        if trace_state.level >= self.max_level {
-            return color;
-        }
-
-        // check for reflected ray
-        let r_weight = weight * texture.k_r; // weight of reflected ray
-        if r_weight > self.threshold {
-            // get reflected ray direction
-            ray.dir = view - texture.n * (2.0 * vn);
-            color +=
-                texture.k_r * self.trace_state(trace_state, current_medium, r_weight, &mut ray);
-        }
-
-        // check for transmitted ray
-        let t_weight = weight * texture.k_t; // weight of transmitted ray
-        if t_weight > self.threshold {
-            // relative index of refraction
-            let eta = current_medium.n_refr / if entering {
-                texture.medium.n_refr
-            } else {
-                AIR.n_refr
-            };
-            let ci = -vn; // cosine of incedent angle
-            let ct_square = 1.0 + eta * eta * (ci * ci - 1.0); // square cosine of transm. angle
-
-            // not a Total Internal Reflection
-            if ct_square > self.threshold {
-                ray.dir = view * eta + texture.n * (eta * ci - ct_square.sqrt());
-                let medium = &if entering {
-                    // ray enters object (texture.medium)
-                    texture.medium
-                } else {
-                    // ray leaves object (AIR)
-                    AIR
-                };
-                color += texture.k_r * self.trace_state(trace_state, medium, t_weight, &mut ray);
-            }
-        }
-        color
+    /// Primary ray through image point `(x, y)`, see `Camera::ray`.
+    pub fn camera(&self, x: f64, y: f64) -> Ray {
+        self.stats().primary_rays += 1;
+        self.camera.ray(x, y)
     }
 
     pub fn random_vector(&self) -> Vector {
@@ -3,7 +3,6 @@ use super::*;
 pub struct Box {
     material: Surface,
     loc: Vector,
-    center: Vector,
     n: [Vector; 3],
     d1: [f64; 3],
     d2: [f64; 3],
@@ -14,8 +13,7 @@ pub struct Box {
 
 impl Box {
     pub fn new(material: Surface, loc: Vector, e1: Vector, e2: Vector, e3: Vector) -> Box {
-        let center = loc + (e1 + e2 + e3) * 0.5;
-        Box::init_normals(&material, &loc, &e1, &e2, &e3, &center)
+        Box::init_normals(&material, &loc, &e1, &e2, &e3)
     }
 
     fn init_normals(
@@ -24,7 +22,6 @@ impl Box {
         &e1: &Vector,
         &e2: &Vector,
         &e3: &Vector,
-        &center: &Vector,
     ) -> Box {
         let mut n = [
             (e1 ^ e2).normalize(),
@@ -47,7 +44,6 @@ impl Box {
         Box {
             material,
             loc,
-            center,
             n,
             d1,
             d2,
@@ -63,21 +59,24 @@ impl GObject for Box {
         &self.material
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
         let mut t_near = -INFINITY;
         let mut t_far = INFINITY;
+        // outward normals of the faces where the ray enters and leaves
+        let mut n_near = Vector::from(0.0);
+        let mut n_far = Vector::from(0.0);
 
         for i in 0..3 {
             let vd = ray.dir & self.n[i];
             let vo = ray.org & self.n[i];
 
-            let (t1, t2) = if vd > EPS {
-                (-(vo + self.d2[i]) / vd, -(vo + self.d1[i]) / vd)
+            let (t1, t2, n) = if vd > EPS {
+                (-(vo + self.d2[i]) / vd, -(vo + self.d1[i]) / vd, self.n[i])
             } else if vd < -EPS {
-                (-(vo + self.d1[i]) / vd, -(vo + self.d2[i]) / vd)
+                (-(vo + self.d1[i]) / vd, -(vo + self.d2[i]) / vd, -self.n[i])
             } else {
-                if vo < self.d1[i] || vo > self.d2[i] {
-                    return false;
+                if vo < -self.d2[i] || vo > -self.d1[i] {
+                    return None;
                 } else {
                     continue;
                 }
@@ -85,42 +84,117 @@ impl GObject for Box {
 
             if t1 > t_near {
                 t_near = t1;
+                n_near = -n;
             }
 
             if t2 < t_far {
-                t_far = if t2 < GEOMETRY_THRESHOLD {
-                    return false;
-                } else {
-                    t2
-                };
+                t_far = t2;
+                n_far = n;
             }
 
-            if t_near > t_far {
-                return false;
+            if t_near > t_far || t_far < ray.t_min {
+                return None;
             }
         }
 
-        *t = t_near;
+        let (t, n) = if ray.contains(t_near) {
+            (t_near, n_near)
+        } else if ray.contains(t_far) {
+            (t_far, n_far)
+        } else {
+            return None;
+        };
+
+        // box coordinates of the hit point, each in [0, 1]
+        let q = ray.point(t) - self.loc;
+        let a = (q & self.n[2]) / (self.e1 & self.n[2]);
+        let b = (q & self.n[1]) / (self.e2 & self.n[1]);
+        let c = (q & self.n[0]) / (self.e3 & self.n[0]);
+
+        let (u, v) = if (n & self.n[0]).abs() > 0.5 {
+            (a, b)
+        } else if (n & self.n[1]).abs() > 0.5 {
+            (a, c)
+        } else {
+            (b, c)
+        };
 
-        *t > GEOMETRY_THRESHOLD
+        Some(Hit::new(ray, t, n, u, v))
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        let mut min_dist = INFINITY;
-        let mut index = 0;
+    fn bounds(&self) -> Option<Aabb> {
+        let corners: Vec<Vector> = (0..8)
+            .map(|i| {
+                let mut p = self.loc;
+                if i & 1 != 0 {
+                    p += self.e1;
+                }
+                if i & 2 != 0 {
+                    p += self.e2;
+                }
+                if i & 4 != 0 {
+                    p += self.e3;
+                }
+                p
+            })
+            .collect();
+        Some(Aabb::from_points(&corners))
+    }
 
-        for i in 0..3 {
-            let d = *p & self.n[i];
-            let dist = (d + self.d1[i]).abs().min((d + self.d2[i]).abs());
-            if dist < min_dist {
-                min_dist = dist;
-                index = i;
-            }
-        }
-        let normal = self.n[index];
-        if (*p - self.center) & normal < 0.0 {
-            return -normal
-        }
-        normal
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("box")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("e1", Json::from(self.e1)),
+            ("e2", Json::from(self.e2)),
+            ("e3", Json::from(self.e3)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn unit_box() -> Box {
+        Box::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((1.0, 0.0, 0.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            Vector::from((0.0, 0.0, 1.0)),
+        )
+    }
+
+    #[test]
+    fn should_hit_face_with_outward_normal() {
+        let ray = Ray::from(((0.25, 0.5, -2.0), (0.0, 0.0, 1.0)));
+        let hit = unit_box().intersect(&ray).unwrap();
+        assert_eq!(hit.t, 2.0);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, -1.0)));
+        assert_eq!((hit.u, hit.v), (0.25, 0.5));
+        assert!(hit.front_face);
+
+        let ray = Ray::from(((3.0, 0.5, 0.5), (-1.0, 0.0, 0.0)));
+        let hit = unit_box().intersect(&ray).unwrap();
+        assert_eq!(hit.t, 2.0);
+        assert_eq!(hit.normal, Vector::from((1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn should_hit_from_inside() {
+        let ray = Ray::from(((0.5, 0.5, 0.5), (0.0, 1.0, 0.0)));
+        let hit = unit_box().intersect(&ray).unwrap();
+        assert_eq!(hit.t, 0.5);
+        assert_eq!(hit.normal, Vector::from((0.0, 1.0, 0.0)));
+        assert!(!hit.front_face);
+    }
+
+    #[test]
+    fn should_respect_ray_interval() {
+        let mut ray = Ray::from(((0.5, 0.5, -2.0), (0.0, 0.0, 1.0)));
+        ray.t_max = 1.5;
+        assert!(unit_box().intersect(&ray).is_none());
     }
 }
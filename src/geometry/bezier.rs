@@ -0,0 +1,278 @@
+use super::*;
+
+/// Number of sub-patches along each parameter direction.
+const SUBDIVISIONS: usize = 8;
+const NEWTON_STEPS: usize = 16;
+const TOLERANCE: f64 = 1e-9;
+
+/// Bicubic Bezier patch.
+///
+/// `control[i][j]` is the control point for `v = i / 3` and `u = j / 3`.
+/// The patch is split into sub-patches whose control points bound them, a
+/// ray is intersected by Newton iteration started in every sub-patch it
+/// enters. The normal `dS/du ^ dS/dv` follows the parameterization.
+pub struct BezierPatch {
+    material: Surface,
+    control: [[Vector; 4]; 4],
+    cells: Vec<Cell>,
+}
+
+/// Sub-patch with its parameter ranges and bounding box.
+struct Cell {
+    u: (f64, f64),
+    v: (f64, f64),
+    bounds: Aabb,
+}
+
+impl BezierPatch {
+    pub fn new(material: Surface, control: [[Vector; 4]; 4]) -> BezierPatch {
+        let mut cells = Vec::with_capacity(SUBDIVISIONS * SUBDIVISIONS);
+        let step = 1.0 / SUBDIVISIONS as f64;
+        for i in 0..SUBDIVISIONS {
+            let v = (i as f64 * step, (i + 1) as f64 * step);
+            let rows: Vec<[Vector; 4]> = (0..4)
+                .map(|k| {
+                    restrict(
+                        &[control[0][k], control[1][k], control[2][k], control[3][k]],
+                        v,
+                    )
+                })
+                .collect();
+            for j in 0..SUBDIVISIONS {
+                let u = (j as f64 * step, (j + 1) as f64 * step);
+                let points: Vec<Vector> = (0..4)
+                    .flat_map(|k| {
+                        let row = [rows[0][k], rows[1][k], rows[2][k], rows[3][k]];
+                        restrict(&row, u).to_vec()
+                    })
+                    .collect();
+                cells.push(Cell {
+                    u,
+                    v,
+                    bounds: Aabb::from_points(&points),
+                });
+            }
+        }
+        BezierPatch {
+            material,
+            control,
+            cells,
+        }
+    }
+
+    /// Point on the patch and its partial derivatives.
+    pub fn evaluate(&self, u: f64, v: f64) -> (Vector, Vector, Vector) {
+        let (bu, du) = (bernstein(u), bernstein_derivative(u));
+        let (bv, dv) = (bernstein(v), bernstein_derivative(v));
+        let mut p = Vector::from(0.0);
+        let mut pu = Vector::from(0.0);
+        let mut pv = Vector::from(0.0);
+        for i in 0..4 {
+            for j in 0..4 {
+                let c = self.control[i][j];
+                p += c * (bv[i] * bu[j]);
+                pu += c * (bv[i] * du[j]);
+                pv += c * (dv[i] * bu[j]);
+            }
+        }
+        (p, pu, pv)
+    }
+
+    fn normal(&self, u: f64, v: f64) -> Vector {
+        let (_, pu, pv) = self.evaluate(u, v);
+        let n = pu ^ pv;
+        if !n > 1e-12 {
+            return n.normalize();
+        }
+        // degenerate edge, e.g. all control points of one row coincide
+        let (u, v) = (0.5 + (u - 0.5) * 0.999, 0.5 + (v - 0.5) * 0.999);
+        let (_, pu, pv) = self.evaluate(u, v);
+        (pu ^ pv).normalize()
+    }
+
+    /// Solves `S(u, v) = org + t dir` starting from the middle of `cell`.
+    fn newton(&self, ray: &Ray, cell: &Cell) -> Option<(f64, f64, f64)> {
+        let mut u = 0.5 * (cell.u.0 + cell.u.1);
+        let mut v = 0.5 * (cell.v.0 + cell.v.1);
+        let (p, _, _) = self.evaluate(u, v);
+        let mut t = ((p - ray.org) & ray.dir) / (ray.dir & ray.dir);
+        for _ in 0..NEWTON_STEPS {
+            let (p, pu, pv) = self.evaluate(u, v);
+            let f = p - ray.point(t);
+            if (f & f) < TOLERANCE * TOLERANCE {
+                return if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+                    Some((t, u, v))
+                } else {
+                    None
+                };
+            }
+            // Cramer's rule for [pu pv -dir] (du dv dt) = -f
+            let d = -ray.dir;
+            let det = pu & (pv ^ d);
+            if det.abs() < 1e-14 {
+                return None;
+            }
+            u -= (f & (pv ^ d)) / det;
+            v -= (pu & (f ^ d)) / det;
+            t -= (pu & (pv ^ f)) / det;
+        }
+        None
+    }
+}
+
+impl GObject for BezierPatch {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut entered: Vec<(f64, &Cell)> = self
+            .cells
+            .iter()
+            .filter_map(|cell| cell.bounds.intersect(ray).map(|(t, _)| (t, cell)))
+            .collect();
+        entered.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut nearest: Option<(f64, f64, f64)> = None;
+        for (t_enter, cell) in entered {
+            if nearest.is_some_and(|(t, _, _)| t < t_enter) {
+                break;
+            }
+            if let Some((t, u, v)) = self.newton(ray, cell) {
+                if ray.contains(t) && nearest.is_none_or(|(t_near, _, _)| t < t_near) {
+                    nearest = Some((t, u, v));
+                }
+            }
+        }
+        nearest.map(|(t, u, v)| Hit::new(ray, t, self.normal(u, v), u, v))
+    }
+
+    /// The patch lies in the convex hull of its control points, so the cells
+    /// bound it.
+    fn bounds(&self) -> Option<Aabb> {
+        let first = self.cells[0].bounds;
+        Some(self.cells[1..].iter().fold(first, |aabb, cell| aabb.union(&cell.bounds)))
+    }
+
+    /// Control points row by row, 16 in total.
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("bezier")),
+            ("material", Json::from(self.material)),
+            (
+                "control",
+                Json::Array(
+                    self.control
+                        .iter()
+                        .flat_map(|row| row.iter())
+                        .map(|&p| Json::from(p))
+                        .collect(),
+                ),
+            ),
+        ]))
+    }
+}
+
+fn bernstein(t: f64) -> [f64; 4] {
+    let s = 1.0 - t;
+    [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t]
+}
+
+fn bernstein_derivative(t: f64) -> [f64; 4] {
+    let s = 1.0 - t;
+    [
+        -3.0 * s * s,
+        3.0 * s * s - 6.0 * t * s,
+        6.0 * t * s - 3.0 * t * t,
+        3.0 * t * t,
+    ]
+}
+
+/// Splits a cubic Bezier curve at `t`, returning both halves.
+fn split(p: &[Vector; 4], t: f64) -> ([Vector; 4], [Vector; 4]) {
+    let lerp = |a: Vector, b: Vector| a + (b - a) * t;
+    let (p01, p12, p23) = (lerp(p[0], p[1]), lerp(p[1], p[2]), lerp(p[2], p[3]));
+    let (p012, p123) = (lerp(p01, p12), lerp(p12, p23));
+    let m = lerp(p012, p123);
+    ([p[0], p01, p012, m], [m, p123, p23, p[3]])
+}
+
+/// Control points of the curve restricted to parameters `range`.
+fn restrict(p: &[Vector; 4], range: (f64, f64)) -> [Vector; 4] {
+    if range.1 <= 0.0 {
+        return [p[0]; 4];
+    }
+    let (head, _) = split(p, range.1);
+    split(&head, range.0 / range.1).1
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Patch over -1..1 in x and y, with `z` as height of the inner
+    /// control points.
+    fn dome(z: f64) -> BezierPatch {
+        let mut control = [[Vector::from(0.0); 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                let inner = (i == 1 || i == 2) && (j == 1 || j == 2);
+                control[i][j] = Vector::from((
+                    j as f64 * 2.0 / 3.0 - 1.0,
+                    i as f64 * 2.0 / 3.0 - 1.0,
+                    if inner { z } else { 0.0 },
+                ));
+            }
+        }
+        BezierPatch::new(Surface::new(), control)
+    }
+
+    #[test]
+    fn flat_patch() {
+        let patch = dome(0.0);
+        let ray = Ray::from(((0.5, -0.5, 5.0), (0.0, 0.0, -1.0)));
+        let hit = patch.intersect(&ray).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        assert!((hit.u - 0.75).abs() < 1e-9 && (hit.v - 0.25).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+        assert!(hit.front_face);
+
+        let ray = Ray::from(((1.5, 0.0, 5.0), (0.0, 0.0, -1.0)));
+        assert!(patch.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn curved_patch() {
+        // center height is 9/16 of the inner control point height
+        let patch = dome(1.0);
+        let ray = Ray::from(((0.0, 0.0, 5.0), (0.0, 0.0, -1.0)));
+        let hit = patch.intersect(&ray).unwrap();
+        assert!((hit.point.z - 0.5625).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+
+        // oblique ray hits the surface it is evaluated on
+        let ray = Ray::from(((-3.0, 0.3, 3.0), (1.0, 0.0, -1.0)));
+        let hit = patch.intersect(&ray).unwrap();
+        let (p, _, _) = patch.evaluate(hit.u, hit.v);
+        assert!(!(p - hit.point) < 1e-8);
+        assert!(hit.normal.x < 0.0 && hit.normal.z > 0.0);
+    }
+
+    #[test]
+    fn restricted_curve() {
+        let curve = [
+            Vector::from(0.0),
+            Vector::from((1.0, 2.0, 0.0)),
+            Vector::from((3.0, 2.0, 0.0)),
+            Vector::from((4.0, 0.0, 0.0)),
+        ];
+        let part = restrict(&curve, (0.25, 0.5));
+        let at = |p: &[Vector; 4], t: f64| {
+            let b = bernstein(t);
+            p[0] * b[0] + p[1] * b[1] + p[2] * b[2] + p[3] * b[3]
+        };
+        assert!(!(at(&part, 0.0) - at(&curve, 0.25)) < 1e-12);
+        assert!(!(at(&part, 0.5) - at(&curve, 0.375)) < 1e-12);
+        assert!(!(at(&part, 1.0) - at(&curve, 0.5)) < 1e-12);
+    }
+}
@@ -0,0 +1,322 @@
+use super::*;
+
+use super::super::solver::{solve_quadratic, solve_quartic};
+
+/// Isosurface of a field summed from sphere and cylinder sources.
+///
+/// Every source contributes `strength * (1 - r^2 / radius^2)^2` within
+/// `radius` of its center or axis segment, the surface lies where the sum
+/// equals `threshold`. Sources with negative strength carve the blob.
+pub struct Blob {
+    material: Surface,
+    threshold: f64,
+    components: Vec<Component>,
+}
+
+enum Shape {
+    Sphere(Vector),
+    /// start, unit axis and length of the axis segment
+    Cylinder(Vector, Vector, f64),
+}
+
+struct Component {
+    shape: Shape,
+    radius2: f64,
+    strength: f64,
+}
+
+/// Part of the ray where one source contributes with squared distance
+/// `q[0] t^2 + q[1] t + q[2]`.
+struct Piece {
+    t0: f64,
+    t1: f64,
+    q: [f64; 3],
+    radius2: f64,
+    strength: f64,
+}
+
+impl Blob {
+    pub fn new(material: Surface, threshold: f64) -> Blob {
+        Blob {
+            material,
+            threshold,
+            components: Vec::new(),
+        }
+    }
+
+    pub fn add_sphere(&mut self, center: Vector, radius: f64, strength: f64) {
+        self.components.push(Component {
+            shape: Shape::Sphere(center),
+            radius2: radius * radius,
+            strength,
+        });
+    }
+
+    /// Source around the segment from `loc` to `loc + dir`, its field has
+    /// hemispherical ends.
+    pub fn add_cylinder(&mut self, loc: Vector, dir: Vector, radius: f64, strength: f64) {
+        let len = !dir;
+        self.components.push(Component {
+            shape: Shape::Cylinder(loc, dir / len, len),
+            radius2: radius * radius,
+            strength,
+        });
+    }
+
+    /// Pieces of the ray where each source has influence.
+    fn pieces(&self, ray: &Ray) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let d = ray.dir;
+        for component in &self.components {
+            let mut push = |range: Option<(f64, f64)>, q: [f64; 3]| {
+                if let Some((t0, t1)) = range {
+                    pieces.push(Piece {
+                        t0,
+                        t1,
+                        q,
+                        radius2: component.radius2,
+                        strength: component.strength,
+                    });
+                }
+            };
+            match component.shape {
+                Shape::Sphere(center) => {
+                    let q = sphere_distance(ray, &center);
+                    push(below(&q, component.radius2), q);
+                }
+                Shape::Cylinder(loc, axis, len) => {
+                    // axial position along the ray, s0 + t sd
+                    let o = ray.org - loc;
+                    let s0 = o & axis;
+                    let sd = d & axis;
+
+                    let q = [
+                        (d & d) - sd * sd,
+                        2.0 * ((o & d) - s0 * sd),
+                        (o & o) - s0 * s0,
+                    ];
+                    push(
+                        overlap(below(&q, component.radius2), between(s0, sd, 0.0, len)),
+                        q,
+                    );
+
+                    let q = sphere_distance(ray, &loc);
+                    push(
+                        overlap(below(&q, component.radius2), between(s0, sd, -INFINITY, 0.0)),
+                        q,
+                    );
+
+                    let q = sphere_distance(ray, &(loc + axis * len));
+                    push(
+                        overlap(below(&q, component.radius2), between(s0, sd, len, INFINITY)),
+                        q,
+                    );
+                }
+            }
+        }
+        pieces
+    }
+
+    /// Outward normal, opposite to the field gradient.
+    fn normal(&self, p: &Vector) -> Vector {
+        let mut n = Vector::from(0.0);
+        for component in &self.components {
+            let closest = match component.shape {
+                Shape::Sphere(center) => center,
+                Shape::Cylinder(loc, axis, len) => {
+                    loc + axis * ((*p - loc) & axis).max(0.0).min(len)
+                }
+            };
+            let r = *p - closest;
+            let g = 1.0 - (r & r) / component.radius2;
+            if g > 0.0 {
+                n += r * (component.strength * g / component.radius2);
+            }
+        }
+        n.normalize()
+    }
+}
+
+/// Squared distance from `center` along the ray as polynomial in t.
+fn sphere_distance(ray: &Ray, &center: &Vector) -> [f64; 3] {
+    let o = ray.org - center;
+    [ray.dir & ray.dir, 2.0 * (o & ray.dir), o & o]
+}
+
+/// Interval of t where polynomial `q` is below `r2`.
+fn below(q: &[f64; 3], r2: f64) -> Option<(f64, f64)> {
+    let roots = solve_quadratic(q[0], q[1], q[2] - r2);
+    if roots.len() == 2 {
+        Some((roots[0], roots[1]))
+    } else if roots.is_empty() && q[2] < r2 {
+        // no roots and inside: ray runs parallel to a cylinder axis
+        Some((-INFINITY, INFINITY))
+    } else {
+        None
+    }
+}
+
+/// Interval of t where `s0 + t sd` is between `lo` and `hi`.
+fn between(s0: f64, sd: f64, lo: f64, hi: f64) -> Option<(f64, f64)> {
+    if sd.abs() < 1e-12 {
+        return if s0 >= lo && s0 <= hi {
+            Some((-INFINITY, INFINITY))
+        } else {
+            None
+        };
+    }
+    let t0 = (lo - s0) / sd;
+    let t1 = (hi - s0) / sd;
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+fn overlap(a: Option<(f64, f64)>, b: Option<(f64, f64)>) -> Option<(f64, f64)> {
+    match (a, b) {
+        (Some((a0, a1)), Some((b0, b1))) if a0.max(b0) < a1.min(b1) => {
+            Some((a0.max(b0), a1.min(b1)))
+        }
+        _ => None,
+    }
+}
+
+impl GObject for Blob {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// Blobs have no parameterization, `u` and `v` are 0.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let pieces = self.pieces(ray);
+
+        // between consecutive piece ends the set of contributing sources is
+        // fixed and the field along the ray is a quartic polynomial
+        let mut ends: Vec<f64> = pieces
+            .iter()
+            .flat_map(|piece| vec![piece.t0, piece.t1])
+            .map(|t| t.max(ray.t_min).min(ray.t_max))
+            .collect();
+        ends.sort_by(|a, b| a.total_cmp(b));
+        ends.dedup();
+
+        for span in ends.windows(2) {
+            let (ta, tb) = (span[0], span[1]);
+            let mid = 0.5 * (ta + tb);
+
+            // coefficients in tau = t - ta, highest power first
+            let mut c = [0.0, 0.0, 0.0, 0.0, -self.threshold];
+            for piece in pieces.iter().filter(|piece| piece.t0 <= mid && mid <= piece.t1) {
+                let q = &piece.q;
+                let g2 = -q[0] / piece.radius2;
+                let g1 = -(2.0 * q[0] * ta + q[1]) / piece.radius2;
+                let g0 = 1.0 - ((q[0] * ta + q[1]) * ta + q[2]) / piece.radius2;
+                let s = piece.strength;
+                c[0] += s * g2 * g2;
+                c[1] += s * 2.0 * g2 * g1;
+                c[2] += s * (g1 * g1 + 2.0 * g2 * g0);
+                c[3] += s * 2.0 * g1 * g0;
+                c[4] += s * g0 * g0;
+            }
+
+            let roots = solve_quartic(c[0], c[1], c[2], c[3], c[4]);
+            if let Some(t) = roots
+                .iter()
+                .map(|&tau| tau + ta)
+                .find(|&t| t >= ta && t <= tb && ray.contains(t))
+            {
+                let n = self.normal(&ray.point(t));
+                return Some(Hit::new(ray, t, n, 0.0, 0.0));
+            }
+        }
+        None
+    }
+
+    /// Box around the influence of all sources, `None` without any.
+    fn bounds(&self) -> Option<Aabb> {
+        self.components
+            .iter()
+            .map(|component| {
+                let ends = match component.shape {
+                    Shape::Sphere(center) => vec![center],
+                    Shape::Cylinder(loc, axis, len) => vec![loc, loc + axis * len],
+                };
+                Aabb::from_points(&ends).grow(component.radius2.sqrt())
+            })
+            .fold(None, |bounds: Option<Aabb>, aabb| {
+                Some(bounds.map_or(aabb, |b| b.union(&aabb)))
+            })
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        let components = self.components
+            .iter()
+            .map(|c| {
+                let mut members = match c.shape {
+                    Shape::Sphere(center) => vec![("center", Json::from(center))],
+                    Shape::Cylinder(loc, axis, len) => {
+                        vec![("loc", Json::from(loc)), ("dir", Json::from(axis * len))]
+                    }
+                };
+                members.push(("radius", Json::from(c.radius2.sqrt())));
+                members.push(("strength", Json::from(c.strength)));
+                Json::object(members)
+            })
+            .collect();
+        Some(Json::object(vec![
+            ("type", Json::from("blob")),
+            ("material", Json::from(self.material)),
+            ("threshold", Json::from(self.threshold)),
+            ("components", Json::Array(components)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn single_sphere_source() {
+        // (1 - r^2 / 4)^2 = 0.25 at r^2 = 2
+        let mut blob = Blob::new(Surface::new(), 0.25);
+        blob.add_sphere(Vector::from((0.0, 0.0, 10.0)), 2.0, 1.0);
+
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = blob.intersect(&ray).unwrap();
+        assert!((hit.t - (10.0 - 2.0f64.sqrt())).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+
+        let ray = Ray::from(((0.0, 1.5, 0.0), (0.0, 0.0, 1.0)));
+        assert!(blob.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn sources_merge() {
+        // two spheres whose isosurfaces alone would not touch
+        let mut blob = Blob::new(Surface::new(), 0.5);
+        blob.add_sphere(Vector::from((-1.0, 0.0, 0.0)), 2.0, 1.0);
+        blob.add_sphere(Vector::from((1.0, 0.0, 0.0)), 2.0, 1.0);
+
+        let ray = Ray::from(((0.0, 5.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = blob.intersect(&ray).unwrap();
+        let p = hit.point;
+        let f = 2.0 * (1.0 - (1.0 + p.y * p.y) / 4.0).powi(2);
+        assert!((f - 0.5).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 1.0, 0.0))) < 1e-9);
+    }
+
+    #[test]
+    fn cylinder_source() {
+        let mut blob = Blob::new(Surface::new(), 0.25);
+        blob.add_cylinder(Vector::from(0.0), Vector::from((0.0, 4.0, 0.0)), 2.0, 1.0);
+
+        let ray = Ray::from(((-5.0, 2.0, 0.0), (1.0, 0.0, 0.0)));
+        let hit = blob.intersect(&ray).unwrap();
+        assert!((hit.t - (5.0 - 2.0f64.sqrt())).abs() < 1e-9);
+
+        // hemispherical end
+        let ray = Ray::from(((0.0, 10.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = blob.intersect(&ray).unwrap();
+        assert!((hit.t - (6.0 - 2.0f64.sqrt())).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 1.0, 0.0))) < 1e-9);
+    }
+}
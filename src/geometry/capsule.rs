@@ -0,0 +1,150 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::solver::solve_quadratic;
+
+/// Cylinder from `loc` to `loc + dir` with hemispherical ends.
+pub struct Capsule {
+    material: Surface,
+    loc: Vector,
+    axis: Vector,
+    e1: Vector,
+    e2: Vector,
+    len: f64,
+    radius: f64,
+}
+
+impl Capsule {
+    pub fn new(material: Surface, loc: Vector, dir: Vector, radius: f64) -> Capsule {
+        let len = !dir;
+        let axis = dir / len;
+        let e1 = axis.perpendicular();
+        Capsule {
+            material,
+            loc,
+            axis,
+            e1,
+            e2: axis ^ e1,
+            len,
+            radius,
+        }
+    }
+
+    /// Axial position of point `p`, 0 at `loc` and `len` at the other end.
+    fn axial(&self, p: &Vector) -> f64 {
+        (*p - self.loc) & self.axis
+    }
+}
+
+impl GObject for Capsule {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut ray = *ray;
+        let mut closest = None;
+
+        // tube, the part of the infinite cylinder between the ends
+        let o = ray.org - self.loc;
+        let oa = o & self.axis;
+        let da = ray.dir & self.axis;
+        let a = (ray.dir & ray.dir) - da * da;
+        let b = 2.0 * ((o & ray.dir) - oa * da);
+        let c = (o & o) - oa * oa - self.radius * self.radius;
+        for &t in solve_quadratic(a, b, c).iter() {
+            let s = oa + t * da;
+            if ray.contains(t) && s >= 0.0 && s <= self.len {
+                let radial = o + ray.dir * t - self.axis * s;
+                let u = 0.5 + (radial & self.e2).atan2(radial & self.e1) / (2.0 * PI);
+                closest = Some(Hit::new(&ray, t, radial / self.radius, u, s / self.len));
+                ray.t_max = t;
+                break;
+            }
+        }
+
+        // hemispheres, each only beyond its end of the tube
+        for &(center, bottom) in &[(self.loc, true), (self.loc + self.axis * self.len, false)] {
+            let o = ray.org - center;
+            let b = 2.0 * (o & ray.dir);
+            let c = (o & o) - self.radius * self.radius;
+            for &t in solve_quadratic(ray.dir & ray.dir, b, c).iter() {
+                if !ray.contains(t) {
+                    continue;
+                }
+                let p = ray.point(t);
+                let s = self.axial(&p);
+                if (bottom && s <= 0.0) || (!bottom && s >= self.len) {
+                    let n = (p - center) / self.radius;
+                    let u = 0.5 + (n & self.e2).atan2(n & self.e1) / (2.0 * PI);
+                    let v = if bottom {
+                        n & self.axis
+                    } else {
+                        1.0 + (n & self.axis)
+                    };
+                    closest = Some(Hit::new(&ray, t, n, u, v));
+                    ray.t_max = t;
+                    break;
+                }
+            }
+        }
+
+        closest
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let ends = [self.loc, self.loc + self.axis * self.len];
+        Some(Aabb::from_points(&ends).grow(self.radius))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("capsule")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("dir", Json::from(self.axis * self.len)),
+            ("radius", Json::from(self.radius)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn capsule() -> Capsule {
+        Capsule::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 4.0)),
+            1.0,
+        )
+    }
+
+    #[test]
+    fn should_intersect_tube_and_ends() {
+        let ray = Ray::from(((-5.0, 0.0, 2.0), (1.0, 0.0, 0.0)));
+        let hit = capsule().intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((-1.0, 0.0, 0.0)));
+
+        let ray = Ray::from(((0.0, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        let hit = capsule().intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, -1.0)));
+
+        let ray = Ray::from(((0.0, 0.0, 10.0), (0.0, 0.0, -1.0)));
+        let hit = capsule().intersect(&ray).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn should_exit_through_end_from_inside() {
+        let ray = Ray::from(((0.0, 0.0, 2.0), (0.0, 0.0, 1.0)));
+        let hit = capsule().intersect(&ray).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-9);
+        assert!(!hit.front_face);
+    }
+}
@@ -0,0 +1,198 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::solver::solve_quadratic;
+
+/// Truncated cone with radius `radius1` at `loc` and `radius2` at
+/// `loc + dir`, closed by caps.
+pub struct Frustum {
+    material: Surface,
+    loc: Vector,
+    dir: Vector,
+    /// unit axis and basis around it
+    axis: Vector,
+    e1: Vector,
+    e2: Vector,
+    len: f64,
+    radius1: f64,
+    radius2: f64,
+    /// radius change per unit of length along the axis
+    slope: f64,
+}
+
+impl Frustum {
+    pub fn new(material: Surface, loc: Vector, dir: Vector, radius1: f64, radius2: f64) -> Frustum {
+        let len = !dir;
+        let axis = dir / len;
+        let e1 = axis.perpendicular();
+        Frustum {
+            material,
+            loc,
+            dir,
+            axis,
+            e1,
+            e2: axis ^ e1,
+            len,
+            radius1,
+            radius2,
+            slope: (radius2 - radius1) / len,
+        }
+    }
+
+    /// Hit with the slanted side between the caps.
+    fn intersect_side(&self, ray: &Ray) -> Option<Hit> {
+        let o = ray.org - self.loc;
+        let oa = o & self.axis;
+        let da = ray.dir & self.axis;
+        let ro = self.radius1 + self.slope * oa; // radius at the axial position of org
+
+        // |q|^2 - (q, axis)^2 = r(s)^2 with q = o + t dir
+        let a = (ray.dir & ray.dir) - da * da * (1.0 + self.slope * self.slope);
+        let b = 2.0 * ((o & ray.dir) - oa * da - self.slope * da * ro);
+        let c = (o & o) - oa * oa - ro * ro;
+
+        for &t in solve_quadratic(a, b, c).iter() {
+            let s = oa + t * da;
+            if ray.contains(t) && s >= 0.0 && s <= self.len {
+                let q = o + ray.dir * t;
+                let radial = q - self.axis * s;
+                let r = self.radius1 + self.slope * s;
+                let n = (radial - self.axis * (self.slope * r)).normalize();
+                let u = 0.5 + (radial & self.e2).atan2(radial & self.e1) / (2.0 * PI);
+                return Some(Hit::new(ray, t, n, u, s / self.len));
+            }
+        }
+        None
+    }
+}
+
+impl GObject for Frustum {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut ray = *ray;
+        let mut closest = self.intersect_side(&ray);
+        if let Some(hit) = closest {
+            ray.t_max = hit.t;
+        }
+        for &(center, n, radius) in &[
+            (self.loc, -self.axis, self.radius1),
+            (self.loc + self.dir, self.axis, self.radius2),
+        ] {
+            if radius <= 0.0 {
+                continue;
+            }
+            if let Some(hit) = intersect_disc(&ray, center, n, self.e1, radius) {
+                ray.t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let bottom = Aabb::disc(self.loc, self.axis, self.radius1);
+        Some(bottom.union(&Aabb::disc(self.loc + self.dir, self.axis, self.radius2)))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("frustum")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("dir", Json::from(self.dir)),
+            ("radius1", Json::from(self.radius1)),
+            ("radius2", Json::from(self.radius2)),
+        ]))
+    }
+}
+
+/// Cone with base of `radius` at `loc` and apex at `loc + dir`.
+pub struct Cone {
+    frustum: Frustum,
+}
+
+impl Cone {
+    pub fn new(material: Surface, loc: Vector, dir: Vector, radius: f64) -> Cone {
+        Cone {
+            frustum: Frustum::new(material, loc, dir, radius, 0.0),
+        }
+    }
+}
+
+impl GObject for Cone {
+    fn material(&self) -> &Surface {
+        self.frustum.material()
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.frustum.intersect(ray)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.frustum.bounds()
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("cone")),
+            ("material", Json::from(self.frustum.material)),
+            ("loc", Json::from(self.frustum.loc)),
+            ("dir", Json::from(self.frustum.dir)),
+            ("radius", Json::from(self.frustum.radius1)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_intersect_cone_side_and_base() {
+        let cone = Cone::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 2.0)),
+            1.0,
+        );
+
+        // halfway up the cone the radius is 0.5
+        let ray = Ray::from(((-5.0, 0.0, 1.0), (1.0, 0.0, 0.0)));
+        let hit = cone.intersect(&ray).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-9);
+        let n = Vector::from((-2.0, 0.0, 1.0)).normalize();
+        assert!(!(hit.normal - n) < 1e-9);
+
+        let ray = Ray::from(((0.2, 0.0, -1.0), (0.0, 0.0, 1.0)));
+        let hit = cone.intersect(&ray).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, -1.0)));
+
+        // above the apex
+        let ray = Ray::from(((-5.0, 0.0, 2.5), (1.0, 0.0, 0.0)));
+        assert!(cone.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_intersect_frustum_top_cap() {
+        let frustum = Frustum::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 1.0, 0.0)),
+            2.0,
+            1.0,
+        );
+        let ray = Ray::from(((0.9, 5.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = frustum.intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 1.0, 0.0)));
+
+        let ray = Ray::from(((1.2, 5.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = frustum.intersect(&ray).unwrap();
+        assert!((hit.t - 4.2).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,325 @@
+use super::*;
+
+/// Surface crossings followed along a ray before giving up.
+const MAX_CROSSINGS: usize = 256;
+/// Children walked with buffers on the stack, more need the heap.
+const STACK_CHILDREN: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsgOperation {
+    /// all children, inner surfaces are kept
+    Union,
+    /// where all children overlap
+    Intersection,
+    /// first child with the others cut out
+    Difference,
+}
+
+/// Constructive solid geometry of closed objects.
+///
+/// `Hit::index` tells which child was hit: it is `index * n + child` with
+/// the index of the child's own hit and `n` children.
+pub struct Csg<'a> {
+    operation: CsgOperation,
+    children: Vec<::std::boxed::Box<GObject + 'a>>,
+}
+
+impl<'a> Csg<'a> {
+    pub fn new(operation: CsgOperation, children: Vec<::std::boxed::Box<GObject + 'a>>) -> Csg<'a> {
+        assert!(!children.is_empty(), "CSG needs at least one child");
+        Csg {
+            operation,
+            children,
+        }
+    }
+
+    fn combine(&self, inside: &[bool]) -> bool {
+        match self.operation {
+            CsgOperation::Union => inside.iter().any(|&i| i),
+            CsgOperation::Intersection => inside.iter().all(|&i| i),
+            CsgOperation::Difference => inside[0] && !inside[1..].iter().any(|&i| i),
+        }
+    }
+
+    fn tag(&self, hit: Hit, child: usize) -> Hit {
+        Hit {
+            index: hit.index * self.children.len() + child,
+            ..hit
+        }
+    }
+
+    /// Walks the surfaces of all children along `ray`, tracking which of
+    /// them contain the current point, up to the first surface of the
+    /// result. The buffers hold one entry per child.
+    fn walk(
+        &self,
+        ray: &Ray,
+        rays: &mut [Ray],
+        hits: &mut [Option<Hit>],
+        inside: &mut [bool],
+    ) -> Option<Hit> {
+        // hits at NaN distances of degenerate children count as misses
+        let intersect =
+            |child: &GObject, ray: &Ray| child.intersect(ray).filter(|hit| !hit.t.is_nan());
+        let start = ray.point(ray.t_min);
+        for (i, child) in self.children.iter().enumerate() {
+            hits[i] = intersect(&**child, ray);
+            inside[i] = match hits[i] {
+                Some(hit) => !hit.front_face,
+                None => child.inside(&start),
+            };
+        }
+        let mut solid = self.combine(inside);
+
+        for _ in 0..MAX_CROSSINGS {
+            let mut next: Option<(usize, Hit)> = None;
+            for (i, hit) in hits.iter().enumerate() {
+                if let Some(hit) = *hit {
+                    if hit.t < next.map_or(f64::INFINITY, |(_, next)| next.t) {
+                        next = Some((i, hit));
+                    }
+                }
+            }
+            let (i, hit) = next?;
+
+            inside[i] = hit.front_face;
+            let now = self.combine(inside);
+            if now != solid {
+                // the normal faces the ray where it enters the result
+                let hit = if hit.front_face == now {
+                    hit
+                } else {
+                    Hit {
+                        normal: -hit.normal,
+                        front_face: now,
+                        ..hit
+                    }
+                };
+                return Some(self.tag(hit, i));
+            }
+            solid = now;
+
+            rays[i].t_min = hit.t + 1e-7;
+            hits[i] = intersect(&*self.children[i], &rays[i]);
+        }
+        None
+    }
+}
+
+impl<'a> GObject for Csg<'a> {
+    fn material(&self) -> &Surface {
+        self.children[0].material()
+    }
+
+    fn find_texture(&self, hit: &Hit) -> Surface {
+        let n = self.children.len();
+        let child = Hit {
+            index: hit.index / n,
+            ..*hit
+        };
+        self.children[hit.index % n].find_texture(&child)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        if self.operation == CsgOperation::Union {
+            let mut ray = *ray;
+            let mut closest = None;
+            for (i, child) in self.children.iter().enumerate() {
+                if let Some(hit) = child.intersect(&ray) {
+                    ray.t_max = hit.t;
+                    closest = Some(self.tag(hit, i));
+                }
+            }
+            return closest;
+        }
+
+        let n = self.children.len();
+        if n <= STACK_CHILDREN {
+            let mut rays = [*ray; STACK_CHILDREN];
+            let mut hits = [None; STACK_CHILDREN];
+            let mut inside = [false; STACK_CHILDREN];
+            self.walk(ray, &mut rays[..n], &mut hits[..n], &mut inside[..n])
+        } else {
+            self.walk(
+                ray,
+                &mut vec![*ray; n],
+                &mut vec![None; n],
+                &mut vec![false; n],
+            )
+        }
+    }
+
+    fn inside(&self, p: &Vector) -> bool {
+        let inside: Vec<bool> = self.children.iter().map(|c| c.inside(p)).collect();
+        self.combine(&inside)
+    }
+
+    /// A union is bounded by all children, an intersection by any of them
+    /// and a difference by its first child.
+    fn bounds(&self) -> Option<Aabb> {
+        let mut children = self.children.iter().map(|child| child.bounds());
+        match self.operation {
+            CsgOperation::Union => {
+                let first = children.next().unwrap()?;
+                children.try_fold(first, |a, b| Some(a.union(&b?)))
+            }
+            CsgOperation::Intersection => children.fold(None, |a, b| match (a, b) {
+                (Some(a), Some(b)) => Some(overlap(&a, &b)),
+                (a, b) => a.or(b),
+            }),
+            CsgOperation::Difference => children.next().unwrap(),
+        }
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        let operation = match self.operation {
+            CsgOperation::Union => "union",
+            CsgOperation::Intersection => "intersection",
+            CsgOperation::Difference => "difference",
+        };
+        let children = self.children
+            .iter()
+            .map(|child| child.to_json())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Json::object(vec![
+            ("type", Json::from("csg")),
+            ("operation", Json::from(operation)),
+            ("children", Json::Array(children)),
+        ]))
+    }
+}
+
+/// Common part of two boxes, collapsed to a corner if they are disjoint.
+fn overlap(a: &Aabb, b: &Aabb) -> Aabb {
+    let min = Vector::from((
+        a.min.x.max(b.min.x),
+        a.min.y.max(b.min.y),
+        a.min.z.max(b.min.z),
+    ));
+    let max = Vector::from((
+        a.max.x.min(b.max.x).max(min.x),
+        a.max.y.min(b.max.y).max(min.y),
+        a.max.z.min(b.max.z).max(min.z),
+    ));
+    Aabb::new(min, max)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn spheres(operation: CsgOperation) -> Csg<'static> {
+        let mut red = Surface::new();
+        red.color = Color::from((1.0, 0.0, 0.0));
+        Csg::new(
+            operation,
+            vec![
+                ::std::boxed::Box::new(Sphere::new(
+                    Surface::new(),
+                    Vector::from((-1.0, 0.0, 0.0)),
+                    2.0,
+                )),
+                ::std::boxed::Box::new(Sphere::new(red, Vector::from((1.0, 0.0, 0.0)), 2.0)),
+            ],
+        )
+    }
+
+    #[test]
+    fn should_intersect_spheres() {
+        let ray = Ray::from(((-5.0, 0.0, 0.0), (1.0, 0.0, 0.0)));
+
+        let hit = spheres(CsgOperation::Intersection).intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((-1.0, 0.0, 0.0)));
+
+        let hit = spheres(CsgOperation::Union).intersect(&ray).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-9);
+
+        // inside the first sphere the cut out second one is entered from
+        // within, its normal is turned around
+        let difference = spheres(CsgOperation::Difference);
+        let ray = Ray::from(((-5.0, 1.9, 0.0), (1.0, 0.0, 0.0)));
+        assert!(difference.intersect(&ray).unwrap().t < 5.0);
+        let ray = Ray::from(((0.5, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        assert!(difference.intersect(&ray).is_none());
+        let ray = Ray::from(((5.0, 0.0, 0.0), (-1.0, 0.0, 0.0)));
+        let hit = difference.intersect(&ray).unwrap();
+        assert!((hit.t - 6.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((1.0, 0.0, 0.0)));
+        assert!(hit.front_face);
+        assert_eq!(
+            difference.find_texture(&hit).color,
+            Color::from((1.0, 0.0, 0.0))
+        );
+
+        assert!(difference.inside(&Vector::from((-2.0, 0.0, 0.0))));
+        assert!(!difference.inside(&Vector::from((0.0, 0.0, 0.0))));
+    }
+
+    #[test]
+    fn should_clip_with_half_space() {
+        // lower half of a sphere, seen from above without ever hitting the
+        // plane first
+        let csg = Csg::new(
+            CsgOperation::Intersection,
+            vec![
+                ::std::boxed::Box::new(Sphere::new(Surface::new(), Vector::from(0.0), 1.0)),
+                ::std::boxed::Box::new(Plane::new(
+                    Surface::new(),
+                    Vector::from((0.0, 1.0, 0.0)),
+                    0.0,
+                )),
+            ],
+        );
+        let ray = Ray::from(((0.0, 5.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = csg.intersect(&ray).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 1.0, 0.0)));
+
+        let ray = Ray::from(((0.5, -5.0, 0.0), (0.0, 1.0, 0.0)));
+        let hit = csg.intersect(&ray).unwrap();
+        assert!((hit.t - (5.0 - 0.75f64.sqrt())).abs() < 1e-9);
+    }
+    /// Degenerate object hit at a NaN distance by every ray.
+    struct Degenerate(Surface);
+
+    impl GObject for Degenerate {
+        fn material(&self) -> &Surface {
+            &self.0
+        }
+
+        fn intersect(&self, ray: &Ray) -> Option<Hit> {
+            Some(Hit::new(ray, f64::NAN, Vector::from(0.0), 0.0, 0.0))
+        }
+
+        fn inside(&self, _: &Vector) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn should_cut_many_children() {
+        // more children than fit on the stack, one of them degenerate
+        let mut children: Vec<::std::boxed::Box<GObject>> = vec![::std::boxed::Box::new(
+            Sphere::new(Surface::new(), Vector::from(0.0), 5.0),
+        )];
+        for i in 0..STACK_CHILDREN {
+            let x = -4.0 + i as f64;
+            children.push(::std::boxed::Box::new(Sphere::new(
+                Surface::new(),
+                Vector::from((x, 0.0, 0.0)),
+                0.25,
+            )));
+        }
+        children.push(::std::boxed::Box::new(Degenerate(Surface::new())));
+        let csg = Csg::new(CsgOperation::Difference, children);
+
+        let ray = Ray::from(((-10.0, 0.0, 0.0), (1.0, 0.0, 0.0)));
+        let hit = csg.intersect(&ray).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        // along the axis the small spheres are cut out of the big one
+        let ray = Ray::from(((-3.0, 0.0, 0.0), (1.0, 0.0, 0.0)));
+        let hit = csg.intersect(&ray).unwrap();
+        assert!((hit.t - 0.25).abs() < 1e-9);
+    }
+}
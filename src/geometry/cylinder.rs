@@ -1,14 +1,13 @@
 use super::*;
 
+use std::f64::consts::PI;
+
 pub struct Cylinder {
     material: Surface,
     e1: Vector,
     e2: Vector,
-    d1: f64,
-    d2: f64,
     len: f64,
     len2: f64,
-    radius2: f64,
     radius4: f64,
     loc: Vector,
     dir: Vector,
@@ -17,7 +16,6 @@ pub struct Cylinder {
 
 impl Cylinder {
     pub fn new(material: Surface, loc: Vector, dir: Vector, radius: f64) -> Cylinder {
-        let radius2 = radius * radius;
         let len2 = dir & dir;
         let e1 = if dir.x.abs() + dir.y.abs() > dir.z.abs() {
             Vector::from((dir.y, -dir.x, 0.0))
@@ -30,10 +28,7 @@ impl Cylinder {
             loc,
             dir,
             radius,
-            radius2,
-            radius4: radius2 * radius2,
-            d1: -(loc & dir),
-            d2: -((loc + dir) & dir),
+            radius4: radius.powi(4),
             len2,
             len: len2.sqrt(),
             e1,
@@ -41,45 +36,8 @@ impl Cylinder {
         }
     }
 
-    fn intersect_bottom_top(&self, ray: &Ray, l1: f64, len: f64, t: &mut f64) {
-        if len < 0.0 {
-            *t = -((ray.org & self.dir) + self.d1) / l1;
-            let p = ray.point(*t) - self.loc;
-            if (p & p) >= self.radius2 {
-                *t = -1.0;
-            }
-        } else if len > 1.0 {
-            *t = -((ray.org & self.dir) + self.d2) / l1;
-            let p = ray.point(*t) - self.loc - self.dir;
-            if (p & p) >= self.radius2 {
-                *t = -1.0;
-            }
-        }
-    }
-
-    fn intersect_top_bottom(&self, ray: &Ray, l1: f64, len: f64, t: &mut f64) {
-        if len < 0.0 {
-            *t = -((ray.org & self.dir) + self.d2) / l1;
-            let p = ray.point(*t) - self.loc - self.dir;
-            if (p & p) >= self.radius2 {
-                *t = -1.0;
-            }
-        } else if len > 1.0 {
-            *t = -((ray.org & self.dir) + self.d1) / l1;
-            let p = ray.point(*t) - self.loc;
-            if (p & p) >= self.radius2 {
-                *t = -1.0;
-            }
-        }
-    }
-}
-
-impl GObject for Cylinder {
-    fn material(&self) -> &Surface {
-        &self.material
-    }
-
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
+    /// Hit with the tube between the caps.
+    fn intersect_side(&self, ray: &Ray) -> Option<Hit> {
         let l = ray.org - self.loc;
         let u0 = l & self.e1;
         let u1 = ray.dir & self.e1;
@@ -93,44 +51,59 @@ impl GObject for Cylinder {
         let d = b * b - a * c;
 
         if d <= 0.0 {
-            return false;
+            return None;
         }
 
         let d = d.sqrt();
 
-        let mut t1 = (-b - d) / a;
-        let mut t2 = (-b + d) / a;
+        for &t in &[(-b - d) / a, (-b + d) / a] {
+            let len = (l0 + t * l1) / self.len2;
+            if ray.contains(t) && (0.0..=1.0).contains(&len) {
+                let q = ray.point(t) - self.loc;
+                let n = (q - self.dir * len) / self.radius;
+                let u = 0.5 + (q & self.e2).atan2(q & self.e1) / (2.0 * PI);
+                return Some(Hit::new(ray, t, n, u, len));
+            }
+        }
+        None
+    }
+}
 
-        let len1 = (l0 + t1 * l1) / self.len2;
-        let len2 = (l0 + t2 * l1) / self.len2;
+impl GObject for Cylinder {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
 
-        if l1 > EPS {
-            self.intersect_bottom_top(ray, l1, len1, &mut t1);
-            self.intersect_bottom_top(ray, l1, len2, &mut t2);
-        } else if l1 < -EPS {
-            self.intersect_top_bottom(ray, l1, len1, &mut t1);
-            self.intersect_top_bottom(ray, l1, len2, &mut t2);
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let axis = self.dir / self.len;
+        let e1 = self.e1 / self.radius;
+        let mut ray = *ray;
+        let mut closest = self.intersect_side(&ray);
+        if let Some(hit) = closest {
+            ray.t_max = hit.t;
         }
-
-        if t1 > GEOMETRY_THRESHOLD {
-            *t = t1;
-            return true;
+        for &(center, n) in &[(self.loc, -axis), (self.loc + self.dir, axis)] {
+            if let Some(hit) = intersect_disc(&ray, center, n, e1, self.radius) {
+                ray.t_max = hit.t;
+                closest = Some(hit);
+            }
         }
-
-        *t = t2;
-        t2 > GEOMETRY_THRESHOLD
+        closest
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        let t = ((*p - self.loc) & self.dir) / self.len2;
+    fn bounds(&self) -> Option<Aabb> {
+        let bottom = Aabb::disc(self.loc, self.dir, self.radius);
+        Some(bottom.union(&Aabb::disc(self.loc + self.dir, self.dir, self.radius)))
+    }
 
-        if t < EPS {
-            -self.dir / self.len
-        } else if t > 1.0 - EPS {
-            self.dir / self.len
-        } else {
-            (*p - self.loc - self.dir * t).normalize()
-        }
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("cylinder")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("dir", Json::from(self.dir)),
+            ("radius", Json::from(self.radius)),
+        ]))
     }
 }
 
@@ -149,17 +122,36 @@ pub mod tests {
 
         let dir = Vector::from((0.0, 1.0, 0.0)).normalize();
         let ray = Ray::new(Vector::from(0.0), dir);
-        let mut dist = 30000.0;
-        assert!(cylinder.intersect(&ray, &mut dist));
+        assert!(cylinder.intersect(&ray).is_some());
 
         let dir = Vector::from((0.0, 1.0, 0.1)).normalize();
         let ray = Ray::new(Vector::from(0.0), dir);
-        let mut dist = 30000.0;
-        assert!(cylinder.intersect(&ray, &mut dist));
+        assert!(cylinder.intersect(&ray).is_some());
 
         let dir = Vector::from((0.0, 1.0, -0.1)).normalize();
         let ray = Ray::new(Vector::from(0.0), dir);
-        let mut dist = 30000.0;
-        assert!(!cylinder.intersect(&ray, &mut dist));
+        assert!(cylinder.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_hit_caps_along_axis() {
+        let cylinder = Cylinder::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 2.0)),
+            1.0,
+        );
+
+        let ray = Ray::from(((0.5, 0.0, -1.0), (0.0, 0.0, 1.0)));
+        let hit = cylinder.intersect(&ray).unwrap();
+        assert_eq!(hit.t, 1.0);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, -1.0)));
+
+        let ray = Ray::from(((0.0, 0.0, 1.0), (0.0, 1.0, 0.0)));
+        let hit = cylinder.intersect(&ray).unwrap();
+        assert_eq!(hit.t, 1.0);
+        assert_eq!(hit.normal, Vector::from((0.0, 1.0, 0.0)));
+        assert_eq!(hit.v, 0.5);
+        assert!(!hit.front_face);
     }
 }
@@ -0,0 +1,149 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+/// Flat disc of `radius` around `center`, facing `normal`.
+pub struct Disc {
+    material: Surface,
+    center: Vector,
+    normal: Vector,
+    e1: Vector,
+    radius: f64,
+}
+
+impl Disc {
+    pub fn new(material: Surface, center: Vector, normal: Vector, radius: f64) -> Disc {
+        let normal = normal.normalize();
+        Disc {
+            material,
+            center,
+            normal,
+            e1: normal.perpendicular(),
+            radius,
+        }
+    }
+}
+
+impl GObject for Disc {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// The uv coordinates run from 0 to 1 across the disc.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        intersect_disc(ray, self.center, self.normal, self.e1, self.radius)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::disc(self.center, self.normal, self.radius))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("disc")),
+            ("material", Json::from(self.material)),
+            ("center", Json::from(self.center)),
+            ("normal", Json::from(self.normal)),
+            ("radius", Json::from(self.radius)),
+        ]))
+    }
+}
+
+/// Flat ring between `inner_radius` and `outer_radius` around `center`,
+/// facing `normal`.
+pub struct Annulus {
+    disc: Disc,
+    inner_radius: f64,
+}
+
+impl Annulus {
+    pub fn new(
+        material: Surface,
+        center: Vector,
+        normal: Vector,
+        inner_radius: f64,
+        outer_radius: f64,
+    ) -> Annulus {
+        Annulus {
+            disc: Disc::new(material, center, normal, outer_radius),
+            inner_radius,
+        }
+    }
+}
+
+impl GObject for Annulus {
+    fn material(&self) -> &Surface {
+        self.disc.material()
+    }
+
+    /// `u` is the angle around the ring from 0 to 1 and `v` the radial
+    /// position, 0 at the inner and 1 at the outer edge.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut hit = self.disc.intersect(ray)?;
+        let q = hit.point - self.disc.center;
+        let r = !q;
+        if r < self.inner_radius {
+            return None;
+        }
+        let e2 = self.disc.normal ^ self.disc.e1;
+        hit.u = 0.5 + (q & e2).atan2(q & self.disc.e1) / (2.0 * PI);
+        hit.v = (r - self.inner_radius) / (self.disc.radius - self.inner_radius);
+        Some(hit)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.disc.bounds()
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("annulus")),
+            ("material", Json::from(self.disc.material)),
+            ("center", Json::from(self.disc.center)),
+            ("normal", Json::from(self.disc.normal)),
+            ("inner_radius", Json::from(self.inner_radius)),
+            ("outer_radius", Json::from(self.disc.radius)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_intersect_disc() {
+        let disc = Disc::new(
+            Surface::new(),
+            Vector::from((0.0, 0.0, 5.0)),
+            Vector::from((0.0, 0.0, -2.0)),
+            1.0,
+        );
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = disc.intersect(&ray).unwrap();
+        assert_eq!(hit.t, 5.0);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, -1.0)));
+        assert_eq!((hit.u, hit.v), (0.5, 0.5));
+
+        let ray = Ray::from(((1.1, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        assert!(disc.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_intersect_annulus_ring_only() {
+        let annulus = Annulus::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 1.0, 0.0)),
+            1.0,
+            2.0,
+        );
+        let ray = Ray::from(((0.0, 1.0, 0.0), (0.0, -1.0, 0.0)));
+        assert!(annulus.intersect(&ray).is_none());
+
+        let ray = Ray::from(((1.5, 1.0, 0.0), (0.0, -1.0, 0.0)));
+        let hit = annulus.intersect(&ray).unwrap();
+        assert_eq!(hit.t, 1.0);
+        assert_eq!(hit.v, 0.5);
+    }
+}
@@ -0,0 +1,395 @@
+use super::*;
+
+use std::io::{self, Read};
+
+/// Terrain from a regular grid of height samples.
+///
+/// Sample `(i, j)` lies at `corner + (size.x * i / (width - 1),
+/// size.y * height, size.z * j / (depth - 1))`. Each grid cell is split into
+/// two triangles, normals are interpolated between the samples.
+pub struct HeightField {
+    material: Surface,
+    corner: Vector,
+    /// size of one grid cell, and the height scale
+    scale: Vector,
+    width: usize,
+    depth: usize,
+    heights: Vec<f64>,
+    min_height: f64,
+    max_height: f64,
+}
+
+impl HeightField {
+    /// `heights` holds `depth` rows of `width` samples each.
+    pub fn new(
+        material: Surface,
+        corner: Vector,
+        size: Vector,
+        width: usize,
+        depth: usize,
+        heights: Vec<f64>,
+    ) -> HeightField {
+        assert!(
+            width >= 2 && depth >= 2,
+            "height field needs at least 2x2 samples"
+        );
+        assert_eq!(heights.len(), width * depth);
+        let min_height = heights.iter().cloned().fold(f64::MAX, f64::min);
+        let max_height = heights.iter().cloned().fold(f64::MIN, f64::max);
+        HeightField {
+            material,
+            corner,
+            scale: Vector::from((
+                size.x / (width - 1) as f64,
+                size.y,
+                size.z / (depth - 1) as f64,
+            )),
+            width,
+            depth,
+            heights,
+            min_height,
+            max_height,
+        }
+    }
+
+    /// Reads heights from a binary or plain PGM grayscale image, the
+    /// brightest value maps to height 1.
+    pub fn from_pgm<R: Read>(
+        material: Surface,
+        reader: R,
+        corner: Vector,
+        size: Vector,
+    ) -> io::Result<HeightField> {
+        let (width, depth, heights) = read_pgm(reader)?;
+        if width < 2 || depth < 2 {
+            return Err(invalid("height field image needs at least 2x2 pixels"));
+        }
+        Ok(HeightField::new(
+            material, corner, size, width, depth, heights,
+        ))
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.width + i]
+    }
+
+    /// Normal at sample `(i, j)` from the differences to its neighbours.
+    fn vertex_normal(&self, i: usize, j: usize) -> Vector {
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.width - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.depth - 1));
+        let dx = (self.height(i1, j) - self.height(i0, j)) * self.scale.y
+            / ((i1 - i0) as f64 * self.scale.x);
+        let dz = (self.height(i, j1) - self.height(i, j0)) * self.scale.y
+            / ((j1 - j0) as f64 * self.scale.z);
+        Vector::from((-dx, 1.0, -dz)).normalize()
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vector {
+        Vector::from((i as f64, self.height(i, j), j as f64))
+    }
+
+    /// Intersects the two triangles of cell `(i, j)` with the ray given in
+    /// grid coordinates.
+    fn intersect_cell(&self, ray: &Ray, o: &Vector, d: &Vector, i: usize, j: usize) -> Option<Hit> {
+        let corners = [
+            [(i, j), (i + 1, j), (i, j + 1)],
+            [(i + 1, j + 1), (i, j + 1), (i + 1, j)],
+        ];
+        let mut nearest: Option<(f64, Vector)> = None;
+        for tri in &corners {
+            let [a, b, c] = *tri;
+            if let Some((t, u, v)) = intersect_triangle(
+                o,
+                d,
+                self.vertex(a.0, a.1),
+                self.vertex(b.0, b.1),
+                self.vertex(c.0, c.1),
+            ) {
+                if ray.contains(t) && nearest.is_none_or(|(t_near, _)| t < t_near) {
+                    let n = self.vertex_normal(a.0, a.1) * (1.0 - u - v)
+                        + self.vertex_normal(b.0, b.1) * u
+                        + self.vertex_normal(c.0, c.1) * v;
+                    nearest = Some((t, n.normalize()));
+                }
+            }
+        }
+        nearest.map(|(t, n)| {
+            let p = *o + *d * t;
+            Hit::new(
+                ray,
+                t,
+                n,
+                p.x / (self.width - 1) as f64,
+                p.z / (self.depth - 1) as f64,
+            )
+        })
+    }
+}
+
+impl GObject for HeightField {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        // grid coordinates keep the ray parameter
+        let o = (ray.org - self.corner) / self.scale;
+        let d = ray.dir / self.scale;
+
+        let (w, h) = ((self.width - 1) as f64, (self.depth - 1) as f64);
+        let mut t0 = ray.t_min;
+        let mut t1 = ray.t_max;
+        for &(o, d, lo, hi) in &[
+            (o.x, d.x, 0.0, w),
+            (o.y, d.y, self.min_height, self.max_height),
+            (o.z, d.z, 0.0, h),
+        ] {
+            if d.abs() < 1e-12 {
+                if o < lo || o > hi {
+                    return None;
+                }
+            } else {
+                let (a, b) = ((lo - o) / d, (hi - o) / d);
+                t0 = t0.max(a.min(b));
+                t1 = t1.min(a.max(b));
+            }
+        }
+        if t0 > t1 {
+            return None;
+        }
+
+        // walk the cells along the ray
+        let start = o + d * t0;
+        let mut i = (start.x.floor() as isize)
+            .max(0)
+            .min(self.width as isize - 2);
+        let mut j = (start.z.floor() as isize)
+            .max(0)
+            .min(self.depth as isize - 2);
+        let axis = |o: f64, d: f64, cell: isize| {
+            if d > 0.0 {
+                (1, (cell as f64 + 1.0 - o) / d, 1.0 / d)
+            } else if d < 0.0 {
+                (-1, (cell as f64 - o) / d, -1.0 / d)
+            } else {
+                (0, f64::MAX, f64::MAX)
+            }
+        };
+        let (step_i, mut next_x, delta_x) = axis(o.x, d.x, i);
+        let (step_j, mut next_z, delta_z) = axis(o.z, d.z, j);
+
+        let mut t = t0;
+        loop {
+            let t_exit = next_x.min(next_z).min(t1);
+
+            // skip cells the ray passes above or below
+            let (cell_min, cell_max) = {
+                let (i, j) = (i as usize, j as usize);
+                let hs = [
+                    self.height(i, j),
+                    self.height(i + 1, j),
+                    self.height(i, j + 1),
+                    self.height(i + 1, j + 1),
+                ];
+                (
+                    hs.iter().cloned().fold(f64::MAX, f64::min),
+                    hs.iter().cloned().fold(f64::MIN, f64::max),
+                )
+            };
+            let (ya, yb) = (o.y + d.y * t, o.y + d.y * t_exit);
+            if ya.min(yb) <= cell_max && ya.max(yb) >= cell_min {
+                let hit = self.intersect_cell(ray, &o, &d, i as usize, j as usize);
+                if hit.is_some() {
+                    return hit;
+                }
+            }
+
+            if t_exit >= t1 {
+                return None;
+            }
+            if next_x < next_z {
+                i += step_i;
+                next_x += delta_x;
+            } else {
+                j += step_j;
+                next_z += delta_z;
+            }
+            if i < 0 || j < 0 || i > self.width as isize - 2 || j > self.depth as isize - 2 {
+                return None;
+            }
+            t = t_exit;
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (w, h) = ((self.width - 1) as f64, (self.depth - 1) as f64);
+        let low = Vector::from((0.0, self.min_height, 0.0));
+        let high = Vector::from((w, self.max_height, h));
+        Some(Aabb::from_points(&[
+            self.corner + low * self.scale,
+            self.corner + high * self.scale,
+        ]))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("height_field")),
+            ("material", Json::from(self.material)),
+            ("corner", Json::from(self.corner)),
+            (
+                "size",
+                Json::from(Vector::from((
+                    self.scale.x * (self.width - 1) as f64,
+                    self.scale.y,
+                    self.scale.z * (self.depth - 1) as f64,
+                ))),
+            ),
+            ("width", Json::from(self.width as f64)),
+            ("depth", Json::from(self.depth as f64)),
+            ("heights", Json::from(self.heights.clone())),
+        ]))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Returns width, height and the samples scaled to 0..1.
+fn read_pgm<R: Read>(mut reader: R) -> io::Result<(usize, usize, Vec<f64>)> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 4 {
+        while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+            if data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < data.len() && !data[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("truncated PGM header"));
+        }
+        header.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+    }
+
+    let number = |s: &str| {
+        s.parse::<usize>()
+            .map_err(|_| invalid("bad number in PGM header"))
+    };
+    let width = number(&header[1])?;
+    let height = number(&header[2])?;
+    let max_value = number(&header[3])?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("bad PGM maximum value"));
+    }
+    let count = width * height;
+
+    let samples: Vec<usize> = match header[0].as_str() {
+        "P5" => {
+            let body = &data[(pos + 1).min(data.len())..];
+            if max_value < 256 {
+                body.iter().take(count).map(|&b| b as usize).collect()
+            } else {
+                body.chunks(2)
+                    .filter(|pair| pair.len() == 2)
+                    .take(count)
+                    .map(|pair| (pair[0] as usize) << 8 | pair[1] as usize)
+                    .collect()
+            }
+        }
+        "P2" => String::from_utf8_lossy(&data[pos..])
+            .split_whitespace()
+            .take(count)
+            .map(number)
+            .collect::<io::Result<_>>()?,
+        _ => return Err(invalid("not a PGM image")),
+    };
+    if samples.len() < count {
+        return Err(invalid("truncated PGM data"));
+    }
+
+    let max_value = max_value as f64;
+    Ok((
+        width,
+        height,
+        samples.into_iter().map(|s| s as f64 / max_value).collect(),
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_field() {
+        let field = HeightField::new(
+            Surface::new(),
+            Vector::from((-2.0, 0.0, -2.0)),
+            Vector::from((4.0, 1.0, 4.0)),
+            3,
+            3,
+            vec![0.5; 9],
+        );
+        let ray = Ray::from(((0.5, 3.0, 0.5), (0.0, -1.0, 0.0)));
+        let hit = field.intersect(&ray).unwrap();
+        assert!((hit.t - 2.5).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 1.0, 0.0))) < 1e-9);
+        assert!((hit.u - 0.625).abs() < 1e-9 && (hit.v - 0.625).abs() < 1e-9);
+
+        let ray = Ray::from(((3.0, 3.0, 0.0), (0.0, -1.0, 0.0)));
+        assert!(field.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn walks_to_far_cell() {
+        // single peak in the last cell, seen by a shallow ray
+        let mut heights = vec![0.0; 10 * 2];
+        heights[9] = 1.0;
+        heights[19] = 1.0;
+        let field = HeightField::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((9.0, 1.0, 1.0)),
+            10,
+            2,
+            heights,
+        );
+        let ray = Ray::from(((-1.0, 0.5, 0.5), (1.0, 0.0, 0.0)));
+        let hit = field.intersect(&ray).unwrap();
+        assert!((hit.point.x - 8.5).abs() < 1e-9);
+        assert!(hit.normal.x < 0.0 && hit.normal.y > 0.0);
+        assert!(hit.front_face);
+    }
+
+    #[test]
+    fn pgm_images() {
+        let plain = b"P2\n# terrain\n2 2\n4\n0 1\n2 4\n";
+        let (w, h, heights) = read_pgm(&plain[..]).unwrap();
+        assert_eq!((w, h), (2, 2));
+        assert_eq!(heights, vec![0.0, 0.25, 0.5, 1.0]);
+
+        let binary = b"P5 2 2 255\n\x00\xff\x33\x66";
+        let field = HeightField::from_pgm(
+            Surface::new(),
+            &binary[..],
+            Vector::from(0.0),
+            Vector::from(1.0),
+        )
+        .unwrap();
+        assert_eq!(field.height(1, 0), 1.0);
+        assert_eq!(field.height(0, 1), 0.2);
+
+        assert!(read_pgm(&b"P5 2 2 255\n\x00"[..]).is_err());
+        assert!(read_pgm(&b"P6 2 2 255\n"[..]).is_err());
+    }
+}
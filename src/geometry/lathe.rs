@@ -0,0 +1,228 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::solver::{solve_cubic, solve_quadratic};
+
+/// Surface of revolution around `axis` through `loc`.
+///
+/// The profile is a list of `(radius, height)` points with increasing
+/// heights. The squared radius is interpolated between them by a cubic
+/// Catmull-Rom spline, so every ray meets a segment in the roots of a cubic.
+/// The ends are open, a profile point with radius 0 closes them.
+pub struct Lathe {
+    material: Surface,
+    loc: Vector,
+    axis: Vector,
+    e1: Vector,
+    e2: Vector,
+    heights: Vec<f64>,
+    profile: Vec<(f64, f64)>,
+    /// squared radius polynomial of each segment in `s` from 0 to 1, lowest
+    /// power first
+    segments: Vec<[f64; 4]>,
+}
+
+impl Lathe {
+    pub fn new(material: Surface, loc: Vector, axis: Vector, profile: &[(f64, f64)]) -> Lathe {
+        assert!(
+            profile.len() >= 2,
+            "lathe profile needs at least two points"
+        );
+        assert!(
+            profile.windows(2).all(|w| w[1].1 > w[0].1),
+            "lathe profile heights must increase"
+        );
+        let n = profile.len();
+        let f: Vec<f64> = profile.iter().map(|&(r, _)| r * r).collect();
+        let y: Vec<f64> = profile.iter().map(|&(_, y)| y).collect();
+
+        // slope of the squared radius at every point
+        let m: Vec<f64> = (0..n)
+            .map(|k| {
+                let (a, b) = (k.saturating_sub(1), (k + 1).min(n - 1));
+                (f[b] - f[a]) / (y[b] - y[a])
+            })
+            .collect();
+
+        let segments = (0..n - 1)
+            .map(|k| {
+                let h = y[k + 1] - y[k];
+                let (f0, f1, m0, m1) = (f[k], f[k + 1], h * m[k], h * m[k + 1]);
+                [
+                    f0,
+                    m0,
+                    -3.0 * f0 - 2.0 * m0 + 3.0 * f1 - m1,
+                    2.0 * f0 + m0 - 2.0 * f1 + m1,
+                ]
+            })
+            .collect();
+
+        let axis = axis.normalize();
+        let e1 = axis.perpendicular();
+        Lathe {
+            material,
+            loc,
+            axis,
+            e1,
+            e2: axis ^ e1,
+            heights: y,
+            profile: profile.to_vec(),
+            segments,
+        }
+    }
+}
+
+impl GObject for Lathe {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        // ray in the frame (e1, axis, e2)
+        let o = ray.org - self.loc;
+        let (ox, oy, oz) = (o & self.e1, o & self.axis, o & self.e2);
+        let d = ray.dir;
+        let (dx, dy, dz) = (d & self.e1, d & self.axis, d & self.e2);
+
+        // x^2 + z^2 along the ray
+        let qa = dx * dx + dz * dz;
+        let qb = 2.0 * (ox * dx + oz * dz);
+        let qc = ox * ox + oz * oz;
+
+        let mut nearest: Option<(f64, usize, f64)> = None;
+        for (k, c) in self.segments.iter().enumerate() {
+            let h = self.heights[k + 1] - self.heights[k];
+            let s0 = (oy - self.heights[k]) / h;
+            let s1 = dy / h;
+
+            // skip segments the ray does not pass in height
+            if s1.abs() < 1e-12 {
+                if !(0.0..=1.0).contains(&s0) {
+                    continue;
+                }
+            } else {
+                let (ta, tb) = (-s0 / s1, (1.0 - s0) / s1);
+                if ta.max(tb) < ray.t_min || ta.min(tb) > ray.t_max {
+                    continue;
+                }
+            }
+
+            // squared radius with s = s0 + s1 t, highest power first
+            let a3 = c[3] * s1 * s1 * s1;
+            let a2 = (3.0 * c[3] * s0 + c[2]) * s1 * s1;
+            let a1 = (3.0 * c[3] * s0 * s0 + 2.0 * c[2] * s0 + c[1]) * s1;
+            let a0 = ((c[3] * s0 + c[2]) * s0 + c[1]) * s0 + c[0];
+
+            for &t in solve_cubic(-a3, qa - a2, qb - a1, qc - a0).iter() {
+                let s = s0 + s1 * t;
+                if !(0.0..=1.0).contains(&s) || !ray.contains(t) {
+                    continue;
+                }
+                if nearest.is_none_or(|(t_near, _, _)| t < t_near) {
+                    nearest = Some((t, k, s));
+                }
+                break;
+            }
+        }
+
+        nearest.map(|(t, k, s)| {
+            let c = &self.segments[k];
+            let h = self.heights[k + 1] - self.heights[k];
+            let (x, z) = (ox + dx * t, oz + dz * t);
+            // gradient of x^2 + z^2 - f(y)
+            let slope = (c[1] + s * (2.0 * c[2] + s * 3.0 * c[3])) / h;
+            let n = self.e1 * (2.0 * x) - self.axis * slope + self.e2 * (2.0 * z);
+            let first = self.heights[0];
+            let v = (oy + dy * t - first) / (self.heights[self.heights.len() - 1] - first);
+            let u = 0.5 + z.atan2(x) / (2.0 * PI);
+            Hit::new(ray, t, n.normalize(), u, v)
+        })
+    }
+
+    /// The spline may bulge beyond the profile points, the box takes the
+    /// largest radius of every segment.
+    fn bounds(&self) -> Option<Aabb> {
+        let radius2 = self.segments
+            .iter()
+            .map(|c| {
+                let value = |s: f64| c[0] + s * (c[1] + s * (c[2] + s * c[3]));
+                let extremes = solve_quadratic(3.0 * c[3], 2.0 * c[2], c[1]);
+                extremes
+                    .iter()
+                    .filter(|&&s| s > 0.0 && s < 1.0)
+                    .fold(value(0.0).max(value(1.0)), |m, &s| m.max(value(s)))
+            })
+            .fold(0.0, f64::max);
+        let radius = radius2.sqrt();
+        let bottom = self.loc + self.axis * self.heights[0];
+        let top = self.loc + self.axis * self.heights[self.heights.len() - 1];
+        let aabb = Aabb::disc(bottom, self.axis, radius);
+        Some(aabb.union(&Aabb::disc(top, self.axis, radius)))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("lathe")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("axis", Json::from(self.axis)),
+            (
+                "profile",
+                Json::Array(
+                    self.profile
+                        .iter()
+                        .map(|&(r, y)| Json::from(vec![r, y]))
+                        .collect(),
+                ),
+            ),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_profile_is_cylinder() {
+        let lathe = Lathe::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 1.0, 0.0)),
+            &[(1.0, 0.0), (1.0, 1.0), (1.0, 2.0)],
+        );
+        let ray = Ray::from(((-5.0, 1.5, 0.0), (1.0, 0.0, 0.0)));
+        let hit = lathe.intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((-1.0, 0.0, 0.0))) < 1e-9);
+        assert!((hit.v - 0.75).abs() < 1e-9);
+
+        // open ends
+        let ray = Ray::from(((0.0, 5.0, 0.0), (0.0, -1.0, 0.0)));
+        assert!(lathe.intersect(&ray).is_none());
+        let ray = Ray::from(((-5.0, 2.5, 0.0), (1.0, 0.0, 0.0)));
+        assert!(lathe.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn paraboloid_profile() {
+        // squared radius y / 2, closed at the bottom
+        let lathe = Lathe::new(
+            Surface::new(),
+            Vector::from((0.0, 0.0, 3.0)),
+            Vector::from((0.0, 0.0, 1.0)),
+            &[(0.0, 0.0), (1.0, 2.0)],
+        );
+        let ray = Ray::from(((-5.0, 0.0, 4.0), (1.0, 0.0, 0.0)));
+        let hit = lathe.intersect(&ray).unwrap();
+        assert!((hit.t - (5.0 - 0.5f64.sqrt())).abs() < 1e-9);
+        let n = Vector::from((-2.0 * 0.5f64.sqrt(), 0.0, -0.5)).normalize();
+        assert!(!(hit.normal - n) < 1e-9);
+
+        // from below through the closed tip
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = lathe.intersect(&ray).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,329 @@
+use super::*;
+
+/// Triangles per leaf of the bounding volume hierarchy.
+const LEAF_SIZE: usize = 4;
+
+/// Triangle mesh with optional per vertex normals and colors.
+///
+/// Triangles are found through a bounding volume hierarchy built when the
+/// mesh is created. `Hit::index` is the index of the hit triangle, `u` and
+/// `v` its barycentric coordinates.
+pub struct Mesh {
+    material: Surface,
+    vertices: Vec<Vector>,
+    normals: Option<Vec<Vector>>,
+    colors: Option<Vec<Color>>,
+    triangles: Vec<[usize; 3]>,
+    nodes: Vec<Node>,
+}
+
+/// Node of the hierarchy, a leaf owns `count` triangles starting at `first`,
+/// an inner node has its children at `first` and `first + 1`.
+struct Node {
+    bounds: Aabb,
+    first: usize,
+    count: usize,
+}
+
+impl Mesh {
+    /// Mesh of `triangles` given as indices into `vertices`.
+    pub fn new(material: Surface, vertices: Vec<Vector>, triangles: Vec<[usize; 3]>) -> Mesh {
+        assert!(
+            triangles
+                .iter()
+                .all(|tri| tri.iter().all(|&i| i < vertices.len())),
+            "triangle vertex index out of range"
+        );
+        let mut mesh = Mesh {
+            material,
+            vertices,
+            normals: None,
+            colors: None,
+            triangles,
+            nodes: Vec::new(),
+        };
+        if !mesh.triangles.is_empty() {
+            let count = mesh.triangles.len();
+            mesh.nodes.push(Node {
+                bounds: mesh.triangle_bounds(0, count),
+                first: 0,
+                count,
+            });
+            mesh.split(0);
+        }
+        mesh
+    }
+
+    /// Normals interpolated over the triangles, one per vertex.
+    pub fn set_normals(&mut self, normals: Vec<Vector>) {
+        assert_eq!(normals.len(), self.vertices.len());
+        self.normals = Some(normals.iter().map(|n| n.normalize()).collect());
+    }
+
+    /// Colors interpolated over the triangles, one per vertex.
+    pub fn set_colors(&mut self, colors: Vec<Color>) {
+        assert_eq!(colors.len(), self.vertices.len());
+        self.colors = Some(colors);
+    }
+
+    pub fn vertices(&self) -> &[Vector] {
+        &self.vertices
+    }
+
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    fn corners(&self, index: usize) -> [Vector; 3] {
+        let [a, b, c] = self.triangles[index];
+        [self.vertices[a], self.vertices[b], self.vertices[c]]
+    }
+
+    fn triangle_bounds(&self, first: usize, count: usize) -> Aabb {
+        let points: Vec<Vector> = (first..first + count)
+            .flat_map(|i| self.corners(i).to_vec())
+            .collect();
+        Aabb::from_points(&points)
+    }
+
+    /// Splits a leaf at the median of the triangle centers along the longest
+    /// axis, until leaves are small.
+    fn split(&mut self, node: usize) {
+        let (first, count) = (self.nodes[node].first, self.nodes[node].count);
+        if count <= LEAF_SIZE {
+            return;
+        }
+
+        let centers: Vec<Vector> = (first..first + count)
+            .map(|i| {
+                let [a, b, c] = self.corners(i);
+                (a + b + c) / 3.0
+            })
+            .collect();
+        let extent = Aabb::from_points(&centers);
+        let size = extent.max - extent.min;
+        let axis = |p: &Vector| {
+            if size.x >= size.y && size.x >= size.z {
+                p.x
+            } else if size.y >= size.z {
+                p.y
+            } else {
+                p.z
+            }
+        };
+
+        let mut order: Vec<(f64, [usize; 3])> = centers
+            .iter()
+            .zip(&self.triangles[first..first + count])
+            .map(|(c, &tri)| (axis(c), tri))
+            .collect();
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, tri)) in self.triangles[first..first + count].iter_mut().zip(order) {
+            *slot = tri;
+        }
+
+        let half = count / 2;
+        let children = self.nodes.len();
+        for &(first, count) in &[(first, half), (first + half, count - half)] {
+            let bounds = self.triangle_bounds(first, count);
+            self.nodes.push(Node {
+                bounds,
+                first,
+                count,
+            });
+        }
+        self.nodes[node].first = children;
+        self.nodes[node].count = 0;
+        self.split(children);
+        self.split(children + 1);
+    }
+
+    /// Barycentric weights of `p` in triangle `index`.
+    fn weights(&self, index: usize, p: &Vector) -> [f64; 3] {
+        let [a, b, c] = self.corners(index);
+        let (e1, e2, q) = (b - a, c - a, *p - a);
+        let n = e1 ^ e2;
+        let area = n & n;
+        let u = ((q ^ e2) & n) / area;
+        let v = ((e1 ^ q) & n) / area;
+        [1.0 - u - v, u, v]
+    }
+}
+
+impl GObject for Mesh {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// Interpolates vertex normals and colors at the hit point.
+    fn find_texture(&self, hit: &Hit) -> Surface {
+        let mut texture = self.material;
+        texture.n = hit.normal;
+        if let Some(ref colors) = self.colors {
+            let [a, b, c] = self.triangles[hit.index];
+            let w = self.weights(hit.index, &hit.point);
+            texture.color = colors[a] * w[0] + colors[b] * w[1] + colors[c] * w[2];
+        }
+        texture
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut ray = *ray;
+        let mut nearest: Option<(f64, usize, f64, f64)> = None;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.bounds.intersect(&ray).is_none() {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+                continue;
+            }
+            for i in node.first..node.first + node.count {
+                let [a, b, c] = self.corners(i);
+                if let Some((t, u, v)) = intersect_triangle(&ray.org, &ray.dir, a, b, c) {
+                    if ray.contains(t) {
+                        ray.t_max = t;
+                        nearest = Some((t, i, u, v));
+                    }
+                }
+            }
+        }
+
+        nearest.map(|(t, i, u, v)| {
+            let [a, b, c] = self.triangles[i];
+            let n = match self.normals {
+                Some(ref normals) => normals[a] * (1.0 - u - v) + normals[b] * u + normals[c] * v,
+                None => {
+                    let [pa, pb, pc] = self.corners(i);
+                    (pb - pa) ^ (pc - pa)
+                }
+            };
+            Hit {
+                index: i,
+                ..Hit::new(&ray, t, n.normalize(), u, v)
+            }
+        })
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /// Vertex data and indices are written as flat arrays of numbers.
+    fn to_json(&self) -> Option<Json> {
+        let flat = |vectors: &[Vector]| {
+            Json::from(
+                vectors
+                    .iter()
+                    .flat_map(|v| vec![v.x, v.y, v.z])
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let mut members = vec![
+            ("type", Json::from("mesh")),
+            ("material", Json::from(self.material)),
+            ("vertices", flat(&self.vertices)),
+            (
+                "triangles",
+                Json::from(
+                    self.triangles
+                        .iter()
+                        .flat_map(|t| t.iter().map(|&i| i as f64))
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+        ];
+        if let Some(ref normals) = self.normals {
+            members.push(("normals", flat(normals)));
+        }
+        if let Some(ref colors) = self.colors {
+            members.push(("colors", flat(colors)));
+        }
+        Some(Json::object(members))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Grid of `n` by `n` quads in the plane z = 0, counterclockwise seen
+    /// from +z.
+    fn grid(n: usize) -> Mesh {
+        let mut vertices = Vec::new();
+        for j in 0..n + 1 {
+            for i in 0..n + 1 {
+                vertices.push(Vector::from((i as f64, j as f64, 0.0)));
+            }
+        }
+        let mut triangles = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let k = j * (n + 1) + i;
+                triangles.push([k, k + 1, k + n + 2]);
+                triangles.push([k, k + n + 2, k + n + 1]);
+            }
+        }
+        Mesh::new(Surface::new(), vertices, triangles)
+    }
+
+    #[test]
+    fn should_find_triangle_through_hierarchy() {
+        let mesh = grid(10);
+        assert_eq!(
+            mesh.bounds(),
+            Some(Aabb::new(
+                Vector::from(0.0),
+                Vector::from((10.0, 10.0, 0.0))
+            ))
+        );
+
+        let ray = Ray::from(((7.25, 3.5, 5.0), (0.0, 0.0, -1.0)));
+        let hit = mesh.intersect(&ray).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, 1.0)));
+        let [a, b, c] = mesh.triangles()[hit.index];
+        let p = mesh.vertices()[a] * (1.0 - hit.u - hit.v)
+            + mesh.vertices()[b] * hit.u
+            + mesh.vertices()[c] * hit.v;
+        assert!(!(p - hit.point) < 1e-9);
+
+        let ray = Ray::from(((10.5, 3.5, 5.0), (0.0, 0.0, -1.0)));
+        assert!(mesh.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_interpolate_normals_and_colors() {
+        let mut mesh = Mesh::new(
+            Surface::new(),
+            vec![
+                Vector::from(0.0),
+                Vector::from((1.0, 0.0, 0.0)),
+                Vector::from((0.0, 1.0, 0.0)),
+            ],
+            vec![[0, 1, 2]],
+        );
+        mesh.set_normals(vec![
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((1.0, 0.0, 1.0)),
+            Vector::from((0.0, 0.0, 1.0)),
+        ]);
+        mesh.set_colors(vec![
+            Color::from((1.0, 0.0, 0.0)),
+            Color::from((0.0, 1.0, 0.0)),
+            Color::from((0.0, 0.0, 1.0)),
+        ]);
+
+        let ray = Ray::from(((0.5, 0.25, 1.0), (0.0, 0.0, -1.0)));
+        let hit = mesh.intersect(&ray).unwrap();
+        assert!(hit.normal.x > 0.0 && hit.normal.z > 0.0);
+        let texture = mesh.find_texture(&hit);
+        assert!(!(texture.color - Color::from((0.25, 0.5, 0.25))) < 1e-9);
+    }
+}
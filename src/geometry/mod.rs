@@ -1,11 +1,27 @@
 use super::*;
 
+use super::loader::Json;
+
 pub mod sphere;
 pub mod plane;
 pub mod rect;
 pub mod triangle;
 pub mod _box;
 pub mod cylinder;
+pub mod transform;
+pub mod torus;
+pub mod cone;
+pub mod capsule;
+pub mod disc;
+pub mod quadric;
+pub mod sdf;
+pub mod blob;
+pub mod heightfield;
+pub mod bezier;
+pub mod lathe;
+pub mod prism;
+pub mod mesh;
+pub mod csg;
 
 pub use self::sphere::Sphere;
 pub use self::plane::Plane;
@@ -13,6 +29,214 @@ pub use self::rect::Rect;
 pub use self::triangle::Triangle;
 pub use self::_box::Box;
 pub use self::cylinder::Cylinder;
+pub use self::transform::{Instance, Transform};
+pub use self::torus::Torus;
+pub use self::cone::{Cone, Frustum};
+pub use self::capsule::Capsule;
+pub use self::disc::{Annulus, Disc};
+pub use self::quadric::{Ellipsoid, Quadric};
+pub use self::sdf::SdfObject;
+pub use self::blob::Blob;
+pub use self::heightfield::HeightField;
+pub use self::bezier::BezierPatch;
+pub use self::lathe::Lathe;
+pub use self::prism::Prism;
+pub use self::mesh::Mesh;
+pub use self::csg::{Csg, CsgOperation};
 
-const GEOMETRY_THRESHOLD: f64 = 0.001;
 const EPS: f64 = 0.01;
+
+/// Hit with the disc of `radius` around `center` with normal `n`. The uv
+/// coordinates run from 0 to 1 across the disc along unit `e1` and `n ^ e1`.
+fn intersect_disc(ray: &Ray, center: Vector, n: Vector, e1: Vector, radius: f64) -> Option<Hit> {
+    let vd = ray.dir & n;
+    if vd > -EPS && vd < EPS {
+        return None;
+    }
+
+    let t = ((center - ray.org) & n) / vd;
+    if !ray.contains(t) {
+        return None;
+    }
+
+    let q = ray.point(t) - center;
+    if (q & q) >= radius * radius {
+        return None;
+    }
+
+    let u = 0.5 + 0.5 * (q & e1) / radius;
+    let v = 0.5 + 0.5 * (q & (n ^ e1)) / radius;
+    Some(Hit::new(ray, t, n, u, v))
+}
+
+/// Möller-Trumbore test, returns the ray parameter and the barycentric
+/// coordinates of `b` and `c`.
+fn intersect_triangle(
+    o: &Vector,
+    d: &Vector,
+    a: Vector,
+    b: Vector,
+    c: Vector,
+) -> Option<(f64, f64, f64)> {
+    let e1 = b - a;
+    let e2 = c - a;
+    let p = *d ^ e2;
+    let det = e1 & p;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let s = *o - a;
+    let u = (s & p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s ^ e1;
+    let v = (*d & q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some(((e2 & q) / det, u, v))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::random::rnd;
+    use super::sdf::primitives;
+
+    #[test]
+    fn bounds_contain_hits() {
+        let m = Surface::new();
+        let v = |x, y, z| Vector::from((x, y, z));
+        let mut quadric = Quadric::new(m, [1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0]);
+        quadric.set_clip_box(v(-2.0, -2.0, -1.0), v(2.0, 2.0, 1.0));
+        let mut blob = Blob::new(m, 0.5);
+        blob.add_sphere(v(0.0, 1.0, 0.0), 1.5, 1.0);
+        blob.add_cylinder(v(-1.0, 0.0, 0.0), v(2.0, -1.0, 0.5), 1.0, 1.0);
+        let sphere = Sphere::new(m, v(0.5, 0.0, 0.0), 1.0);
+        let mut control = [[Vector::from(0.0); 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                control[i][j] = v(j as f64 - 1.5, i as f64 - 1.5, (i * j) as f64 % 3.0 - 1.0);
+            }
+        }
+
+        let objects: Vec<::std::boxed::Box<GObject>> = vec![
+            ::std::boxed::Box::new(Sphere::new(m, v(1.0, 2.0, 0.0), 1.0)),
+            ::std::boxed::Box::new(Rect::new(
+                m,
+                v(0.0, 0.0, 1.0),
+                v(2.0, 1.0, 0.0),
+                v(0.0, 1.0, 2.0),
+            )),
+            ::std::boxed::Box::new(Triangle::new(
+                m,
+                v(0.0, 0.0, 1.0),
+                v(2.0, 1.0, 0.0),
+                v(0.0, 1.0, 2.0),
+            )),
+            ::std::boxed::Box::new(Box::new(
+                m,
+                v(-1.0, 0.0, 0.0),
+                v(1.0, 1.0, 0.0),
+                v(-1.0, 1.0, 0.0),
+                v(0.0, 0.0, 2.0),
+            )),
+            ::std::boxed::Box::new(Cylinder::new(m, v(0.0, -1.0, 0.0), v(1.0, 2.0, 1.0), 1.0)),
+            ::std::boxed::Box::new(Frustum::new(
+                m,
+                v(0.0, -1.0, 0.0),
+                v(1.0, 2.0, 1.0),
+                1.5,
+                0.5,
+            )),
+            ::std::boxed::Box::new(Cone::new(m, v(0.0, -1.0, 0.0), v(-1.0, 2.0, 1.0), 1.0)),
+            ::std::boxed::Box::new(Capsule::new(m, v(0.0, -1.0, 0.0), v(1.0, 2.0, 1.0), 0.5)),
+            ::std::boxed::Box::new(Disc::new(m, v(0.0, 0.0, 0.0), v(1.0, 1.0, 0.0), 2.0)),
+            ::std::boxed::Box::new(Annulus::new(
+                m,
+                v(0.0, 0.0, 0.0),
+                v(0.0, 1.0, 1.0),
+                1.0,
+                2.0,
+            )),
+            ::std::boxed::Box::new(Torus::new(m, v(0.0, 0.0, 0.0), v(1.0, 2.0, 0.0), 1.5, 0.5)),
+            ::std::boxed::Box::new(Ellipsoid::new(m, v(0.0, 0.5, 0.0), v(2.0, 1.0, 0.5))),
+            ::std::boxed::Box::new(quadric),
+            ::std::boxed::Box::new(blob),
+            ::std::boxed::Box::new(BezierPatch::new(m, control)),
+            ::std::boxed::Box::new(Lathe::new(
+                m,
+                v(0.0, -1.0, 0.0),
+                v(0.0, 1.0, 1.0),
+                &[(0.5, 0.0), (2.0, 1.0), (0.5, 2.0)],
+            )),
+            ::std::boxed::Box::new(Prism::new(
+                m,
+                v(0.0, -1.0, 0.0),
+                v(1.0, 2.0, 0.0),
+                v(0.0, 0.0, 1.0),
+                vec![(0.0, 0.0), (2.0, 0.0), (0.0, 1.0)],
+            )),
+            ::std::boxed::Box::new(HeightField::new(
+                m,
+                v(-2.0, 0.0, -2.0),
+                v(4.0, 1.0, 4.0),
+                3,
+                3,
+                vec![0.0, 0.5, 0.0, 1.0, -1.0, 0.2, 0.0, 0.3, 0.0],
+            )),
+            ::std::boxed::Box::new(SdfObject::new(
+                m,
+                primitives::Sphere::new(v(0.0, 0.0, 0.0), 1.0),
+                v(0.0, 0.0, 0.0),
+                1.5,
+            )),
+            ::std::boxed::Box::new(Csg::new(
+                CsgOperation::Intersection,
+                vec![
+                    ::std::boxed::Box::new(Sphere::new(m, v(0.0, 0.0, 0.0), 1.0)),
+                    ::std::boxed::Box::new(Sphere::new(m, v(1.0, 0.0, 0.0), 1.0)),
+                ],
+            )),
+            ::std::boxed::Box::new(Transform::new(
+                &sphere,
+                Matrix::rotate_z(0.5) * Matrix::scale(&v(1.0, 3.0, 1.0)),
+            )),
+        ];
+
+        for object in &objects {
+            let aabb = object.bounds().unwrap();
+            let mut hits = 0;
+            for _ in 0..2000 {
+                let org =
+                    (Vector::from((rnd(), rnd(), rnd())) - Vector::from(0.5)).normalize() * 20.0;
+                let target = Vector::from((rnd(), rnd(), rnd())) * 6.0 - Vector::from(3.0);
+                let ray = Ray::new(org, (target - org).normalize());
+                if let Some(hit) = object.intersect(&ray) {
+                    let p = hit.point;
+                    let (lo, hi) = (aabb.min - Vector::from(1e-6), aabb.max + Vector::from(1e-6));
+                    assert!(
+                        p.x >= lo.x && p.y >= lo.y && p.z >= lo.z,
+                        "{:?} outside {:?}",
+                        p,
+                        aabb
+                    );
+                    assert!(
+                        p.x <= hi.x && p.y <= hi.y && p.z <= hi.z,
+                        "{:?} outside {:?}",
+                        p,
+                        aabb
+                    );
+                    hits += 1;
+                }
+            }
+            assert!(hits > 20, "only {} hits of {:?}", hits, aabb);
+        }
+
+        let plane = Plane::new(m, v(0.0, 1.0, 0.0), 0.0);
+        assert!(plane.bounds().is_none());
+        assert!(Quadric::new(m, [0.0; 10]).bounds().is_none());
+    }
+}
@@ -7,14 +7,20 @@ pub struct Plane {
     normal: Vector,
     /// distance from origin
     distance: f64,
+    /// in-plane basis for uv coordinates
+    e1: Vector,
+    e2: Vector,
 }
 
 impl Plane {
     pub fn new(material: Surface, normal: Vector, distance: f64) -> Plane {
+        let e1 = normal.perpendicular();
         Plane {
             material,
             normal,
             distance,
+            e1,
+            e2: normal ^ e1,
         }
     }
 
@@ -27,11 +33,7 @@ impl Plane {
 
         let distance = d / normal_length;
 
-        Plane {
-            material,
-            normal,
-            distance,
-        }
+        Plane::new(material, normal, distance)
     }
 }
 
@@ -40,19 +42,39 @@ impl GObject for Plane {
         &self.material
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
         let vd = self.normal & ray.dir;
 
         if vd > -EPS && vd < EPS {
-            return false;
+            return None;
         }
 
-        *t = -((self.normal & ray.org) + self.distance) / vd;
+        let t = -((self.normal & ray.org) + self.distance) / vd;
+
+        if !ray.contains(t) {
+            return None;
+        }
+
+        let p = ray.point(t);
+        Some(Hit::new(ray, t, self.normal, p & self.e1, p & self.e2))
+    }
+
+    /// The plane bounds the half space behind its normal.
+    fn inside(&self, p: &Vector) -> bool {
+        (self.normal & *p) + self.distance < 0.0
+    }
 
-        *t > GEOMETRY_THRESHOLD
+    /// A plane is infinite, it has no bounds.
+    fn bounds(&self) -> Option<Aabb> {
+        None
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        self.normal
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("plane")),
+            ("material", Json::from(self.material)),
+            ("normal", Json::from(self.normal)),
+            ("distance", Json::from(self.distance)),
+        ]))
     }
 }
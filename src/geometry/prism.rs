@@ -0,0 +1,220 @@
+use super::*;
+
+/// Polygon swept along a straight line from `loc` to `loc + dir`, closed by
+/// caps.
+///
+/// A profile point `(a, b)` lies at `loc + a * right + b * (axis ^ right)`,
+/// with `right` made perpendicular to `dir`. The profile may be given in
+/// either winding, normals always point outward.
+pub struct Prism {
+    material: Surface,
+    loc: Vector,
+    axis: Vector,
+    e1: Vector,
+    e2: Vector,
+    len: f64,
+    profile: Vec<(f64, f64)>,
+    /// 1 for a counterclockwise profile, -1 otherwise
+    winding: f64,
+    perimeter: f64,
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Prism {
+    pub fn new(
+        material: Surface,
+        loc: Vector,
+        dir: Vector,
+        right: Vector,
+        profile: Vec<(f64, f64)>,
+    ) -> Prism {
+        assert!(
+            profile.len() >= 3,
+            "prism profile needs at least three points"
+        );
+        let len = !dir;
+        let axis = dir / len;
+        let e1 = (right - axis * (right & axis)).normalize();
+
+        let edges = || profile.iter().zip(profile.iter().cycle().skip(1));
+        let area: f64 = edges().map(|(p, q)| p.0 * q.1 - q.0 * p.1).sum();
+        let perimeter = edges().map(|(p, q)| (q.0 - p.0).hypot(q.1 - p.1)).sum();
+        let min = profile
+            .iter()
+            .fold((f64::MAX, f64::MAX), |m, p| {
+                (m.0.min(p.0), m.1.min(p.1))
+            });
+        let max = profile
+            .iter()
+            .fold((f64::MIN, f64::MIN), |m, p| {
+                (m.0.max(p.0), m.1.max(p.1))
+            });
+
+        Prism {
+            material,
+            loc,
+            axis,
+            e1,
+            e2: axis ^ e1,
+            len,
+            winding: area.signum(),
+            perimeter,
+            min,
+            max,
+            profile,
+        }
+    }
+
+    /// Even-odd test of a point in profile coordinates.
+    fn inside(&self, a: f64, b: f64) -> bool {
+        let mut inside = false;
+        let edges = self.profile.iter().zip(self.profile.iter().cycle().skip(1));
+        for (p, q) in edges {
+            if (p.1 > b) != (q.1 > b) && a < p.0 + (b - p.1) * (q.0 - p.0) / (q.1 - p.1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+impl GObject for Prism {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// On the sides `u` runs along the profile and `v` along the sweep, on
+    /// the caps they span the bounding rectangle of the profile.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let o = ray.org - self.loc;
+        let (oa, ob, oh) = (o & self.e1, o & self.e2, o & self.axis);
+        let d = ray.dir;
+        let (da, db, dh) = (d & self.e1, d & self.e2, d & self.axis);
+
+        let mut nearest: Option<(f64, Vector, f64, f64)> = None;
+        let mut closer = |t: f64, n: Vector, u: f64, v: f64| {
+            if ray.contains(t) && nearest.is_none_or(|(t_near, _, _, _)| t < t_near) {
+                nearest = Some((t, n, u, v));
+            }
+        };
+
+        let mut walked = 0.0;
+        for (p, q) in self.profile.iter().zip(self.profile.iter().cycle().skip(1)) {
+            let (ea, eb) = (q.0 - p.0, q.1 - p.1);
+            let edge = ea.hypot(eb);
+            // (oa, ob) + t (da, db) = p + w e
+            let det = db * ea - da * eb;
+            if det.abs() > 1e-12 {
+                let (pa, pb) = (p.0 - oa, p.1 - ob);
+                let t = (pb * ea - pa * eb) / det;
+                let w = (pb * da - pa * db) / det;
+                let h = oh + dh * t;
+                if (0.0..=1.0).contains(&w) && (0.0..=self.len).contains(&h) {
+                    let n = (self.e1 * eb - self.e2 * ea) * self.winding;
+                    closer(
+                        t,
+                        n.normalize(),
+                        (walked + w * edge) / self.perimeter,
+                        h / self.len,
+                    );
+                }
+            }
+            walked += edge;
+        }
+
+        if dh.abs() > 1e-12 {
+            for &(h, n) in &[(0.0, -self.axis), (self.len, self.axis)] {
+                let t = (h - oh) / dh;
+                let (a, b) = (oa + da * t, ob + db * t);
+                if self.inside(a, b) {
+                    closer(
+                        t,
+                        n,
+                        (a - self.min.0) / (self.max.0 - self.min.0),
+                        (b - self.min.1) / (self.max.1 - self.min.1),
+                    );
+                }
+            }
+        }
+
+        nearest.map(|(t, n, u, v)| Hit::new(ray, t, n, u, v))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let top = self.axis * self.len;
+        let points: Vec<Vector> = self.profile
+            .iter()
+            .map(|&(a, b)| self.loc + self.e1 * a + self.e2 * b)
+            .flat_map(|p| vec![p, p + top])
+            .collect();
+        Some(Aabb::from_points(&points))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("prism")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("dir", Json::from(self.axis * self.len)),
+            ("right", Json::from(self.e1)),
+            (
+                "profile",
+                Json::Array(
+                    self.profile
+                        .iter()
+                        .map(|&(a, b)| Json::from(vec![a, b]))
+                        .collect(),
+                ),
+            ),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn triangle(profile: Vec<(f64, f64)>) -> Prism {
+        Prism::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 0.0, 2.0)),
+            Vector::from((1.0, 0.0, 0.0)),
+            profile,
+        )
+    }
+
+    #[test]
+    fn should_intersect_sides() {
+        for profile in vec![
+            vec![(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)],
+            vec![(0.0, 2.0), (2.0, 0.0), (0.0, 0.0)],
+        ] {
+            let prism = triangle(profile);
+            let ray = Ray::from(((5.0, 0.5, 1.0), (-1.0, 0.0, 0.0)));
+            let hit = prism.intersect(&ray).unwrap();
+            assert!((hit.t - 3.5).abs() < 1e-9);
+            let n = Vector::from((1.0, 1.0, 0.0)).normalize();
+            assert!(!(hit.normal - n) < 1e-9);
+            assert!((hit.v - 0.5).abs() < 1e-9);
+
+            let ray = Ray::from(((-5.0, 0.5, 1.0), (1.0, 0.0, 0.0)));
+            let hit = prism.intersect(&ray).unwrap();
+            assert!((hit.t - 5.0).abs() < 1e-9);
+            assert_eq!(hit.normal, Vector::from((-1.0, 0.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn should_intersect_caps() {
+        let prism = triangle(vec![(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)]);
+        let ray = Ray::from(((0.5, 0.5, 5.0), (0.0, 0.0, -1.0)));
+        let hit = prism.intersect(&ray).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-9);
+        assert_eq!(hit.normal, Vector::from((0.0, 0.0, 1.0)));
+
+        let ray = Ray::from(((1.5, 1.5, 5.0), (0.0, 0.0, -1.0)));
+        assert!(prism.intersect(&ray).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::solver::solve_quadratic;
+
+/// General quadric surface
+/// `A x^2 + B y^2 + C z^2 + D xy + E xz + F yz + G x + H y + I z + J = 0`,
+/// optionally clipped to an axis aligned box.
+///
+/// The normal points to where the left hand side is positive.
+pub struct Quadric {
+    material: Surface,
+    /// coefficients `A` to `J`
+    k: [f64; 10],
+    clip: Option<(Vector, Vector)>,
+}
+
+impl Quadric {
+    pub fn new(material: Surface, coefficients: [f64; 10]) -> Quadric {
+        Quadric {
+            material,
+            k: coefficients,
+            clip: None,
+        }
+    }
+
+    /// Keeps only the part of the surface between corners `min` and `max`.
+    pub fn set_clip_box(&mut self, min: Vector, max: Vector) {
+        self.clip = Some((min, max));
+    }
+
+    fn inside_clip(&self, p: &Vector) -> bool {
+        match self.clip {
+            Some((min, max)) => {
+                p.x >= min.x && p.y >= min.y && p.z >= min.z && p.x <= max.x && p.y <= max.y
+                    && p.z <= max.z
+            }
+            None => true,
+        }
+    }
+
+    fn gradient(&self, p: &Vector) -> Vector {
+        let k = &self.k;
+        Vector::from((
+            2.0 * k[0] * p.x + k[3] * p.y + k[4] * p.z + k[6],
+            2.0 * k[1] * p.y + k[3] * p.x + k[5] * p.z + k[7],
+            2.0 * k[2] * p.z + k[4] * p.x + k[5] * p.y + k[8],
+        ))
+    }
+}
+
+impl GObject for Quadric {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// A quadric has no natural parameterization, `u` and `v` are 0.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let k = &self.k;
+        let (o, d) = (ray.org, ray.dir);
+
+        let a = k[0] * d.x * d.x + k[1] * d.y * d.y + k[2] * d.z * d.z + k[3] * d.x * d.y
+            + k[4] * d.x * d.z + k[5] * d.y * d.z;
+        let b = 2.0 * (k[0] * o.x * d.x + k[1] * o.y * d.y + k[2] * o.z * d.z)
+            + k[3] * (o.x * d.y + o.y * d.x) + k[4] * (o.x * d.z + o.z * d.x)
+            + k[5] * (o.y * d.z + o.z * d.y) + k[6] * d.x + k[7] * d.y + k[8] * d.z;
+        let c = k[0] * o.x * o.x + k[1] * o.y * o.y + k[2] * o.z * o.z + k[3] * o.x * o.y
+            + k[4] * o.x * o.z + k[5] * o.y * o.z + k[6] * o.x + k[7] * o.y + k[8] * o.z
+            + k[9];
+
+        for &t in solve_quadratic(a, b, c).iter() {
+            if !ray.contains(t) {
+                continue;
+            }
+            let p = ray.point(t);
+            if self.inside_clip(&p) {
+                return Some(Hit::new(ray, t, self.gradient(&p).normalize(), 0.0, 0.0));
+            }
+        }
+        None
+    }
+
+    /// Only the clip box bounds a quadric, without it the surface may be
+    /// infinite like a paraboloid or a hyperboloid.
+    fn bounds(&self) -> Option<Aabb> {
+        self.clip.map(|(min, max)| Aabb::new(min, max))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        let mut members = vec![
+            ("type", Json::from("quadric")),
+            ("material", Json::from(self.material)),
+            ("coefficients", Json::from(self.k.to_vec())),
+        ];
+        if let Some((min, max)) = self.clip {
+            members.push(("clip_min", Json::from(min)));
+            members.push(("clip_max", Json::from(max)));
+        }
+        Some(Json::object(members))
+    }
+}
+
+/// Axis aligned ellipsoid around `center` with semi-axes `radii`.
+pub struct Ellipsoid {
+    material: Surface,
+    center: Vector,
+    radii: Vector,
+}
+
+impl Ellipsoid {
+    pub fn new(material: Surface, center: Vector, radii: Vector) -> Ellipsoid {
+        Ellipsoid {
+            material,
+            center,
+            radii,
+        }
+    }
+}
+
+impl GObject for Ellipsoid {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        // unit sphere after scaling space by 1 / radii
+        let o = (ray.org - self.center) / self.radii;
+        let d = ray.dir / self.radii;
+
+        for &t in solve_quadratic(d & d, 2.0 * (o & d), (o & o) - 1.0).iter() {
+            if ray.contains(t) {
+                let s = o + d * t;
+                let n = (s / self.radii).normalize();
+                let u = 0.5 + s.z.atan2(s.x) / (2.0 * PI);
+                let v = s.y.clamp(-1.0, 1.0).acos() / PI;
+                return Some(Hit::new(ray, t, n, u, v));
+            }
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = Vector::from((self.radii.x.abs(), self.radii.y.abs(), self.radii.z.abs()));
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("ellipsoid")),
+            ("material", Json::from(self.material)),
+            ("center", Json::from(self.center)),
+            ("radii", Json::from(self.radii)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_intersect_clipped_paraboloid() {
+        // z = x^2 + y^2, opening towards +z, cut at z = 1
+        let mut paraboloid = Quadric::new(
+            Surface::new(),
+            [1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0],
+        );
+        paraboloid.set_clip_box(Vector::from(-1.0), Vector::from(1.0));
+
+        let ray = Ray::from(((0.5, 0.0, 5.0), (0.0, 0.0, -1.0)));
+        let hit = paraboloid.intersect(&ray).unwrap();
+        assert!((hit.t - 4.75).abs() < 1e-9);
+        assert!((hit.normal & Vector::from((0.0, 0.0, 1.0))) < 0.0);
+
+        // passes the paraboloid above the clip box
+        let ray = Ray::from(((-5.0, 0.0, 2.0), (1.0, 0.0, 0.0)));
+        assert!(paraboloid.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_intersect_ellipsoid() {
+        let ellipsoid = Ellipsoid::new(
+            Surface::new(),
+            Vector::from((0.0, 0.0, 10.0)),
+            Vector::from((1.0, 2.0, 3.0)),
+        );
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = ellipsoid.intersect(&ray).unwrap();
+        assert!((hit.t - 7.0).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+
+        let ray = Ray::from(((0.0, -5.0, 10.0), (0.0, 1.0, 0.0)));
+        let hit = ellipsoid.intersect(&ray).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-9);
+
+        let ray = Ray::from(((1.5, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        assert!(ellipsoid.intersect(&ray).is_none());
+    }
+}
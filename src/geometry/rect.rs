@@ -33,22 +33,35 @@ impl Rect {
         }
     }
 
-    pub fn intersect_uv(&self, ray: &Ray, t: &mut f64) -> Option<(f64, f64)> {
+    /// Corner `loc` and the sides `side_a` and `side_b` spanning the rect.
+    pub fn span(&self) -> (Vector, Vector, Vector) {
+        (self.loc, self.side_a, self.side_b)
+    }
+
+    /// Corners in order around the rect, starting at `loc`.
+    pub fn corners(&self) -> [Vector; 4] {
+        let (a, b) = (self.side_a, self.side_b);
+        [self.loc, self.loc + a, self.loc + a + b, self.loc + b]
+    }
+
+    /// Hit with the plane of the rect, `u` and `v` measured along `side_a`
+    /// and `side_b`.
+    pub fn intersect_uv(&self, ray: &Ray) -> Option<Hit> {
         let vd = self.normal & ray.dir;
         if vd > -EPS && vd < EPS {
             return None;
         }
 
-        *t = ((self.loc - ray.org) & self.normal) / vd;
-        if *t < GEOMETRY_THRESHOLD {
+        let t = ((self.loc - ray.org) & self.normal) / vd;
+        if !ray.contains(t) {
             return None;
         }
 
-        let p = ray.point(*t);
+        let p = ray.point(t);
         let u = self.u0 + (p & self.ku);
         let v = self.v0 + (p & self.kv);
 
-        Some((u, v))
+        Some(Hit::new(ray, t, self.normal, u, v))
     }
 }
 
@@ -57,14 +70,22 @@ impl GObject for Rect {
         &self.material
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
-        match self.intersect_uv(ray, t) {
-            Some((u, v)) => u > 0.0 && v > 0.0 && u < 1.0 && v < 1.0,
-            None => false,
-        }
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.intersect_uv(ray)
+            .filter(|hit| hit.u > 0.0 && hit.v > 0.0 && hit.u < 1.0 && hit.v < 1.0)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.corners()))
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        self.normal
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("rect")),
+            ("material", Json::from(self.material)),
+            ("loc", Json::from(self.loc)),
+            ("side_a", Json::from(self.side_a)),
+            ("side_b", Json::from(self.side_b)),
+        ]))
     }
 }
@@ -0,0 +1,232 @@
+use super::*;
+
+/// Shapes `a` and `b` together.
+pub struct Union<A: Sdf, B: Sdf> {
+    a: A,
+    b: B,
+}
+
+impl<A: Sdf, B: Sdf> Union<A, B> {
+    pub fn new(a: A, b: B) -> Union<A, B> {
+        Union { a, b }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Union<A, B> {
+    fn distance(&self, p: &Vector) -> f64 {
+        self.a.distance(p).min(self.b.distance(p))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("union")),
+            ("a", self.a.to_json()?),
+            ("b", self.b.to_json()?),
+        ]))
+    }
+}
+
+/// Union blending the shapes into each other within distance `k`.
+pub struct SmoothUnion<A: Sdf, B: Sdf> {
+    a: A,
+    b: B,
+    k: f64,
+}
+
+impl<A: Sdf, B: Sdf> SmoothUnion<A, B> {
+    pub fn new(a: A, b: B, k: f64) -> SmoothUnion<A, B> {
+        SmoothUnion { a, b, k }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+    fn distance(&self, p: &Vector) -> f64 {
+        let da = self.a.distance(p);
+        let db = self.b.distance(p);
+        // polynomial smooth minimum
+        let h = (0.5 + 0.5 * (db - da) / self.k).clamp(0.0, 1.0);
+        db + (da - db) * h - self.k * h * (1.0 - h)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("smooth_union")),
+            ("a", self.a.to_json()?),
+            ("b", self.b.to_json()?),
+            ("k", Json::from(self.k)),
+        ]))
+    }
+}
+
+/// Shape `a` with shape `b` cut out of it.
+pub struct Subtraction<A: Sdf, B: Sdf> {
+    a: A,
+    b: B,
+}
+
+impl<A: Sdf, B: Sdf> Subtraction<A, B> {
+    pub fn new(a: A, b: B) -> Subtraction<A, B> {
+        Subtraction { a, b }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Subtraction<A, B> {
+    fn distance(&self, p: &Vector) -> f64 {
+        self.a.distance(p).max(-self.b.distance(p))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("subtraction")),
+            ("a", self.a.to_json()?),
+            ("b", self.b.to_json()?),
+        ]))
+    }
+}
+
+/// Common part of shapes `a` and `b`.
+pub struct Intersection<A: Sdf, B: Sdf> {
+    a: A,
+    b: B,
+}
+
+impl<A: Sdf, B: Sdf> Intersection<A, B> {
+    pub fn new(a: A, b: B) -> Intersection<A, B> {
+        Intersection { a, b }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Intersection<A, B> {
+    fn distance(&self, p: &Vector) -> f64 {
+        self.a.distance(p).max(self.b.distance(p))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("intersection")),
+            ("a", self.a.to_json()?),
+            ("b", self.b.to_json()?),
+        ]))
+    }
+}
+
+/// Infinite copies of a shape centered at the origin, every `period` along
+/// each axis. A zero period component disables repetition along that axis.
+pub struct Repeat<S: Sdf> {
+    sdf: S,
+    period: Vector,
+}
+
+impl<S: Sdf> Repeat<S> {
+    pub fn new(sdf: S, period: Vector) -> Repeat<S> {
+        Repeat { sdf, period }
+    }
+}
+
+fn wrap(x: f64, period: f64) -> f64 {
+    if period > 0.0 {
+        x - period * (x / period).round()
+    } else {
+        x
+    }
+}
+
+impl<S: Sdf> Sdf for Repeat<S> {
+    fn distance(&self, p: &Vector) -> f64 {
+        self.sdf.distance(&Vector::from((
+            wrap(p.x, self.period.x),
+            wrap(p.y, self.period.y),
+            wrap(p.z, self.period.z),
+        )))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("repeat")),
+            ("sdf", self.sdf.to_json()?),
+            ("period", Json::from(self.period)),
+        ]))
+    }
+}
+
+/// Twists a shape around the y axis by `rate` radians per unit of height.
+///
+/// Overestimates distance, use a step scale below 1.
+pub struct Twist<S: Sdf> {
+    sdf: S,
+    rate: f64,
+}
+
+impl<S: Sdf> Twist<S> {
+    pub fn new(sdf: S, rate: f64) -> Twist<S> {
+        Twist { sdf, rate }
+    }
+}
+
+impl<S: Sdf> Sdf for Twist<S> {
+    fn distance(&self, p: &Vector) -> f64 {
+        let (sine, cosine) = (self.rate * p.y).sin_cos();
+        self.sdf.distance(&Vector::from((
+            cosine * p.x - sine * p.z,
+            p.y,
+            sine * p.x + cosine * p.z,
+        )))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("twist")),
+            ("sdf", self.sdf.to_json()?),
+            ("rate", Json::from(self.rate)),
+        ]))
+    }
+}
+
+/// Offsets the surface of a shape by `displacement(p)`, positive values
+/// push it outward.
+///
+/// Overestimates distance for steep displacements, use a step scale below 1.
+/// The displacement closure cannot be written to a scene file.
+pub struct Displace<S: Sdf, F: Fn(&Vector) -> f64> {
+    sdf: S,
+    displacement: F,
+}
+
+impl<S: Sdf, F: Fn(&Vector) -> f64> Displace<S, F> {
+    pub fn new(sdf: S, displacement: F) -> Displace<S, F> {
+        Displace { sdf, displacement }
+    }
+}
+
+impl<S: Sdf, F: Fn(&Vector) -> f64> Sdf for Displace<S, F> {
+    fn distance(&self, p: &Vector) -> f64 {
+        self.sdf.distance(p) - (self.displacement)(p)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn sphere(x: f64) -> Sphere {
+        Sphere::new(Vector::from((x, 0.0, 0.0)), 1.0)
+    }
+
+    #[test]
+    fn combinators() {
+        let p = Vector::from(0.0);
+        assert_eq!(Union::new(sphere(2.0), sphere(-3.0)).distance(&p), 1.0);
+        assert_eq!(
+            Intersection::new(sphere(2.0), sphere(-3.0)).distance(&p),
+            2.0
+        );
+        assert_eq!(Subtraction::new(sphere(0.0), sphere(0.5)).distance(&p), 0.5);
+        assert!(SmoothUnion::new(sphere(2.0), sphere(-2.0), 1.0).distance(&p) < 1.0);
+        let repeat = Repeat::new(sphere(0.0), Vector::from((4.0, 0.0, 0.0)));
+        assert_eq!(repeat.distance(&Vector::from((8.0, 0.0, 0.0))), -1.0);
+        let twist = Twist::new(sphere(0.0), 1.0);
+        assert_eq!(twist.distance(&Vector::from((0.0, 2.0, 0.0))), 1.0);
+        let displace = Displace::new(sphere(0.0), |_: &Vector| 0.5);
+        assert_eq!(displace.distance(&Vector::from((2.0, 0.0, 0.0))), 0.5);
+    }
+}
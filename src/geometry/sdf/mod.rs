@@ -0,0 +1,185 @@
+use super::*;
+
+pub mod primitives;
+pub mod combinators;
+
+pub use self::primitives::{Capsule, Cuboid, Plane, Sphere, Torus};
+pub use self::combinators::{Displace, Intersection, Repeat, SmoothUnion, Subtraction, Twist,
+                            Union};
+
+/// Signed distance function: distance from `p` to the surface, negative
+/// inside of the shape.
+///
+/// Sphere tracing relies on the value never overestimating the true
+/// distance; functions which do, like `Twist` and `Displace`, need a step
+/// scale below 1 in `SdfObject`.
+pub trait Sdf {
+    fn distance(&self, p: &Vector) -> f64;
+
+    /// Description in the native scene format, `None` if the function
+    /// cannot be written.
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
+}
+
+impl Sdf for ::std::boxed::Box<Sdf> {
+    fn distance(&self, p: &Vector) -> f64 {
+        (**self).distance(p)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        (**self).to_json()
+    }
+}
+
+const MAX_STEPS: u32 = 256;
+const EPSILON: f64 = 1e-4;
+
+/// Object rendered by sphere tracing a signed distance function.
+///
+/// The shape must lie inside the bounding sphere given by `center` and
+/// `radius`; marching only happens there.
+pub struct SdfObject<S: Sdf> {
+    material: Surface,
+    sdf: S,
+    center: Vector,
+    radius: f64,
+    step_scale: f64,
+    max_steps: u32,
+}
+
+impl<S: Sdf> SdfObject<S> {
+    pub fn new(material: Surface, sdf: S, center: Vector, radius: f64) -> SdfObject<S> {
+        SdfObject {
+            material,
+            sdf,
+            center,
+            radius,
+            step_scale: 1.0,
+            max_steps: MAX_STEPS,
+        }
+    }
+
+    /// Scales every marching step, values below 1 make marching safe for
+    /// distance functions which overestimate.
+    pub fn set_step_scale(&mut self, step_scale: f64) {
+        self.step_scale = step_scale;
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Normal as the gradient of the distance by central differences.
+    fn normal(&self, p: &Vector) -> Vector {
+        let h = EPSILON;
+        let dx = Vector::from((h, 0.0, 0.0));
+        let dy = Vector::from((0.0, h, 0.0));
+        let dz = Vector::from((0.0, 0.0, h));
+        Vector::from((
+            self.sdf.distance(&(*p + dx)) - self.sdf.distance(&(*p - dx)),
+            self.sdf.distance(&(*p + dy)) - self.sdf.distance(&(*p - dy)),
+            self.sdf.distance(&(*p + dz)) - self.sdf.distance(&(*p - dz)),
+        )).normalize()
+    }
+}
+
+impl<S: Sdf> GObject for SdfObject<S> {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    /// Distance functions have no parameterization, `u` and `v` are 0.
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        // clip the interval to the bounding sphere
+        let len = !ray.dir;
+        let l = self.center - ray.org;
+        let tca = (l & ray.dir) / len;
+        let t2hc = self.radius * self.radius - (l & l) + tca * tca;
+        if t2hc < 0.0 {
+            return None;
+        }
+        let t2hc = t2hc.sqrt();
+        let t_end = ((tca + t2hc) / len).min(ray.t_max);
+        let mut t = ((tca - t2hc) / len).max(ray.t_min);
+
+        // march towards the surface from whichever side the ray starts on
+        let side = self.sdf.distance(&ray.point(t)).signum();
+        for _ in 0..self.max_steps {
+            if t > t_end {
+                return None;
+            }
+            let p = ray.point(t);
+            let d = side * self.sdf.distance(&p);
+            if d < EPSILON {
+                return Some(Hit::new(ray, t, self.normal(&p), 0.0, 0.0));
+            }
+            t += self.step_scale * d / len;
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = Vector::from(self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("sdf")),
+            ("material", Json::from(self.material)),
+            ("sdf", self.sdf.to_json()?),
+            ("center", Json::from(self.center)),
+            ("radius", Json::from(self.radius)),
+            ("step_scale", Json::from(self.step_scale)),
+            ("max_steps", Json::from(self.max_steps as f64)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_trace_sphere() {
+        let object = SdfObject::new(
+            Surface::new(),
+            Sphere::new(Vector::from((0.0, 0.0, 10.0)), 1.0),
+            Vector::from((0.0, 0.0, 10.0)),
+            1.5,
+        );
+
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = object.intersect(&ray).unwrap();
+        assert!((hit.t - 9.0).abs() < 1e-3);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, -1.0))) < 1e-3);
+
+        let ray = Ray::from(((0.0, 0.0, 10.0), (0.0, 1.0, 0.0)));
+        let hit = object.intersect(&ray).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-3);
+        assert!(!hit.front_face);
+
+        let ray = Ray::from(((0.0, 1.2, 0.0), (0.0, 0.0, 1.0)));
+        assert!(object.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_trace_subtraction() {
+        // unit cube with a spherical bite out of its front face
+        let sdf = Subtraction::new(
+            Cuboid::new(Vector::from(0.0), Vector::from(1.0)),
+            Sphere::new(Vector::from((0.0, 0.0, -1.0)), 0.5),
+        );
+        let object = SdfObject::new(Surface::new(), sdf, Vector::from(0.0), 2.0);
+
+        let ray = Ray::from(((0.0, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        let hit = object.intersect(&ray).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-3);
+
+        let ray = Ray::from(((0.8, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        let hit = object.intersect(&ray).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-3);
+    }
+}
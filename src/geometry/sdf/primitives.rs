@@ -0,0 +1,170 @@
+use super::*;
+
+pub struct Sphere {
+    center: Vector,
+    radius: f64,
+}
+
+impl Sphere {
+    pub fn new(center: Vector, radius: f64) -> Sphere {
+        Sphere { center, radius }
+    }
+}
+
+impl Sdf for Sphere {
+    fn distance(&self, p: &Vector) -> f64 {
+        !(*p - self.center) - self.radius
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("sphere")),
+            ("center", Json::from(self.center)),
+            ("radius", Json::from(self.radius)),
+        ]))
+    }
+}
+
+/// Axis aligned box around `center` extending `half_size` in each direction.
+pub struct Cuboid {
+    center: Vector,
+    half_size: Vector,
+}
+
+impl Cuboid {
+    pub fn new(center: Vector, half_size: Vector) -> Cuboid {
+        Cuboid { center, half_size }
+    }
+}
+
+impl Sdf for Cuboid {
+    fn distance(&self, p: &Vector) -> f64 {
+        let q = *p - self.center;
+        let q = Vector::from((q.x.abs(), q.y.abs(), q.z.abs())) - self.half_size;
+        let outside = Vector::from((q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)));
+        !outside + q.x.max(q.y).max(q.z).min(0.0)
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("cuboid")),
+            ("center", Json::from(self.center)),
+            ("half_size", Json::from(self.half_size)),
+        ]))
+    }
+}
+
+/// Torus around `center` in the xz plane.
+pub struct Torus {
+    center: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+}
+
+impl Torus {
+    pub fn new(center: Vector, major_radius: f64, minor_radius: f64) -> Torus {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Sdf for Torus {
+    fn distance(&self, p: &Vector) -> f64 {
+        let q = *p - self.center;
+        let ring = (q.x * q.x + q.z * q.z).sqrt() - self.major_radius;
+        (ring * ring + q.y * q.y).sqrt() - self.minor_radius
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("torus")),
+            ("center", Json::from(self.center)),
+            ("major_radius", Json::from(self.major_radius)),
+            ("minor_radius", Json::from(self.minor_radius)),
+        ]))
+    }
+}
+
+/// Segment from `a` to `b` thickened by `radius`.
+pub struct Capsule {
+    a: Vector,
+    b: Vector,
+    radius: f64,
+}
+
+impl Capsule {
+    pub fn new(a: Vector, b: Vector, radius: f64) -> Capsule {
+        Capsule { a, b, radius }
+    }
+}
+
+impl Sdf for Capsule {
+    fn distance(&self, p: &Vector) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+        let h = ((pa & ba) / (ba & ba)).clamp(0.0, 1.0);
+        !(pa - ba * h) - self.radius
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("capsule")),
+            ("a", Json::from(self.a)),
+            ("b", Json::from(self.b)),
+            ("radius", Json::from(self.radius)),
+        ]))
+    }
+}
+
+/// Half space below the plane `(n, p) + d = 0`, `normal` must be unit.
+pub struct Plane {
+    normal: Vector,
+    distance: f64,
+}
+
+impl Plane {
+    pub fn new(normal: Vector, distance: f64) -> Plane {
+        Plane { normal, distance }
+    }
+}
+
+impl Sdf for Plane {
+    fn distance(&self, p: &Vector) -> f64 {
+        (*p & self.normal) + self.distance
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("plane")),
+            ("normal", Json::from(self.normal)),
+            ("distance", Json::from(self.distance)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let p = Vector::from((3.0, 0.0, 0.0));
+        assert_eq!(Sphere::new(Vector::from(0.0), 1.0).distance(&p), 2.0);
+        assert_eq!(
+            Cuboid::new(Vector::from(0.0), Vector::from(1.0)).distance(&p),
+            2.0
+        );
+        assert_eq!(Torus::new(Vector::from(0.0), 2.0, 0.5).distance(&p), 0.5);
+        let capsule = Capsule::new(Vector::from(0.0), Vector::from((0.0, 2.0, 0.0)), 1.0);
+        assert_eq!(capsule.distance(&p), 2.0);
+        let plane = Plane::new(Vector::from((1.0, 0.0, 0.0)), -1.0);
+        assert_eq!(plane.distance(&p), 2.0);
+        assert_eq!(
+            Cuboid::new(Vector::from(0.0), Vector::from(1.0)).distance(&Vector::from(0.0)),
+            -1.0
+        );
+    }
+}
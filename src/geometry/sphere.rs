@@ -1,5 +1,7 @@
 use super::*;
 
+use std::f64::consts::PI;
+
 pub struct Sphere {
     material: Surface,
     center: Vector,
@@ -23,35 +25,44 @@ impl GObject for Sphere {
         &self.material
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
         let l = self.center - ray.org; // direction vector
         let l20c = l & l; // squared distance
         let tca = l & ray.dir; // closest dist to center
         let t2hc = self.radius_radius - l20c + tca * tca;
 
         if t2hc < 0.0 {
-            return false;
+            return None;
         }
 
         let t2hc = t2hc.sqrt();
 
-        let t2 = if tca < t2hc {
-            *t = tca + t2hc;
+        let t = if ray.contains(tca - t2hc) {
             tca - t2hc
-        } else {
-            *t = tca - t2hc;
+        } else if ray.contains(tca + t2hc) {
             tca + t2hc
+        } else {
+            return None;
         };
 
-        if t.abs() < GEOMETRY_THRESHOLD {
-            *t = t2;
-        }
+        let n = (ray.point(t) - self.center) / self.radius;
+        let u = 0.5 + n.z.atan2(n.x) / (2.0 * PI);
+        let v = n.y.clamp(-1.0, 1.0).acos() / PI;
+        Some(Hit::new(ray, t, n, u, v))
+    }
 
-        *t > GEOMETRY_THRESHOLD
+    fn bounds(&self) -> Option<Aabb> {
+        let r = Vector::from(self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        (*p - self.center) / self.radius
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("sphere")),
+            ("material", Json::from(self.material)),
+            ("center", Json::from(self.center)),
+            ("radius", Json::from(self.radius)),
+        ]))
     }
 }
 
@@ -64,17 +75,25 @@ pub mod tests {
         let sphere = Sphere::new(Surface::new(), Vector::from((10.0, 0.0, 0.0)), 1.0);
 
         let ray = Ray::from(((0.0, 0.0, 0.0), (1.0, 0.0, 0.0)));
-        let mut dist = 30000.0;
-        assert!(sphere.intersect(&ray, &mut dist));
+        assert!(sphere.intersect(&ray).is_some());
 
         let dir = Vector::from((10.0, 1.001, 0.0)).normalize();
         let ray = Ray::new(Vector::from(0.0), dir);
-        let mut dist = 30000.0;
-        assert!(sphere.intersect(&ray, &mut dist));
+        assert!(sphere.intersect(&ray).is_some());
 
         let dir = Vector::from((10.0, 1.01, 0.0)).normalize();
         let ray = Ray::new(Vector::from(0.0), dir);
-        let mut dist = 30000.0;
-        assert!(!sphere.intersect(&ray, &mut dist));
+        assert!(sphere.intersect(&ray).is_none());
+    }
+
+    #[test]
+    fn should_intersect_from_inside() {
+        let sphere = Sphere::new(Surface::new(), Vector::from(0.0), 2.0);
+
+        let ray = Ray::from(((0.0, 0.0, 0.0), (1.0, 0.0, 0.0)));
+        let hit = sphere.intersect(&ray).unwrap();
+        assert_eq!(hit.t, 2.0);
+        assert_eq!(hit.normal, Vector::from((1.0, 0.0, 0.0)));
+        assert!(!hit.front_face);
     }
 }
@@ -0,0 +1,159 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::solver::solve_quartic;
+
+pub struct Torus {
+    material: Surface,
+    center: Vector,
+    /// local basis, `axis` is the axis of revolution
+    e1: Vector,
+    e2: Vector,
+    axis: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+    /// radius of the bounding sphere
+    outer_radius: f64,
+}
+
+impl Torus {
+    pub fn new(
+        material: Surface,
+        center: Vector,
+        axis: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+    ) -> Torus {
+        let axis = axis.normalize();
+        let e1 = axis.perpendicular();
+        Torus {
+            material,
+            center,
+            e1,
+            e2: axis ^ e1,
+            axis,
+            major_radius,
+            minor_radius,
+            outer_radius: major_radius + minor_radius,
+        }
+    }
+
+    fn to_local(&self, v: &Vector) -> Vector {
+        Vector::from((*v & self.e1, *v & self.e2, *v & self.axis))
+    }
+}
+
+impl GObject for Torus {
+    fn material(&self) -> &Surface {
+        &self.material
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let o = self.to_local(&(ray.org - self.center));
+        let d = self.to_local(&ray.dir);
+
+        // reject rays missing the bounding sphere and start the local ray
+        // close to the torus, which keeps the quartic well conditioned
+        let b = o & d;
+        let disc = b * b - (o & o) + self.outer_radius * self.outer_radius;
+        if disc <= 0.0 {
+            return None;
+        }
+        let t0 = (-b - disc.sqrt()).max(0.0);
+        let o = o + d * t0;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (px^2 + py^2) with p = o + t d, |d| = 1
+        let r2 = self.major_radius * self.major_radius;
+        let f = o & d;
+        let e = (o & o) - r2 - self.minor_radius * self.minor_radius;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + 4.0 * r2 * d.z * d.z,
+            4.0 * f * e + 8.0 * r2 * o.z * d.z,
+            e * e - 4.0 * r2 * (self.minor_radius * self.minor_radius - o.z * o.z),
+        );
+
+        let t = roots.iter().map(|&t| t + t0).find(|&t| ray.contains(t))?;
+
+        let p = o + d * (t - t0);
+        let ring = Vector::from((p.x, p.y, 0.0)).normalize() * self.major_radius;
+        let n = (p - ring).normalize();
+        let u = 0.5 + p.y.atan2(p.x) / (2.0 * PI);
+        let v = 0.5
+            + n.z
+                .atan2((p.x * p.x + p.y * p.y).sqrt() - self.major_radius)
+                / (2.0 * PI);
+        Some(Hit::new(
+            ray,
+            t,
+            self.e1 * n.x + self.e2 * n.y + self.axis * n.z,
+            u,
+            v,
+        ))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let ring = Aabb::disc(self.center, self.axis, self.major_radius);
+        Some(ring.grow(self.minor_radius))
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("torus")),
+            ("material", Json::from(self.material)),
+            ("center", Json::from(self.center)),
+            ("axis", Json::from(self.axis)),
+            ("major_radius", Json::from(self.major_radius)),
+            ("minor_radius", Json::from(self.minor_radius)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_intersect_tube_and_miss_hole() {
+        let torus = Torus::new(
+            Surface::new(),
+            Vector::from((0.0, 0.0, 10.0)),
+            Vector::from((0.0, 0.0, 1.0)),
+            2.0,
+            0.5,
+        );
+
+        // straight through the hole along the axis
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        assert!(torus.intersect(&ray).is_none());
+
+        // down onto the top of the tube
+        let ray = Ray::from(((2.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = torus.intersect(&ray).unwrap();
+        assert!((hit.t - 9.5).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+
+        // across the torus, hits outer side of the tube first
+        let ray = Ray::from(((-10.0, 0.0, 10.0), (1.0, 0.0, 0.0)));
+        let hit = torus.intersect(&ray).unwrap();
+        assert!((hit.t - 7.5).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((-1.0, 0.0, 0.0))) < 1e-9);
+    }
+
+    #[test]
+    fn should_intersect_from_inside_tube() {
+        let torus = Torus::new(
+            Surface::new(),
+            Vector::from(0.0),
+            Vector::from((0.0, 1.0, 0.0)),
+            3.0,
+            1.0,
+        );
+        let ray = Ray::from(((0.0, 0.0, 3.0), (0.0, 1.0, 0.0)));
+        let hit = torus.intersect(&ray).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-9);
+        assert!(!hit.front_face);
+    }
+}
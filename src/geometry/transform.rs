@@ -0,0 +1,262 @@
+use super::*;
+
+use std::rc::Rc;
+
+use super::super::loader::Prototypes;
+
+/// Places another object with an affine `Matrix`, optionally moving it with
+/// constant velocity over the camera shutter.
+pub struct Transform<'a> {
+    object: &'a GObject,
+    placement: Placement,
+}
+
+impl<'a> Transform<'a> {
+    pub fn new(object: &'a GObject, matrix: Matrix) -> Transform<'a> {
+        Transform {
+            object,
+            placement: Placement::new(matrix),
+        }
+    }
+
+    /// At ray time `t` the object is additionally translated by
+    /// `velocity * t`.
+    pub fn set_motion(&mut self, velocity: Vector) {
+        self.placement.velocity = velocity;
+    }
+}
+
+impl<'a> GObject for Transform<'a> {
+    fn material(&self) -> &Surface {
+        self.object.material()
+    }
+
+    fn find_texture(&self, hit: &Hit) -> Surface {
+        self.placement.find_texture(self.object, hit)
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.placement.intersect(self.object, ray)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.placement.bounds(self.object)
+    }
+
+    /// Written as an instance owning a copy of the object.
+    fn to_json(&self) -> Option<Json> {
+        self.placement
+            .to_json(("object", self.object.to_json()?), None)
+    }
+}
+
+/// Copy of a shared object placed with its own `Matrix` and optionally its
+/// own material. Instances of one object share its geometry.
+pub struct Instance<'a> {
+    object: Rc<GObject + 'a>,
+    placement: Placement,
+    material: Option<Surface>,
+    bounds: Option<Aabb>,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(object: Rc<GObject + 'a>, matrix: Matrix) -> Instance<'a> {
+        let placement = Placement::new(matrix);
+        Instance {
+            bounds: placement.bounds(&*object),
+            object,
+            placement,
+            material: None,
+        }
+    }
+
+    /// Replaces the material of the shared object for this instance.
+    pub fn set_material(&mut self, material: Surface) {
+        self.material = Some(material);
+    }
+
+    /// At ray time `t` the instance is additionally translated by
+    /// `velocity * t`.
+    pub fn set_motion(&mut self, velocity: Vector) {
+        self.placement.velocity = velocity;
+        self.bounds = self.placement.bounds(&*self.object);
+    }
+}
+
+impl<'a> GObject for Instance<'a> {
+    fn material(&self) -> &Surface {
+        match self.material {
+            Some(ref material) => material,
+            None => self.object.material(),
+        }
+    }
+
+    fn find_texture(&self, hit: &Hit) -> Surface {
+        let texture = self.placement.find_texture(&*self.object, hit);
+        match self.material {
+            Some(material) => Surface {
+                n: texture.n,
+                ..material
+            },
+            None => texture,
+        }
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.placement.intersect(&*self.object, ray)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// Written on its own the instance owns a copy of the shared object.
+    fn to_json(&self) -> Option<Json> {
+        self.placement
+            .to_json(("object", self.object.to_json()?), self.material)
+    }
+
+    fn to_json_shared(&self, prototypes: &mut Prototypes) -> Option<Json> {
+        let index = prototypes.add(&self.object)?;
+        self.placement
+            .to_json(("prototype", Json::from(index as f64)), self.material)
+    }
+}
+
+/// Matrices moving rays into the space of a placed object and its hits back.
+struct Placement {
+    matrix: Matrix,
+    /// transposed, transforms normals back to object space
+    transposed: Matrix,
+    inverse: Matrix,
+    /// inverse transposed, transforms normals to world space
+    normal_matrix: Matrix,
+    velocity: Vector,
+}
+
+impl Placement {
+    fn new(matrix: Matrix) -> Placement {
+        let mut inverse = matrix;
+        inverse.invert();
+        let mut normal_matrix = inverse;
+        normal_matrix.transpose();
+        let mut transposed = matrix;
+        transposed.transpose();
+        Placement {
+            matrix,
+            transposed,
+            inverse,
+            normal_matrix,
+            velocity: Vector::from(0.0),
+        }
+    }
+
+    fn to_object(&self, &p: &Vector, time: f64) -> Vector {
+        self.inverse * (p - self.velocity * time)
+    }
+
+    /// Moving objects sweep space over the whole shutter interval, which is
+    /// not known here, so they have no bounds.
+    fn bounds(&self, object: &GObject) -> Option<Aabb> {
+        if self.velocity != Vector::from(0.0) {
+            return None;
+        }
+        object.bounds().map(|aabb| aabb.transform(&self.matrix))
+    }
+
+    fn find_texture(&self, object: &GObject, hit: &Hit) -> Surface {
+        let mut local = *hit;
+        local.point = self.to_object(&hit.point, hit.time);
+        local.normal = self.transposed.transform_dir(&hit.normal).normalize();
+
+        let mut texture = object.find_texture(&local);
+        texture.n = self.normal_matrix.transform_dir(&texture.n).normalize();
+        texture
+    }
+
+    /// Instance of the object written as the `object` member.
+    fn to_json(&self, object: (&str, Json), material: Option<Surface>) -> Option<Json> {
+        let mut members = vec![
+            ("type", Json::from("instance")),
+            object,
+            ("matrix", Json::from(self.matrix)),
+            ("velocity", Json::from(self.velocity)),
+        ];
+        if let Some(material) = material {
+            members.push(("material", Json::from(material)));
+        }
+        Some(Json::object(members))
+    }
+
+    fn intersect(&self, object: &GObject, ray: &Ray) -> Option<Hit> {
+        let dir = self.inverse.transform_dir(&ray.dir);
+        let scale = !dir;
+        let mut local = Ray::with_time(self.to_object(&ray.org, ray.time), dir / scale, ray.time);
+        local.t_min = ray.t_min * scale;
+        local.t_max = ray.t_max * scale;
+
+        object.intersect(&local).map(|hit| {
+            let n = self.normal_matrix.transform_dir(&hit.normal).normalize();
+            Hit {
+                index: hit.index,
+                ..Hit::new(ray, hit.t / scale, n, hit.u, hit.v)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_and_moving_sphere() {
+        let sphere = Sphere::new(Surface::new(), Vector::from(0.0), 1.0);
+        let mut transform = Transform::new(
+            &sphere,
+            Matrix::scale(&Vector::from(2.0)) * Matrix::translate(&Vector::from((0.0, 0.0, 10.0))),
+        );
+
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let hit = transform.intersect(&ray).unwrap();
+        assert!((hit.t - 8.0).abs() < 1e-9);
+        assert!(!(hit.normal - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+        let n = transform.find_texture(&hit).n;
+        assert!(!(n - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+
+        transform.set_motion(Vector::from((10.0, 0.0, 0.0)));
+        assert!(transform.intersect(&ray).is_some());
+        assert!(transform
+            .intersect(&Ray::with_time(ray.org, ray.dir, 1.0))
+            .is_none());
+    }
+
+    #[test]
+    fn instances_share_object() {
+        let sphere: Rc<GObject> = Rc::new(Sphere::new(Surface::new(), Vector::from(0.0), 1.0));
+        let left = Instance::new(
+            sphere.clone(),
+            Matrix::translate(&Vector::from((-3.0, 0.0, 0.0))),
+        );
+        let mut right = Instance::new(
+            sphere.clone(),
+            Matrix::scale(&Vector::from(2.0)) * Matrix::translate(&Vector::from((3.0, 0.0, 0.0))),
+        );
+        let mut red = Surface::new();
+        red.color = Color::from((1.0, 0.0, 0.0));
+        right.set_material(red);
+        assert_eq!(Rc::strong_count(&sphere), 3);
+
+        let ray = Ray::from(((-3.0, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        assert!((left.intersect(&ray).unwrap().t - 4.0).abs() < 1e-9);
+        assert!(right.intersect(&ray).is_none());
+
+        let ray = Ray::from(((3.0, 0.0, -5.0), (0.0, 0.0, 1.0)));
+        let hit = right.intersect(&ray).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-9);
+        let texture = right.find_texture(&hit);
+        assert_eq!(texture.color, Color::from((1.0, 0.0, 0.0)));
+        assert!(!(texture.n - Vector::from((0.0, 0.0, -1.0))) < 1e-9);
+        assert_eq!(left.material().color, Color::from(0.0));
+    }
+}
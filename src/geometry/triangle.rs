@@ -17,14 +17,26 @@ impl GObject for Triangle {
         self.rect.material()
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool {
-        match self.rect.intersect_uv(ray, t) {
-            Some((u, v)) => u > 0.0 && v > 0.0 && u + v < 1.0,
-            None => false,
-        }
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.rect
+            .intersect_uv(ray)
+            .filter(|hit| hit.u > 0.0 && hit.v > 0.0 && hit.u + hit.v < 1.0)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let [a, b, _, d] = self.rect.corners();
+        Some(Aabb::from_points(&[a, b, d]))
     }
 
-    fn find_normal(&self, p: &Vector) -> Vector {
-        self.rect.find_normal(p)
+    /// Written like the rect spanning the triangle.
+    fn to_json(&self) -> Option<Json> {
+        let (loc, side_a, side_b) = self.rect.span();
+        Some(Json::object(vec![
+            ("type", Json::from("triangle")),
+            ("material", Json::from(*self.rect.material())),
+            ("loc", Json::from(loc)),
+            ("side_a", Json::from(side_a)),
+            ("side_b", Json::from(side_b)),
+        ]))
     }
 }
@@ -0,0 +1,35 @@
+use super::*;
+
+/// Ray-object intersection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// distance along the ray
+    pub t: f64,
+    pub point: Vector,
+    /// unit geometric normal, pointing out of the object
+    pub normal: Vector,
+    /// surface parameterization of the hit point
+    pub u: f64,
+    pub v: f64,
+    /// ray hits the outer side of the surface: (ray.dir, normal) < 0
+    pub front_face: bool,
+    /// time of the ray
+    pub time: f64,
+    /// part of a compound object that was hit, e.g. a mesh triangle
+    pub index: usize,
+}
+
+impl Hit {
+    pub fn new(ray: &Ray, t: f64, normal: Vector, u: f64, v: f64) -> Hit {
+        Hit {
+            t,
+            point: ray.point(t),
+            normal,
+            u,
+            v,
+            front_face: (ray.dir & normal) < 0.0,
+            time: ray.time,
+            index: 0,
+        }
+    }
+}
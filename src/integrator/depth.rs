@@ -0,0 +1,25 @@
+use super::*;
+
+/// Renders distance along the ray as grayscale: white at `near`, black at
+/// `far` and beyond.
+pub struct Depth {
+    near: f64,
+    far: f64,
+}
+
+impl Depth {
+    pub fn new(near: f64, far: f64) -> Depth {
+        Depth { near, far }
+    }
+}
+
+impl Integrator for Depth {
+    fn radiance(&self, environment: &Environment, ray: &Ray) -> Color {
+        match environment.intersect(ray) {
+            Some((_, hit)) => {
+                Color::from(1.0 - (hit.t - self.near) / (self.far - self.near)).clip()
+            }
+            None => Color::from(0.0),
+        }
+    }
+}
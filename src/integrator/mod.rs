@@ -0,0 +1,16 @@
+use super::*;
+
+pub mod whitted;
+pub mod normals;
+pub mod depth;
+pub mod occlusion;
+
+pub use self::whitted::Whitted;
+pub use self::normals::DebugNormals;
+pub use self::depth::Depth;
+pub use self::occlusion::AmbientOcclusion;
+
+/// Computes radiance arriving along a camera ray.
+pub trait Integrator {
+    fn radiance(&self, environment: &Environment, ray: &Ray) -> Color;
+}
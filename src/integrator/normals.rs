@@ -0,0 +1,29 @@
+use super::*;
+
+/// Visualizes surface normals, mapping each component from `[-1, 1]` to
+/// `[0, 1]`.
+pub struct DebugNormals;
+
+impl DebugNormals {
+    pub fn new() -> DebugNormals {
+        DebugNormals {}
+    }
+}
+
+impl Default for DebugNormals {
+    fn default() -> DebugNormals {
+        DebugNormals::new()
+    }
+}
+
+impl Integrator for DebugNormals {
+    fn radiance(&self, environment: &Environment, ray: &Ray) -> Color {
+        match environment.intersect(ray) {
+            Some((solid, hit)) => {
+                let n = solid.find_texture(&hit).n;
+                (n + Vector::from(1.0)) * 0.5
+            }
+            None => Color::from(0.0),
+        }
+    }
+}
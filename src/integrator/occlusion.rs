@@ -0,0 +1,113 @@
+use super::*;
+
+use std::f64::consts::PI;
+
+use super::super::random::rnd;
+
+/// Ambient occlusion: the cosine weighted fraction of the hemisphere above a
+/// surface point which is not blocked by geometry closer than
+/// `max_distance`.
+///
+/// Used standalone as an AO render pass, or as a multiplier on the ambient
+/// term of `Whitted`.
+#[derive(Clone, Copy)]
+pub struct AmbientOcclusion {
+    samples: u32,
+    max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: u32, max_distance: f64) -> AmbientOcclusion {
+        AmbientOcclusion {
+            samples,
+            max_distance,
+        }
+    }
+
+    /// Returns visibility of point `p` with normal `n` at ray `time`, `1.0`
+    /// when nothing occludes it and `0.0` when fully occluded.
+    pub fn visibility(
+        &self,
+        environment: &Environment,
+        &p: &Vector,
+        &n: &Vector,
+        time: f64,
+    ) -> f64 {
+        if self.samples == 0 {
+            return 1.0;
+        }
+
+        // orthonormal basis around n
+        let e1 = n.perpendicular();
+        let e2 = n ^ e1;
+
+        let mut visible = 0;
+        for _ in 0..self.samples {
+            // cosine weighted direction: uniform point on the unit disc
+            // projected up onto the hemisphere
+            let r = rnd().sqrt();
+            let phi = 2.0 * PI * rnd();
+            let z = (1.0 - r * r).max(0.0).sqrt();
+            let dir = e1 * (r * phi.cos()) + e2 * (r * phi.sin()) + n * z;
+
+            let mut ray = Ray::with_time(p, dir, time);
+            ray.t_max = self.max_distance;
+            if environment.intersect(&ray).is_none() {
+                visible += 1;
+            }
+        }
+        visible as f64 / self.samples as f64
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, environment: &Environment, ray: &Ray) -> Color {
+        let (solid, hit) = match environment.intersect(ray) {
+            Some(closest) => closest,
+            None => return Color::from(1.0),
+        };
+
+        let mut n = solid.find_texture(&hit).n;
+        if (n & ray.dir) > 0.0 {
+            n = -n;
+        }
+
+        Color::from(self.visibility(environment, &hit.point, &n, ray.time))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::super::geometry::Plane;
+
+    #[test]
+    fn open_plane_is_not_occluded() {
+        let floor = Plane::new(Surface::new(), Vector::from((0.0, 1.0, 0.0)), 0.0);
+        let mut environment = Environment::new();
+        environment.add_solid(&floor);
+
+        let ao = AmbientOcclusion::new(16, 10.0);
+        let p = Vector::from(0.0);
+        let n = Vector::from((0.0, 1.0, 0.0));
+        assert_eq!(ao.visibility(&environment, &p, &n, 0.0), 1.0);
+    }
+
+    #[test]
+    fn covered_plane_is_occluded() {
+        let floor = Plane::new(Surface::new(), Vector::from((0.0, 1.0, 0.0)), 0.0);
+        let ceiling = Plane::new(Surface::new(), Vector::from((0.0, 1.0, 0.0)), -1.0);
+        let mut environment = Environment::new();
+        environment.add_solid(&floor);
+        environment.add_solid(&ceiling);
+
+        let p = Vector::from(0.0);
+        let n = Vector::from((0.0, 1.0, 0.0));
+        assert!(AmbientOcclusion::new(16, 100.0).visibility(&environment, &p, &n, 0.0) < 0.5);
+        assert_eq!(
+            AmbientOcclusion::new(16, 0.5).visibility(&environment, &p, &n, 0.0),
+            1.0
+        );
+    }
+}
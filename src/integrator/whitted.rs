@@ -0,0 +1,197 @@
+use super::*;
+
+use super::super::mediums::AIR;
+
+const AMBIENT: Vector = Vector {
+    x: 1.0,
+    y: 1.0,
+    z: 1.0,
+};
+
+/// Classic recursive ray tracing: Phong direct lighting, shadows, mirror
+/// reflection and refraction.
+pub struct Whitted {
+    occlusion: Option<AmbientOcclusion>,
+}
+
+struct TraceState {
+    level: u32,
+}
+
+impl Whitted {
+    pub fn new() -> Whitted {
+        Whitted { occlusion: None }
+    }
+
+    /// Attenuates the ambient term by ambient occlusion computed with
+    /// `samples` rays up to `max_distance`.
+    pub fn with_ambient_occlusion(samples: u32, max_distance: f64) -> Whitted {
+        Whitted {
+            occlusion: Some(AmbientOcclusion::new(samples, max_distance)),
+        }
+    }
+
+    pub fn trace(
+        &self,
+        environment: &Environment,
+        current_medium: &Medium,
+        weight: f64,
+        ray: &Ray,
+    ) -> Color {
+        self.trace_state(
+            environment,
+            &mut TraceState { level: 0 },
+            current_medium,
+            weight,
+            ray,
+        )
+    }
+
+    fn trace_state(
+        &self,
+        environment: &Environment,
+        trace_state: &mut TraceState,
+        current_medium: &Medium,
+        weight: f64,
+        ray: &Ray,
+    ) -> Color {
+        let color: Color;
+
+        trace_state.level += 1;
+        {
+            let mut stats = environment.stats();
+            stats.max_depth = stats.max_depth.max(trace_state.level);
+        }
+
+        if let Some((solid, hit)) = environment.intersect(ray) {
+            color = self.shade(
+                environment,
+                trace_state,
+                current_medium,
+                weight,
+                ray,
+                (solid, &hit),
+            )
+        } else {
+            color = environment.shade_background(ray);
+        }
+        trace_state.level -= 1;
+        color
+    }
+
+    fn shade(
+        &self,
+        environment: &Environment,
+        trace_state: &mut TraceState,
+        current_medium: &Medium,
+        weight: f64,
+        view_ray: &Ray,
+        (solid, hit): (&GObject, &Hit),
+    ) -> Color {
+        let mut entering = true; // flag whether we're entering or leaving object
+
+        let p = hit.point;
+        let view = view_ray.dir;
+        let time = view_ray.time;
+
+        let mut texture = solid.find_texture(hit);
+
+        let mut vn = view & texture.n; // force (-view, n) > 0
+        if vn > 0.0 {
+            texture.n = -texture.n;
+            vn = -vn;
+            entering = false;
+        }
+
+        // since all rays will be cast from here
+        let mut ray = Ray::with_time(p, Vector::from(0.0), time);
+
+        let threshold = environment.threshold();
+
+        let mut color = AMBIENT * texture.color * texture.k_a; // get ambient light
+
+        if let Some(ref occlusion) = self.occlusion {
+            if texture.k_a > threshold {
+                color *= occlusion.visibility(environment, &p, &texture.n, time);
+            }
+        }
+
+        for light in environment.lights() {
+            let mut l = Vector::from(0.0); // light vector
+            let shadow = light.shadow(&p, &mut l, environment, time); // light shadow coeff.
+            if shadow > threshold {
+                let ln = l & texture.n;
+                // if light is visible
+                if ln > threshold {
+                    // compute direct diffuse light
+                    if texture.k_d > threshold {
+                        color += *light.color() * texture.color * (texture.k_d * shadow * ln);
+                    }
+
+                    // compute direct specular light, via Phong shading
+                    if texture.k_s > threshold {
+                        // compute half-vector between -view and light vector
+                        let h = (l - view).normalize();
+                        color += *light.color()
+                            * (texture.k_s * shadow * (texture.n & h).powi(texture.p));
+                    }
+                }
+            }
+        }
+
+        if trace_state.level >= environment.max_level() {
+            return color;
+        }
+
+        // check for reflected ray
+        let r_weight = weight * texture.k_r; // weight of reflected ray
+        if r_weight > threshold {
+            // get reflected ray direction
+            ray.dir = view - texture.n * (2.0 * vn);
+            environment.stats().reflection_rays += 1;
+            color += texture.k_r
+                * self.trace_state(environment, trace_state, current_medium, r_weight, &ray);
+        }
+
+        // check for transmitted ray
+        let t_weight = weight * texture.k_t; // weight of transmitted ray
+        if t_weight > threshold {
+            // relative index of refraction
+            let eta = current_medium.n_refr / if entering {
+                texture.medium.n_refr
+            } else {
+                AIR.n_refr
+            };
+            let ci = -vn; // cosine of incedent angle
+            let ct_square = 1.0 + eta * eta * (ci * ci - 1.0); // square cosine of transm. angle
+
+            // not a Total Internal Reflection
+            if ct_square > threshold {
+                ray.dir = view * eta + texture.n * (eta * ci - ct_square.sqrt());
+                environment.stats().refraction_rays += 1;
+                let medium = &if entering {
+                    // ray enters object (texture.medium)
+                    texture.medium
+                } else {
+                    // ray leaves object (AIR)
+                    AIR
+                };
+                color += texture.k_t
+                    * self.trace_state(environment, trace_state, medium, t_weight, &ray);
+            }
+        }
+        color
+    }
+}
+
+impl Default for Whitted {
+    fn default() -> Whitted {
+        Whitted::new()
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, environment: &Environment, ray: &Ray) -> Color {
+        self.trace(environment, &AIR, 1.0, ray)
+    }
+}
@@ -1,5 +1,7 @@
 pub mod vector;
 pub mod ray;
+pub mod hit;
+pub mod solver;
 pub mod matrix;
 pub mod tracer;
 pub mod render;
@@ -9,16 +11,31 @@ pub mod mediums;
 pub mod light;
 pub mod surface;
 pub mod environment;
+pub mod camera;
+pub mod integrator;
+pub mod random;
+pub mod aabb;
+pub mod loader;
+pub mod scene;
+pub mod accelerator;
+pub mod stats;
 
 pub use vector::Vector;
 pub use ray::Ray;
+pub use hit::Hit;
 pub use matrix::Matrix;
 pub use tracer::{GObject, LightSource};
-pub use render::{render_scene, render_scene_supersampling_grid,
-                 render_scene_supersampling_grid_adaptive};
+pub use render::{render_scene, render_scene_progressive, render_scene_supersampling_grid,
+                 render_scene_supersampling_grid_adaptive, ProgressiveTarget, RenderOptions};
 pub use mediums::Medium;
 pub use surface::Surface;
 pub use environment::Environment;
+pub use integrator::Integrator;
+pub use camera::Camera;
+pub use aabb::Aabb;
+pub use scene::{RenderSettings, Scene};
+pub use accelerator::Accelerator;
+pub use stats::RenderStats;
 
 pub type Color = Vector;
 
@@ -0,0 +1,62 @@
+use super::*;
+
+/// Parallel light shining along `direction` from infinitely far away, like
+/// sunlight. It does not fall off with distance.
+pub struct DirectionalLight {
+    color: Color,
+    direction: Vector,
+}
+
+impl DirectionalLight {
+    pub fn new(color: Color, direction: Vector) -> DirectionalLight {
+        DirectionalLight {
+            color,
+            direction: direction.normalize(),
+        }
+    }
+}
+
+impl LightSource for DirectionalLight {
+    fn color(&self) -> &Vector {
+        &self.color
+    }
+
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("directional")),
+            ("color", Json::from(self.color)),
+            ("direction", Json::from(self.direction)),
+        ]))
+    }
+
+    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment, time: f64) -> f64 {
+        *l = -self.direction;
+        self.shadow_trace(&p, l, environment, INFINITY, 1.0, time)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::super::geometry::Plane;
+
+    #[test]
+    fn should_cast_parallel_shadows() {
+        let light = DirectionalLight::new(Color::from(1.0), Vector::from((0.0, -1.0, 0.0)));
+        let roof = Plane::new(Surface::new(), Vector::from((0.0, 1.0, 0.0)), -100.0);
+        let mut environment = Environment::new();
+        let mut l = Vector::from(0.0);
+        assert_eq!(
+            light.shadow(&Vector::from(0.0), &mut l, &environment, 0.0),
+            1.0
+        );
+        assert_eq!(l, Vector::from((0.0, 1.0, 0.0)));
+
+        environment.add_solid(&roof);
+        assert_eq!(
+            light.shadow(&Vector::from(0.0), &mut l, &environment, 0.0),
+            0.0
+        );
+    }
+}
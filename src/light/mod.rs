@@ -1,9 +1,13 @@
 use super::*;
 
+use super::loader::Json;
+
 pub mod point;
 pub mod spheric;
 pub mod spot;
+pub mod directional;
 
 pub use self::point::PointLight;
 pub use self::spheric::SphericLight;
-pub use self::spot::SpotLight;
\ No newline at end of file
+pub use self::spot::SpotLight;
+pub use self::directional::DirectionalLight;
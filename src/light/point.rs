@@ -21,7 +21,16 @@ impl LightSource for PointLight {
         &self.color
     }
 
-    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment) -> f64 {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("point")),
+            ("color", Json::from(self.color)),
+            ("center", Json::from(self.center)),
+            ("distance_scale", Json::from(self.distance_scale)),
+        ]))
+    }
+
+    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment, time: f64) -> f64 {
         *l = self.center - p; // vector to light source
 
         let distance = !*l; // distance to light source
@@ -31,6 +40,6 @@ impl LightSource for PointLight {
 
         let attenuation = attenuation * attenuation; // distance attenuation is prop. to squared dist.
 
-        self.shadow_trace(&p, l, environment, distance, attenuation)
+        self.shadow_trace(&p, l, environment, distance, attenuation, time)
     }
 }
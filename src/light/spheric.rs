@@ -23,7 +23,17 @@ impl LightSource for SphericLight {
         &self.color
     }
 
-    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment) -> f64 {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("spheric")),
+            ("color", Json::from(self.color)),
+            ("center", Json::from(self.center)),
+            ("radius", Json::from(self.radius)),
+            ("distance_scale", Json::from(self.distance_scale)),
+        ]))
+    }
+
+    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment, time: f64) -> f64 {
         *l = self.center - p + environment.random_vector() * self.radius;
 
         let distance = !*l; // distance to light source
@@ -31,6 +41,6 @@ impl LightSource for SphericLight {
 
         *l /= distance; // normalize vector
 
-        self.shadow_trace(&p, l, environment, distance, attenuation)
+        self.shadow_trace(&p, l, environment, distance, attenuation, time)
     }
 }
@@ -38,7 +38,23 @@ impl LightSource for SpotLight {
         &self.color
     }
 
-    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment) -> f64 {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::object(vec![
+            ("type", Json::from("spot")),
+            ("color", Json::from(self.color)),
+            ("center", Json::from(self.center)),
+            ("direction", Json::from(self.direction)),
+            ("cone_angle", Json::from(self.cone_angle)),
+            ("end_cone_angle", Json::from(self.end_cone_angle)),
+            (
+                "beam_distribution",
+                Json::from(self.beam_distribution as f64),
+            ),
+            ("distance_scale", Json::from(self.distance_scale)),
+        ]))
+    }
+
+    fn shadow(&self, &p: &Vector, l: &mut Vector, environment: &Environment, time: f64) -> f64 {
         *l = self.center - p; // vector to light source
 
         let distance = !*l; // distance to light source
@@ -61,6 +77,6 @@ impl LightSource for SpotLight {
 
         attenuation *= attenuation * f1 * f2;
 
-        self.shadow_trace(&p, l, environment, distance, attenuation)
+        self.shadow_trace(&p, l, environment, distance, attenuation, time)
     }
 }
@@ -0,0 +1,699 @@
+use super::*;
+
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::super::camera::{Orthographic, Perspective};
+use super::super::geometry::{Instance, Mesh};
+use super::super::light::{DirectionalLight, PointLight, SpotLight};
+
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Loads a `.gltf` or `.glb` file, external buffers are read relative to it.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> io::Result<Scene> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    read_gltf(&data, path.parent())
+}
+
+/// Reads a glTF 2.0 asset, as JSON or binary GLB.
+///
+/// Every triangle primitive becomes a `Mesh` shared by `Instance`s placed by
+/// the node hierarchy. Metallic-roughness materials are approximated with
+/// `Surface` coefficients. The first camera and all `KHR_lights_punctual`
+/// lights are taken over. glTF is right handed, the scene is mirrored along
+/// z into the left handed frame of the tracer.
+///
+/// Buffers with relative URIs are read from the `base` directory.
+pub fn read_gltf(data: &[u8], base: Option<&Path>) -> io::Result<Scene> {
+    let (text, bin) = if data.starts_with(b"glTF") {
+        split_glb(data)?
+    } else {
+        (data, None)
+    };
+    let text = ::std::str::from_utf8(text).map_err(|_| invalid("glTF JSON is not UTF-8"))?;
+    let doc = Json::parse(text)?;
+    let buffers = load_buffers(&doc, bin, base)?;
+    let gltf = Gltf { doc: &doc, buffers };
+
+    let meshes = items(&doc, "meshes")
+        .iter()
+        .map(|mesh| gltf.mesh(mesh))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let nodes = items(&doc, "nodes");
+    let roots: Vec<usize> = match doc.get("scenes").and_then(Json::as_array) {
+        Some(scenes) if !scenes.is_empty() => {
+            let scene = doc.get("scene").and_then(Json::as_usize).unwrap_or(0);
+            scenes
+                .get(scene)
+                .map(|scene| indices(scene, "nodes"))
+                .ok_or_else(|| invalid("glTF scene index out of range"))?
+        }
+        _ => {
+            // without scenes every node that is no child is a root
+            let children: Vec<usize> = nodes.iter().flat_map(|n| indices(n, "children")).collect();
+            (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+        }
+    };
+
+    let mut scene = Scene::new();
+    let mut stack: Vec<(usize, Matrix, usize)> = roots
+        .into_iter()
+        .map(|root| (root, Matrix::mirror_z(), 0))
+        .collect();
+    while let Some((index, parent, depth)) = stack.pop() {
+        let node = nodes
+            .get(index)
+            .ok_or_else(|| invalid("glTF node index out of range"))?;
+        if depth > nodes.len() {
+            return Err(invalid("glTF node hierarchy has a cycle"));
+        }
+        let world = node_matrix(node) * parent;
+
+        if let Some(mesh) = node.get("mesh").and_then(Json::as_usize) {
+            let primitives = meshes
+                .get(mesh)
+                .ok_or_else(|| invalid("glTF mesh index out of range"))?;
+            for primitive in primitives {
+                scene
+                    .objects
+                    .push(Box::new(Instance::new(primitive.clone(), world)));
+            }
+        }
+        if let Some(camera) = node.get("camera").and_then(Json::as_usize) {
+            if scene.camera.is_none() {
+                scene.camera = Some(gltf.camera(camera, &world)?);
+            }
+        }
+        if let Some(light) = node
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|e| e.get("light"))
+            .and_then(Json::as_usize)
+        {
+            scene.lights.push(gltf.light(light, &world)?);
+        }
+
+        for child in indices(node, "children") {
+            stack.push((child, world, depth + 1));
+        }
+    }
+    Ok(scene)
+}
+
+/// Size in bytes, reader and largest integer of an accessor component type.
+type Component = (usize, fn(&[u8]) -> f64, f64);
+
+struct Gltf<'a> {
+    doc: &'a Json,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl<'a> Gltf<'a> {
+    fn item(&self, list: &str, index: usize) -> io::Result<&'a Json> {
+        items(self.doc, list)
+            .get(index)
+            .ok_or_else(|| invalid(&format!("glTF {} index out of range", list)))
+    }
+
+    /// Values of an accessor, and the number of components per element.
+    fn accessor(&self, index: usize) -> io::Result<(Vec<f64>, usize)> {
+        let accessor = self.item("accessors", index)?;
+        if accessor.get("sparse").is_some() {
+            return Err(invalid("sparse glTF accessors are not supported"));
+        }
+        let count = accessor
+            .get("count")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| invalid("glTF accessor without count"))?;
+        let width = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT4") => 16,
+            _ => return Err(invalid("unsupported glTF accessor type")),
+        };
+        let component = accessor
+            .get("componentType")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+        let (size, read, max): Component = match component {
+            5120 => (1, |b| b[0] as i8 as f64, 127.0),
+            5121 => (1, |b| b[0] as f64, 255.0),
+            5122 => (2, |b| i16::from_le_bytes([b[0], b[1]]) as f64, 32767.0),
+            5123 => (2, |b| u16::from_le_bytes([b[0], b[1]]) as f64, 65535.0),
+            5125 => (
+                4,
+                |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                1.0,
+            ),
+            5126 => (
+                4,
+                |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                1.0,
+            ),
+            _ => return Err(invalid("unsupported glTF component type")),
+        };
+        let scale = match accessor.get("normalized").and_then(Json::as_bool) {
+            Some(true) => 1.0 / max,
+            _ => 1.0,
+        };
+
+        // without a buffer view the values are zeros refined by sparse
+        // storage, which is not supported either
+        let view = accessor
+            .get("bufferView")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| invalid("glTF accessor without buffer view"))?;
+        let view = self.item("bufferViews", view)?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Json::as_usize)
+            .and_then(|b| self.buffers.get(b))
+            .ok_or_else(|| invalid("glTF buffer view without buffer"))?;
+        let outside = || invalid("glTF accessor outside of its buffer");
+        let offset = view
+            .get("byteOffset")
+            .and_then(Json::as_usize)
+            .unwrap_or(0)
+            .checked_add(
+                accessor
+                    .get("byteOffset")
+                    .and_then(Json::as_usize)
+                    .unwrap_or(0),
+            )
+            .ok_or_else(outside)?;
+        // elements must not overlap, a missing stride packs them tightly
+        let stride = match view.get("byteStride").and_then(Json::as_usize) {
+            None | Some(0) => width * size,
+            Some(stride) if stride < width * size => {
+                return Err(invalid("glTF byte stride shorter than an element"))
+            }
+            Some(stride) => stride,
+        };
+
+        // the count is untrusted, check it against the buffer before
+        // allocating
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|last| last.checked_add(offset))
+                .and_then(|last| last.checked_add(width * size)),
+            None => Some(offset),
+        };
+        if end.is_none_or(|end| end > buffer.len()) {
+            return Err(outside());
+        }
+
+        let capacity = count.checked_mul(width).ok_or_else(outside)?;
+        let mut values = Vec::with_capacity(capacity);
+        for i in 0..count {
+            for k in 0..width {
+                let at = offset + i * stride + k * size;
+                values.push(read(&buffer[at..at + size]) * scale);
+            }
+        }
+        Ok((values, width))
+    }
+
+    /// Meshes of the triangle primitives of a glTF mesh.
+    fn mesh(&self, mesh: &Json) -> io::Result<Vec<Rc<GObject>>> {
+        let mut primitives: Vec<Rc<GObject>> = Vec::new();
+        for primitive in items(mesh, "primitives") {
+            // points, lines and strips are skipped
+            if primitive.get("mode").and_then(Json::as_usize).unwrap_or(4) != 4 {
+                continue;
+            }
+            let attribute = |name: &str| {
+                primitive
+                    .get("attributes")
+                    .and_then(|a| a.get(name))
+                    .and_then(Json::as_usize)
+            };
+
+            let position =
+                attribute("POSITION").ok_or_else(|| invalid("glTF primitive without positions"))?;
+            let (positions, width) = self.accessor(position)?;
+            if width != 3 {
+                return Err(invalid("glTF positions are not VEC3"));
+            }
+            let vertices: Vec<Vector> = positions
+                .chunks(3)
+                .map(|p| Vector::from((p[0], p[1], p[2])))
+                .collect();
+
+            let indices: Vec<usize> = match primitive.get("indices").and_then(Json::as_usize) {
+                Some(indices) => self
+                    .accessor(indices)?
+                    .0
+                    .into_iter()
+                    .map(|i| i as usize)
+                    .collect(),
+                None => (0..vertices.len()).collect(),
+            };
+            if indices.iter().any(|&i| i >= vertices.len()) {
+                return Err(invalid("glTF index refers to a missing vertex"));
+            }
+            let triangles = indices
+                .chunks(3)
+                .filter(|tri| tri.len() == 3)
+                .map(|tri| [tri[0], tri[1], tri[2]])
+                .collect();
+
+            let material = match primitive.get("material").and_then(Json::as_usize) {
+                Some(material) => surface(Some(self.item("materials", material)?)),
+                None => surface(None),
+            };
+            let count = vertices.len();
+            let mut object = Mesh::new(material, vertices, triangles);
+
+            if let Some(normal) = attribute("NORMAL") {
+                let (normals, width) = self.accessor(normal)?;
+                if width != 3 {
+                    return Err(invalid("glTF normals are not VEC3"));
+                }
+                if normals.len() == 3 * count {
+                    object.set_normals(
+                        normals
+                            .chunks(3)
+                            .map(|n| Vector::from((n[0], n[1], n[2])))
+                            .collect(),
+                    );
+                }
+            }
+            if let Some(color) = attribute("COLOR_0") {
+                let (colors, width) = self.accessor(color)?;
+                if width >= 3 && colors.len() == width * count {
+                    object.set_colors(
+                        colors
+                            .chunks(width)
+                            .map(|c| Color::from((c[0], c[1], c[2])))
+                            .collect(),
+                    );
+                }
+            }
+            primitives.push(Rc::new(object));
+        }
+        Ok(primitives)
+    }
+
+    fn camera(&self, index: usize, world: &Matrix) -> io::Result<Box<Camera>> {
+        let camera = self.item("cameras", index)?;
+        let eye = *world * Vector::from(0.0);
+        let dir = world.transform_dir(&Vector::from((0.0, 0.0, -1.0)));
+        let up = world.transform_dir(&Vector::from((0.0, 1.0, 0.0)));
+        let number = |group: &str, key: &str| {
+            camera
+                .get(group)
+                .and_then(|g| g.get(key))
+                .and_then(Json::as_f64)
+        };
+        match camera.get("type").and_then(Json::as_str) {
+            Some("perspective") => {
+                let yfov = number("perspective", "yfov")
+                    .ok_or_else(|| invalid("glTF camera without yfov"))?;
+                let aspect = number("perspective", "aspectRatio").unwrap_or(1.0);
+                let fov = 2.0 * ((0.5 * yfov).tan() * aspect).atan();
+                Ok(Box::new(Perspective::new(eye, dir, up, fov, aspect)))
+            }
+            Some("orthographic") => {
+                let xmag = number("orthographic", "xmag").unwrap_or(1.0);
+                let ymag = number("orthographic", "ymag").unwrap_or(1.0);
+                Ok(Box::new(Orthographic::new(
+                    eye,
+                    dir,
+                    up,
+                    2.0 * xmag,
+                    2.0 * ymag,
+                )))
+            }
+            _ => Err(invalid("unknown glTF camera type")),
+        }
+    }
+
+    /// Lights fall off with the squared distance like glTF lights, the
+    /// intensity scales the falloff distance.
+    fn light(&self, index: usize, world: &Matrix) -> io::Result<Box<LightSource>> {
+        let light = self
+            .doc
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|e| e.get("lights"))
+            .and_then(Json::as_array)
+            .and_then(|lights| lights.get(index))
+            .ok_or_else(|| invalid("glTF light index out of range"))?;
+        let color = vector(light.get("color"), 1.0);
+        let intensity = light.get("intensity").and_then(Json::as_f64).unwrap_or(1.0);
+        let position = *world * Vector::from(0.0);
+        let dir = world
+            .transform_dir(&Vector::from((0.0, 0.0, -1.0)))
+            .normalize();
+
+        match light.get("type").and_then(Json::as_str) {
+            Some("point") => Ok(Box::new(PointLight::new(color, position, intensity.sqrt()))),
+            Some("spot") => {
+                let cone = |key: &str, default: f64| {
+                    light
+                        .get("spot")
+                        .and_then(|s| s.get(key))
+                        .and_then(Json::as_f64)
+                        .unwrap_or(default)
+                };
+                Ok(Box::new(SpotLight::new(
+                    color,
+                    position,
+                    dir,
+                    cone("innerConeAngle", 0.0).cos(),
+                    cone("outerConeAngle", ::std::f64::consts::FRAC_PI_4).cos(),
+                    0,
+                    intensity.sqrt(),
+                )))
+            }
+            Some("directional") => Ok(Box::new(DirectionalLight::new(color * intensity, dir))),
+            _ => Err(invalid("unknown glTF light type")),
+        }
+    }
+}
+
+/// Approximates a metallic-roughness material: smooth metals reflect, rough
+/// ones and dielectrics are diffuse, the roughness sets the highlight.
+fn surface(material: Option<&Json>) -> Surface {
+    let pbr = material.and_then(|m| m.get("pbrMetallicRoughness"));
+    let factor = |key: &str| {
+        pbr.and_then(|p| p.get(key))
+            .and_then(Json::as_f64)
+            .unwrap_or(1.0)
+    };
+    let base = pbr
+        .and_then(|p| p.get("baseColorFactor"))
+        .and_then(Json::as_f64s)
+        .filter(|c| c.len() == 4)
+        .unwrap_or_else(|| vec![1.0; 4]);
+    let metallic = factor("metallicFactor");
+    let roughness = factor("roughnessFactor");
+    let extension = |name: &str, key: &str| {
+        material
+            .and_then(|m| m.get("extensions"))
+            .and_then(|e| e.get(name))
+            .and_then(|e| e.get(key))
+            .and_then(Json::as_f64)
+    };
+    let blend = material
+        .and_then(|m| m.get("alphaMode"))
+        .and_then(Json::as_str)
+        == Some("BLEND");
+    let transmission = extension("KHR_materials_transmission", "transmissionFactor")
+        .unwrap_or(if blend { 1.0 - base[3] } else { 0.0 });
+
+    let mut surface = Surface::new();
+    surface.color = Color::from((base[0], base[1], base[2]));
+    surface.k_r = metallic * (1.0 - roughness);
+    surface.k_d = (1.0 - surface.k_r) * (1.0 - transmission);
+    surface.k_s = 1.0 - roughness;
+    surface.k_t = transmission;
+    let alpha = roughness * roughness;
+    surface.p = (2.0 / (alpha * alpha).max(1e-3) - 2.0).clamp(1.0, 1000.0) as i32;
+    surface.medium = Medium {
+        n_refr: extension("KHR_materials_ior", "ior").unwrap_or(1.5),
+        betta: 0.0,
+    };
+    surface
+}
+
+fn node_matrix(node: &Json) -> Matrix {
+    if let Some(m) = node.get("matrix").and_then(Json::as_f64s) {
+        if m.len() == 16 {
+            // column major with column vectors is row major with row vectors
+            return Matrix([
+                [m[0], m[1], m[2], m[3]],
+                [m[4], m[5], m[6], m[7]],
+                [m[8], m[9], m[10], m[11]],
+                [m[12], m[13], m[14], m[15]],
+            ]);
+        }
+    }
+    let q = node
+        .get("rotation")
+        .and_then(Json::as_f64s)
+        .filter(|q| q.len() == 4)
+        .unwrap_or_else(|| vec![0.0, 0.0, 0.0, 1.0]);
+    let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+    let rotation = Matrix([
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + z * w),
+            2.0 * (x * z - y * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * y - z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z + x * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * z + y * w),
+            2.0 * (y * z - x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    Matrix::scale(&vector(node.get("scale"), 1.0))
+        * rotation
+        * Matrix::translate(&vector(node.get("translation"), 0.0))
+}
+
+fn vector(json: Option<&Json>, default: f64) -> Vector {
+    match json.and_then(Json::as_f64s) {
+        Some(ref v) if v.len() >= 3 => Vector::from((v[0], v[1], v[2])),
+        _ => Vector::from(default),
+    }
+}
+
+fn items<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+fn indices(json: &Json, key: &str) -> Vec<usize> {
+    items(json, key).iter().filter_map(Json::as_usize).collect()
+}
+
+/// JSON and binary chunk of a GLB container.
+fn split_glb(data: &[u8]) -> io::Result<(&[u8], Option<&[u8]>)> {
+    let word = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if word(4) != Some(2) {
+        return Err(invalid("unsupported GLB version"));
+    }
+    let (mut json, mut bin) = (None, None);
+    let mut pos = 12;
+    while let (Some(length), Some(kind)) = (word(pos), word(pos + 4)) {
+        let start = pos + 8;
+        let chunk = data
+            .get(start..start + length as usize)
+            .ok_or_else(|| invalid("truncated GLB chunk"))?;
+        match kind {
+            CHUNK_JSON if json.is_none() => json = Some(chunk),
+            CHUNK_BIN if bin.is_none() => bin = Some(chunk),
+            _ => {}
+        }
+        pos = start + length as usize;
+    }
+    json.map(|json| (json, bin))
+        .ok_or_else(|| invalid("GLB without JSON chunk"))
+}
+
+fn load_buffers(doc: &Json, bin: Option<&[u8]>, base: Option<&Path>) -> io::Result<Vec<Vec<u8>>> {
+    items(doc, "buffers")
+        .iter()
+        .map(|buffer| match buffer.get("uri").and_then(Json::as_str) {
+            None => bin
+                .map(|b| b.to_vec())
+                .ok_or_else(|| invalid("glTF buffer without data")),
+            Some(uri) if uri.starts_with("data:") => match uri.find(',') {
+                Some(comma) if uri[..comma].ends_with(";base64") => {
+                    decode_base64(&uri[comma + 1..])
+                }
+                _ => Err(invalid("glTF data URI is not base64")),
+            },
+            Some(uri) => match base {
+                Some(dir) => fs::read(dir.join(uri)),
+                None => Err(invalid(
+                    "glTF buffer refers to a file without base directory",
+                )),
+            },
+        })
+        .collect()
+}
+
+fn decode_base64(text: &str) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if (c as char).is_whitespace() => continue,
+            _ => return Err(invalid("bad base64 data")),
+        };
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Triangle (0, 0, 0), (1, 0, 0), (0, 1, 0) with indices.
+    const TRIANGLE: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA=";
+
+    fn document(buffer: &str) -> String {
+        format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [0, 3]}}],
+  "nodes": [
+    {{"translation": [0, 0, -5], "children": [1, 2]}},
+    {{"mesh": 0}},
+    {{"mesh": 0, "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 10,0,0,1]}},
+    {{"camera": 0, "translation": [0.25, 0.25, 5],
+      "extensions": {{"KHR_lights_punctual": {{"light": 0}}}}}}
+  ],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1,
+                                "material": 0}}]}}],
+  "materials": [{{"pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1],
+                                           "metallicFactor": 0}}}}],
+  "cameras": [{{"type": "perspective", "perspective": {{"yfov": 1.0, "znear": 0.1}}}}],
+  "extensions": {{"KHR_lights_punctual": {{"lights": [{{"type": "point", "intensity": 4}}]}}}},
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 0, "byteOffset": 36, "componentType": 5123, "count": 3,
+      "type": "SCALAR"}}
+  ],
+  "bufferViews": [{{"buffer": 0, "byteLength": 44}}],
+  "buffers": [{{"byteLength": 44{}}}]
+}}"#,
+            buffer
+        )
+    }
+
+    fn check(scene: &Scene) {
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.lights.len(), 1);
+
+        // the camera looks down -z in glTF, +z after mirroring
+        let environment = scene.environment();
+        let ray = environment.camera(0.0, 0.0);
+        assert!(!(ray.org - Vector::from((0.25, 0.25, -5.0))) < 1e-9);
+        assert!(!(ray.dir - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+        let (object, hit) = environment.intersect(&ray).unwrap();
+        assert!((hit.t - 10.0).abs() < 1e-9);
+        assert!(hit.front_face);
+        let texture = object.find_texture(&hit);
+        assert_eq!(texture.color, Color::from((1.0, 0.0, 0.0)));
+        assert!(texture.k_d > 0.9);
+
+        // camera right is glTF +x
+        let right = environment.camera(1.0, 0.0);
+        assert!(right.dir.x > 0.0);
+
+        // second instance
+        let ray = Ray::from(((10.25, 0.25, -10.0), (0.0, 0.0, 1.0)));
+        assert!(environment.intersect(&ray).is_some());
+    }
+
+    #[test]
+    fn embedded_gltf() {
+        let text = document(&format!(
+            r#", "uri": "data:application/octet-stream;base64,{}""#,
+            TRIANGLE
+        ));
+        check(&read_gltf(text.as_bytes(), None).unwrap());
+
+        let text = document(r#", "uri": "triangle.bin""#);
+        assert!(read_gltf(text.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn malformed_accessors_fail() {
+        let valid = document(&format!(
+            r#", "uri": "data:application/octet-stream;base64,{}""#,
+            TRIANGLE
+        ));
+        let positions = r#""componentType": 5126, "count": 3, "type": "VEC3""#;
+        for broken in &[
+            r#""componentType": 5126, "count": 3, "type": "VEC2""#,
+            r#""componentType": 5126, "count": 4000000000, "type": "VEC3""#,
+            r#""componentType": 5126, "count": 18446744073709551615, "type": "VEC3""#,
+        ] {
+            let text = valid.replacen(positions, broken, 1);
+            assert!(read_gltf(text.as_bytes(), None).is_err(), "{}", broken);
+        }
+
+        // overlapping elements
+        let view = r#""byteLength": 44}"#;
+        for stride in &["4", "11"] {
+            let broken = format!(r#""byteLength": 44, "byteStride": {}}}"#, stride);
+            let text = valid.replacen(view, &broken, 1);
+            assert!(read_gltf(text.as_bytes(), None).is_err(), "{}", stride);
+        }
+        // a zero stride packs tightly, huge counts still run out of buffer
+        let text = valid
+            .replacen(view, r#""byteLength": 44, "byteStride": 0}"#, 1)
+            .replacen(
+                positions,
+                r#""componentType": 5126, "count": 4000000000, "type": "VEC3""#,
+                1,
+            );
+        assert!(read_gltf(text.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn binary_glb() {
+        let mut json = document("").into_bytes();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+        let bin = decode_base64(TRIANGLE).unwrap();
+        assert_eq!(bin.len(), 44);
+
+        let mut data = b"glTF".to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        data.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+        data.extend_from_slice(&json);
+        data.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        data.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        data.extend_from_slice(&bin);
+        check(&read_gltf(&data, None).unwrap());
+    }
+
+    #[test]
+    fn node_rotation() {
+        // quarter turn around z takes x to y
+        let node =
+            Json::parse(r#"{"rotation": [0, 0, 0.7071067811865476, 0.7071067811865476]}"#).unwrap();
+        let p = node_matrix(&node) * Vector::from((1.0, 0.0, 0.0));
+        assert!(!(p - Vector::from((0.0, 1.0, 0.0))) < 1e-9);
+    }
+}
@@ -0,0 +1,465 @@
+use super::*;
+
+use std::fmt;
+
+/// Arrays and objects nested deeper are rejected before they exhaust the
+/// stack.
+const MAX_DEPTH: usize = 512;
+
+/// JSON value. Object members keep their order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> io::Result<Json> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.text.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+            .map(|n| n as usize)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Array of numbers.
+    pub fn as_f64s(&self) -> Option<Vec<f64>> {
+        self.as_array()
+            .and_then(|items| items.iter().map(Json::as_f64).collect())
+    }
+
+    /// Object with members in the given order.
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Vector written as an array of three numbers.
+    pub fn as_vector(&self) -> Option<Vector> {
+        match self.as_f64s() {
+            Some(ref v) if v.len() == 3 => Some(Vector::from((v[0], v[1], v[2]))),
+            _ => None,
+        }
+    }
+
+    /// Matrix written as an array of its 16 elements row by row.
+    pub fn as_matrix(&self) -> Option<Matrix> {
+        match self.as_f64s() {
+            Some(ref v) if v.len() == 16 => {
+                let mut m = Matrix::from_scalar(0.0);
+                for (i, &x) in v.iter().enumerate() {
+                    m.0[i / 4][i % 4] = x;
+                }
+                Some(m)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_medium(&self) -> Option<Medium> {
+        Some(Medium {
+            n_refr: self.get("n_refr")?.as_f64()?,
+            betta: self.get("betta")?.as_f64()?,
+        })
+    }
+
+    /// Surface written by `Json::from(Surface)`, the hit normal `n` is not
+    /// kept.
+    pub fn as_surface(&self) -> Option<Surface> {
+        let number = |key| self.get(key).and_then(Json::as_f64);
+        let mut surface = Surface::new();
+        surface.color = self.get("color")?.as_vector()?;
+        surface.k_a = number("k_a")?;
+        surface.k_d = number("k_d")?;
+        surface.k_s = number("k_s")?;
+        surface.p = number("p")? as i32;
+        surface.k_r = number("k_r")?;
+        surface.k_t = number("k_t")?;
+        surface.medium = self.get("medium")?.as_medium()?;
+        Some(surface)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Json {
+        Json::Number(n)
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<Vec<f64>> for Json {
+    fn from(v: Vec<f64>) -> Json {
+        Json::Array(v.into_iter().map(Json::Number).collect())
+    }
+}
+
+impl From<Vector> for Json {
+    fn from(v: Vector) -> Json {
+        Json::from(vec![v.x, v.y, v.z])
+    }
+}
+
+impl From<Matrix> for Json {
+    fn from(m: Matrix) -> Json {
+        Json::from(
+            m.0.iter()
+                .flat_map(|row| row.iter().cloned())
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl From<Medium> for Json {
+    fn from(medium: Medium) -> Json {
+        Json::object(vec![
+            ("n_refr", Json::from(medium.n_refr)),
+            ("betta", Json::from(medium.betta)),
+        ])
+    }
+}
+
+impl From<Surface> for Json {
+    fn from(surface: Surface) -> Json {
+        Json::object(vec![
+            ("color", Json::from(surface.color)),
+            ("k_a", Json::from(surface.k_a)),
+            ("k_d", Json::from(surface.k_d)),
+            ("k_s", Json::from(surface.k_s)),
+            ("p", Json::from(surface.p as f64)),
+            ("k_r", Json::from(surface.k_r)),
+            ("k_t", Json::from(surface.k_t)),
+            ("medium", Json::from(surface.medium)),
+        ])
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // Display of f64 is the shortest text reading back the same value
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    /// arrays and objects around the current value
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> io::Error {
+        invalid(&format!("JSON {} at byte {}", message, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.text.len() && (self.text[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.text.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, c: u8) -> io::Result<()> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> io::Result<Json> {
+        if self.text[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unknown literal"))
+        }
+    }
+
+    fn value(&mut self) -> io::Result<Json> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.nested_value();
+        self.depth -= 1;
+        value
+    }
+
+    /// Value one level below the current one.
+    fn nested_value(&mut self) -> io::Result<Json> {
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    members.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.text.len()
+                    && b"+-.eE0123456789".contains(&self.text[self.pos])
+                {
+                    self.pos += 1;
+                }
+                String::from_utf8_lossy(&self.text[start..self.pos])
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| self.error("bad number"))
+            }
+            _ => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let c = *self
+                .text
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = *self
+                        .text
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let c = match e {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                // surrogate pair
+                                if !self.text[self.pos..].starts_with(b"\\u") {
+                                    return Err(self.error("lone surrogate"));
+                                }
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code =
+                                    0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00));
+                            }
+                            ::std::char::from_u32(code)
+                                .ok_or_else(|| self.error("bad unicode escape"))?
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    fn hex4(&mut self) -> io::Result<u32> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| ::std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("bad unicode escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_write() {
+        let text = r#" {"a": [1, -2.5e1, true, null], "b": {"c": "x\"é😀"}, "d": []} "#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.get("a").unwrap().as_array().unwrap().len(), 4);
+        assert_eq!(
+            json.get("a").unwrap().as_array().unwrap()[1].as_f64(),
+            Some(-25.0)
+        );
+        assert_eq!(
+            json.get("b")
+                .and_then(|b| b.get("c"))
+                .and_then(Json::as_str),
+            Some("x\"é😀")
+        );
+
+        let written = json.to_string();
+        assert_eq!(
+            written,
+            "{\"a\":[1,-25,true,null],\"b\":{\"c\":\"x\\\"é😀\"},\"d\":[]}"
+        );
+        assert_eq!(Json::parse(&written).unwrap(), json);
+        assert_eq!(Json::Number(0.1).to_string(), "0.1");
+
+        let escaped = Json::parse(r#""\u00e9\ud83d\ude00\n""#).unwrap();
+        assert_eq!(escaped.as_str(), Some("é😀\n"));
+
+        assert!(Json::parse("{\"a\": 1,}").is_err());
+        assert!(Json::parse("[1] 2").is_err());
+        assert!(Json::parse("\"open").is_err());
+
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Json::parse(&"[".repeat(300_000)).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use super::*;
+
+use std::io;
+
+pub mod json;
+pub mod ply;
+pub mod stl;
+pub mod gltf;
+pub mod pov;
+pub mod native;
+
+pub use self::gltf::{load_gltf, read_gltf};
+pub use self::json::Json;
+pub use self::native::{load_scene, read_scene, save_scene, write_scene, Prototypes};
+pub use self::ply::read_ply;
+pub use self::pov::read_pov;
+pub use self::stl::read_stl;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
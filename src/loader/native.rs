@@ -0,0 +1,948 @@
+use super::*;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::super::camera::{Equirectangular, Fisheye, Orthographic, Perspective, Shutter};
+use super::super::geometry::sdf::{self, Sdf, SdfObject};
+use super::super::geometry::{
+    self, Annulus, BezierPatch, Blob, Capsule, Cone, Csg, CsgOperation, Cylinder, Disc, Ellipsoid,
+    Frustum, HeightField, Instance, Lathe, Mesh, Plane, Prism, Quadric, Rect, Sphere, Torus,
+    Triangle,
+};
+use super::super::light::{DirectionalLight, PointLight, SphericLight, SpotLight};
+use super::super::scene::RenderSettings;
+
+/// Reads a scene file, see `read_scene`.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> io::Result<Scene> {
+    read_scene(&fs::read_to_string(path)?)
+}
+
+/// Writes a scene file, see `write_scene`.
+pub fn save_scene<P: AsRef<Path>>(path: P, scene: &Scene) -> io::Result<()> {
+    write_scene(scene, fs::File::create(path)?)
+}
+
+/// Reads a scene in the native format, a JSON object with `settings`,
+/// `camera`, `lights` and `objects`. Objects, lights and cameras are JSON
+/// objects naming their kind in `type`, with the arguments of its
+/// constructor.
+///
+/// Objects shared by several instances are listed once in the optional
+/// `prototypes`, an instance names its object by index in `prototype`
+/// instead of owning a copy in `object`. Prototypes may be instances of
+/// earlier prototypes.
+pub fn read_scene(text: &str) -> io::Result<Scene> {
+    let json = Json::parse(text)?;
+    let root = Fields::new(&json, "scene")?;
+
+    let mut scene = Scene::new();
+    if let Some(settings) = json.get("settings") {
+        scene.settings = read_settings(settings)?;
+    }
+    match json.get("camera") {
+        None | Some(&Json::Null) => {}
+        Some(camera) => scene.camera = Some(read_camera(camera)?),
+    }
+    for light in root.array("lights")? {
+        scene.lights.push(read_light(light)?);
+    }
+    let mut prototypes: Vec<Rc<GObject>> = Vec::new();
+    if root.has("prototypes") {
+        for prototype in root.array("prototypes")? {
+            let object = read_object(prototype, &prototypes)?;
+            prototypes.push(Rc::from(object));
+        }
+    }
+    for object in root.array("objects")? {
+        scene.objects.push(read_object(object, &prototypes)?);
+    }
+    Ok(scene)
+}
+
+/// Writes `scene` in the native format. Fails if an object, light or camera
+/// cannot be written, like a `Displace` signed distance function.
+pub fn write_scene<W: Write>(scene: &Scene, mut writer: W) -> io::Result<()> {
+    let unwritable = |what: &str, index: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} {} cannot be written", what, index),
+        )
+    };
+    let camera = match scene.camera {
+        Some(ref camera) => camera.to_json().ok_or_else(|| unwritable("camera", 0))?,
+        None => Json::Null,
+    };
+    let lights = scene
+        .lights
+        .iter()
+        .enumerate()
+        .map(|(i, light)| light.to_json().ok_or_else(|| unwritable("light", i)))
+        .collect::<io::Result<_>>()?;
+    let mut prototypes = Prototypes::new();
+    let objects = scene
+        .objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| {
+            object
+                .to_json_shared(&mut prototypes)
+                .ok_or_else(|| unwritable("object", i))
+        })
+        .collect::<io::Result<_>>()?;
+
+    let settings = &scene.settings;
+    let json = Json::object(vec![
+        (
+            "settings",
+            Json::object(vec![
+                ("width", Json::from(settings.width as f64)),
+                ("height", Json::from(settings.height as f64)),
+                ("max_level", Json::from(settings.max_level as f64)),
+                ("threshold", Json::from(settings.threshold)),
+                ("background", Json::from(settings.background)),
+            ]),
+        ),
+        ("camera", camera),
+        ("lights", Json::Array(lights)),
+        ("prototypes", Json::Array(prototypes.objects)),
+        ("objects", Json::Array(objects)),
+    ]);
+    write!(writer, "{}", json)
+}
+
+/// Objects shared by the instances of a scene being written, each written
+/// once no matter how many instances place it.
+pub struct Prototypes {
+    /// index of every written object by its address
+    indices: HashMap<*const u8, usize>,
+    objects: Vec<Json>,
+}
+
+impl Prototypes {
+    fn new() -> Prototypes {
+        Prototypes {
+            indices: HashMap::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    /// Index of the shared `object`, written on first use. `None` if it
+    /// cannot be written.
+    pub fn add<'a>(&mut self, object: &Rc<GObject + 'a>) -> Option<usize> {
+        let address = &**object as *const GObject as *const u8;
+        if let Some(&index) = self.indices.get(&address) {
+            return Some(index);
+        }
+        // prototypes written by the object come first
+        let json = object.to_json_shared(self)?;
+        self.objects.push(json);
+        self.indices.insert(address, self.objects.len() - 1);
+        Some(self.objects.len() - 1)
+    }
+}
+
+/// Members of a JSON object of the given kind, with errors naming the kind
+/// and the member.
+struct Fields<'a> {
+    json: &'a Json,
+    kind: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    fn new(json: &'a Json, kind: &'a str) -> io::Result<Fields<'a>> {
+        match *json {
+            Json::Object(_) => Ok(Fields { json, kind }),
+            _ => Err(invalid(&format!("{} is not an object", kind))),
+        }
+    }
+
+    fn error(&self, key: &str) -> io::Error {
+        invalid(&format!("{}: missing or bad '{}'", self.kind, key))
+    }
+
+    fn get(&self, key: &str) -> io::Result<&'a Json> {
+        self.json.get(key).ok_or_else(|| self.error(key))
+    }
+
+    fn has(&self, key: &str) -> bool {
+        self.json.get(key).is_some()
+    }
+
+    fn number(&self, key: &str) -> io::Result<f64> {
+        self.get(key)?.as_f64().ok_or_else(|| self.error(key))
+    }
+
+    fn count(&self, key: &str) -> io::Result<usize> {
+        self.get(key)?.as_usize().ok_or_else(|| self.error(key))
+    }
+
+    fn string(&self, key: &str) -> io::Result<&'a str> {
+        self.get(key)?.as_str().ok_or_else(|| self.error(key))
+    }
+
+    fn vector(&self, key: &str) -> io::Result<Vector> {
+        self.get(key)?.as_vector().ok_or_else(|| self.error(key))
+    }
+
+    fn numbers(&self, key: &str) -> io::Result<Vec<f64>> {
+        self.get(key)?.as_f64s().ok_or_else(|| self.error(key))
+    }
+
+    /// Flat array of numbers read as vectors.
+    fn vectors(&self, key: &str) -> io::Result<Vec<Vector>> {
+        let numbers = self.numbers(key)?;
+        if numbers.len() % 3 != 0 {
+            return Err(self.error(key));
+        }
+        Ok(numbers
+            .chunks(3)
+            .map(|v| Vector::from((v[0], v[1], v[2])))
+            .collect())
+    }
+
+    /// Array of number pairs.
+    fn pairs(&self, key: &str) -> io::Result<Vec<(f64, f64)>> {
+        self.array(key)?
+            .iter()
+            .map(|pair| match pair.as_f64s() {
+                Some(ref p) if p.len() == 2 => Ok((p[0], p[1])),
+                _ => Err(self.error(key)),
+            })
+            .collect()
+    }
+
+    fn array(&self, key: &str) -> io::Result<&'a [Json]> {
+        self.get(key)?.as_array().ok_or_else(|| self.error(key))
+    }
+
+    fn material(&self) -> io::Result<Surface> {
+        self.get("material")?
+            .as_surface()
+            .ok_or_else(|| self.error("material"))
+    }
+}
+
+fn read_settings(json: &Json) -> io::Result<RenderSettings> {
+    let fields = Fields::new(json, "settings")?;
+    Ok(RenderSettings {
+        width: fields.count("width")? as u32,
+        height: fields.count("height")? as u32,
+        max_level: fields.count("max_level")? as u32,
+        threshold: fields.number("threshold")?,
+        background: fields.vector("background")?,
+    })
+}
+
+fn read_camera(json: &Json) -> io::Result<Box<Camera>> {
+    let fields = Fields::new(json, "camera")?;
+    let kind = fields.string("type")?;
+    let fields = Fields { json, kind };
+    let (eye, dir) = (fields.vector("eye"), fields.vector("dir"));
+    let up = fields.vector("up");
+    Ok(match kind {
+        "perspective" => {
+            let mut camera = Perspective::new(
+                eye?,
+                dir?,
+                up?,
+                fields.number("fov")?,
+                fields.number("aspect")?,
+            );
+            camera.set_lens(
+                fields.number("aperture")?,
+                fields.number("focal_distance")?,
+                fields.count("blades")? as u32,
+            );
+            Box::new(camera)
+        }
+        "orthographic" => Box::new(Orthographic::new(
+            eye?,
+            dir?,
+            up?,
+            fields.number("width")?,
+            fields.number("height")?,
+        )),
+        "fisheye" => Box::new(Fisheye::new(eye?, dir?, up?, fields.number("fov")?)),
+        "equirectangular" => Box::new(Equirectangular::new(eye?, dir?, up?)),
+        "shutter" => {
+            let mut shutter = Shutter::new(
+                read_camera(fields.get("camera")?)?,
+                fields.number("open")?,
+                fields.number("close")?,
+            );
+            shutter.set_motion(fields.vector("velocity")?);
+            Box::new(shutter)
+        }
+        _ => return Err(invalid(&format!("unknown camera type '{}'", kind))),
+    })
+}
+
+fn read_light(json: &Json) -> io::Result<Box<LightSource>> {
+    let kind = Fields::new(json, "light")?.string("type")?;
+    let fields = Fields { json, kind };
+    let color = fields.vector("color")?;
+    Ok(match kind {
+        "point" => Box::new(PointLight::new(
+            color,
+            fields.vector("center")?,
+            fields.number("distance_scale")?,
+        )),
+        "spheric" => Box::new(SphericLight::new(
+            color,
+            fields.vector("center")?,
+            fields.number("radius")?,
+            fields.number("distance_scale")?,
+        )),
+        "spot" => Box::new(SpotLight::new(
+            color,
+            fields.vector("center")?,
+            fields.vector("direction")?,
+            fields.number("cone_angle")?,
+            fields.number("end_cone_angle")?,
+            fields.number("beam_distribution")? as i32,
+            fields.number("distance_scale")?,
+        )),
+        "directional" => Box::new(DirectionalLight::new(color, fields.vector("direction")?)),
+        _ => return Err(invalid(&format!("unknown light type '{}'", kind))),
+    })
+}
+
+fn read_object(json: &Json, prototypes: &[Rc<GObject>]) -> io::Result<Box<GObject>> {
+    let kind = Fields::new(json, "object")?.string("type")?;
+    let f = Fields { json, kind };
+    Ok(match kind {
+        "sphere" => Box::new(Sphere::new(
+            f.material()?,
+            f.vector("center")?,
+            f.number("radius")?,
+        )),
+        "plane" => Box::new(Plane::new(
+            f.material()?,
+            f.vector("normal")?,
+            f.number("distance")?,
+        )),
+        "rect" => Box::new(Rect::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("side_a")?,
+            f.vector("side_b")?,
+        )),
+        "triangle" => Box::new(Triangle::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("side_a")?,
+            f.vector("side_b")?,
+        )),
+        "box" => Box::new(geometry::Box::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("e1")?,
+            f.vector("e2")?,
+            f.vector("e3")?,
+        )),
+        "cylinder" => Box::new(Cylinder::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("dir")?,
+            f.number("radius")?,
+        )),
+        "frustum" => Box::new(Frustum::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("dir")?,
+            f.number("radius1")?,
+            f.number("radius2")?,
+        )),
+        "cone" => Box::new(Cone::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("dir")?,
+            f.number("radius")?,
+        )),
+        "capsule" => Box::new(Capsule::new(
+            f.material()?,
+            f.vector("loc")?,
+            f.vector("dir")?,
+            f.number("radius")?,
+        )),
+        "disc" => Box::new(Disc::new(
+            f.material()?,
+            f.vector("center")?,
+            f.vector("normal")?,
+            f.number("radius")?,
+        )),
+        "annulus" => Box::new(Annulus::new(
+            f.material()?,
+            f.vector("center")?,
+            f.vector("normal")?,
+            f.number("inner_radius")?,
+            f.number("outer_radius")?,
+        )),
+        "torus" => Box::new(Torus::new(
+            f.material()?,
+            f.vector("center")?,
+            f.vector("axis")?,
+            f.number("major_radius")?,
+            f.number("minor_radius")?,
+        )),
+        "quadric" => {
+            let k = f.numbers("coefficients")?;
+            if k.len() != 10 {
+                return Err(f.error("coefficients"));
+            }
+            let mut coefficients = [0.0; 10];
+            coefficients.copy_from_slice(&k);
+            let mut quadric = Quadric::new(f.material()?, coefficients);
+            if f.has("clip_min") {
+                quadric.set_clip_box(f.vector("clip_min")?, f.vector("clip_max")?);
+            }
+            Box::new(quadric)
+        }
+        "ellipsoid" => Box::new(Ellipsoid::new(
+            f.material()?,
+            f.vector("center")?,
+            f.vector("radii")?,
+        )),
+        "bezier" => {
+            let points = f
+                .array("control")?
+                .iter()
+                .map(|p| p.as_vector().ok_or_else(|| f.error("control")))
+                .collect::<io::Result<Vec<_>>>()?;
+            if points.len() != 16 {
+                return Err(f.error("control"));
+            }
+            let mut control = [[Vector::from(0.0); 4]; 4];
+            for (i, p) in points.into_iter().enumerate() {
+                control[i / 4][i % 4] = p;
+            }
+            Box::new(BezierPatch::new(f.material()?, control))
+        }
+        "height_field" => {
+            let (width, depth) = (f.count("width")?, f.count("depth")?);
+            let heights = f.numbers("heights")?;
+            if width < 2 || depth < 2 || width.checked_mul(depth) != Some(heights.len()) {
+                return Err(f.error("heights"));
+            }
+            Box::new(HeightField::new(
+                f.material()?,
+                f.vector("corner")?,
+                f.vector("size")?,
+                width,
+                depth,
+                heights,
+            ))
+        }
+        "lathe" => {
+            let profile = f.pairs("profile")?;
+            if profile.len() < 2 || profile.windows(2).any(|w| w[1].1 <= w[0].1) {
+                return Err(f.error("profile"));
+            }
+            Box::new(Lathe::new(
+                f.material()?,
+                f.vector("loc")?,
+                f.vector("axis")?,
+                &profile,
+            ))
+        }
+        "prism" => {
+            let profile = f.pairs("profile")?;
+            if profile.len() < 3 {
+                return Err(f.error("profile"));
+            }
+            Box::new(Prism::new(
+                f.material()?,
+                f.vector("loc")?,
+                f.vector("dir")?,
+                f.vector("right")?,
+                profile,
+            ))
+        }
+        "mesh" => {
+            let vertices = f.vectors("vertices")?;
+            let indices = f.numbers("triangles")?;
+            if indices.len() % 3 != 0
+                || indices
+                    .iter()
+                    .any(|&i| i < 0.0 || i.fract() != 0.0 || i as usize >= vertices.len())
+            {
+                return Err(f.error("triangles"));
+            }
+            let triangles = indices
+                .chunks(3)
+                .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                .collect();
+            let count = vertices.len();
+            let mut mesh = Mesh::new(f.material()?, vertices, triangles);
+            for &(key, normals) in &[("normals", true), ("colors", false)] {
+                if !f.has(key) {
+                    continue;
+                }
+                let values = f.vectors(key)?;
+                if values.len() != count {
+                    return Err(f.error(key));
+                }
+                if normals {
+                    mesh.set_normals(values);
+                } else {
+                    mesh.set_colors(values);
+                }
+            }
+            Box::new(mesh)
+        }
+        "blob" => {
+            let mut blob = Blob::new(f.material()?, f.number("threshold")?);
+            for component in f.array("components")? {
+                let c = Fields::new(component, "blob component")?;
+                let (radius, strength) = (c.number("radius")?, c.number("strength")?);
+                if c.has("center") {
+                    blob.add_sphere(c.vector("center")?, radius, strength);
+                } else {
+                    blob.add_cylinder(c.vector("loc")?, c.vector("dir")?, radius, strength);
+                }
+            }
+            Box::new(blob)
+        }
+        "csg" => {
+            let operation = match f.string("operation")? {
+                "union" => CsgOperation::Union,
+                "intersection" => CsgOperation::Intersection,
+                "difference" => CsgOperation::Difference,
+                _ => return Err(f.error("operation")),
+            };
+            let children = f
+                .array("children")?
+                .iter()
+                .map(|child| read_object(child, prototypes))
+                .collect::<io::Result<Vec<_>>>()?;
+            if children.is_empty() {
+                return Err(f.error("children"));
+            }
+            Box::new(Csg::new(operation, children))
+        }
+        "instance" => {
+            let object = if f.has("prototype") {
+                let index = f.count("prototype")?;
+                prototypes
+                    .get(index)
+                    .ok_or_else(|| f.error("prototype"))?
+                    .clone()
+            } else {
+                Rc::from(read_object(f.get("object")?, prototypes)?)
+            };
+            let matrix = f
+                .get("matrix")?
+                .as_matrix()
+                .ok_or_else(|| f.error("matrix"))?;
+            let mut instance = Instance::new(object, matrix);
+            instance.set_motion(f.vector("velocity")?);
+            if f.has("material") {
+                instance.set_material(f.material()?);
+            }
+            Box::new(instance)
+        }
+        "sdf" => {
+            let mut object = SdfObject::new(
+                f.material()?,
+                read_sdf(f.get("sdf")?)?,
+                f.vector("center")?,
+                f.number("radius")?,
+            );
+            object.set_step_scale(f.number("step_scale")?);
+            object.set_max_steps(f.count("max_steps")? as u32);
+            Box::new(object)
+        }
+        _ => return Err(invalid(&format!("unknown object type '{}'", kind))),
+    })
+}
+
+fn read_sdf(json: &Json) -> io::Result<Box<Sdf>> {
+    let kind = Fields::new(json, "sdf")?.string("type")?;
+    let f = Fields { json, kind };
+    let operands = || -> io::Result<(Box<Sdf>, Box<Sdf>)> {
+        Ok((read_sdf(f.get("a")?)?, read_sdf(f.get("b")?)?))
+    };
+    Ok(match kind {
+        "sphere" => Box::new(sdf::Sphere::new(f.vector("center")?, f.number("radius")?)),
+        "cuboid" => Box::new(sdf::Cuboid::new(
+            f.vector("center")?,
+            f.vector("half_size")?,
+        )),
+        "torus" => Box::new(sdf::Torus::new(
+            f.vector("center")?,
+            f.number("major_radius")?,
+            f.number("minor_radius")?,
+        )),
+        "capsule" => Box::new(sdf::Capsule::new(
+            f.vector("a")?,
+            f.vector("b")?,
+            f.number("radius")?,
+        )),
+        "plane" => Box::new(sdf::Plane::new(f.vector("normal")?, f.number("distance")?)),
+        "union" => {
+            let (a, b) = operands()?;
+            Box::new(sdf::Union::new(a, b))
+        }
+        "smooth_union" => {
+            let (a, b) = operands()?;
+            Box::new(sdf::SmoothUnion::new(a, b, f.number("k")?))
+        }
+        "subtraction" => {
+            let (a, b) = operands()?;
+            Box::new(sdf::Subtraction::new(a, b))
+        }
+        "intersection" => {
+            let (a, b) = operands()?;
+            Box::new(sdf::Intersection::new(a, b))
+        }
+        "repeat" => Box::new(sdf::Repeat::new(
+            read_sdf(f.get("sdf")?)?,
+            f.vector("period")?,
+        )),
+        "twist" => Box::new(sdf::Twist::new(read_sdf(f.get("sdf")?)?, f.number("rate")?)),
+        _ => return Err(invalid(&format!("unknown sdf type '{}'", kind))),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::f64::consts::FRAC_PI_3;
+
+    fn material(k_d: f64) -> Surface {
+        let mut surface = Surface::new();
+        surface.color = Color::from((0.25, 0.5, 0.75));
+        surface.k_a = 0.1;
+        surface.k_d = k_d;
+        surface.k_s = 0.3;
+        surface.p = 20;
+        surface.k_t = 0.2;
+        surface.medium = Medium {
+            n_refr: 1.5,
+            betta: 0.01,
+        };
+        surface
+    }
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.settings.width = 320;
+        scene.settings.max_level = 4;
+
+        let mut camera = Perspective::new(
+            Vector::from((0.0, 1.0, -10.0)),
+            Vector::from((0.0, 0.0, 1.0)),
+            Vector::from((0.0, 1.0, 0.0)),
+            FRAC_PI_3,
+            1.5,
+        );
+        camera.set_lens(0.0, 10.0, 5);
+        scene.camera = Some(Box::new(camera));
+        scene.lights.push(Box::new(PointLight::new(
+            Color::from(1.0),
+            Vector::from((0.0, 10.0, 0.0)),
+            10.0,
+        )));
+        scene.lights.push(Box::new(DirectionalLight::new(
+            Color::from(0.5),
+            Vector::from((1.0, -1.0, 0.0)),
+        )));
+        scene.lights.push(Box::new(SpotLight::new(
+            Color::from(0.8),
+            Vector::from((0.0, 5.0, -5.0)),
+            Vector::from((0.0, -1.0, 1.0)),
+            30.0,
+            45.0,
+            2,
+            5.0,
+        )));
+        scene.lights.push(Box::new(SphericLight::new(
+            Color::from(0.3),
+            Vector::from((5.0, 5.0, 0.0)),
+            0.5,
+            8.0,
+        )));
+
+        scene.objects.push(Box::new(Sphere::new(
+            material(0.7),
+            Vector::from((-3.0, 0.0, 0.0)),
+            1.0,
+        )));
+        scene.objects.push(Box::new(Plane::new(
+            material(0.5),
+            Vector::from((0.0, 1.0, 0.0)),
+            2.0,
+        )));
+        scene.objects.push(Box::new(Csg::new(
+            CsgOperation::Difference,
+            vec![
+                Box::new(geometry::Box::new(
+                    material(0.6),
+                    Vector::from(-1.0),
+                    Vector::from((2.0, 0.0, 0.0)),
+                    Vector::from((0.0, 2.0, 0.0)),
+                    Vector::from((0.0, 0.0, 2.0)),
+                )),
+                Box::new(Cylinder::new(
+                    material(0.6),
+                    Vector::from((0.0, 0.0, -2.0)),
+                    Vector::from((0.0, 0.0, 4.0)),
+                    0.5,
+                )),
+            ],
+        )));
+        let mut mesh = Mesh::new(
+            material(0.8),
+            vec![
+                Vector::from(0.0),
+                Vector::from((1.0, 0.0, 0.0)),
+                Vector::from((0.0, 1.0, 0.0)),
+            ],
+            vec![[0, 1, 2]],
+        );
+        mesh.set_colors(vec![Color::from(1.0); 3]);
+        let mut instance = Instance::new(
+            Rc::new(mesh),
+            Matrix::scale(&Vector::from(2.0)) * Matrix::translate(&Vector::from((3.0, 0.0, 0.0))),
+        );
+        instance.set_material(material(0.9));
+        scene.objects.push(Box::new(instance));
+        scene.objects.push(Box::new(SdfObject::new(
+            material(0.4),
+            sdf::Union::new(
+                sdf::Sphere::new(Vector::from((0.0, 3.0, 0.0)), 0.5),
+                sdf::Torus::new(Vector::from((0.0, 3.0, 0.0)), 1.0, 0.2),
+            ),
+            Vector::from((0.0, 3.0, 0.0)),
+            1.5,
+        )));
+        primitives(&mut scene);
+        scene
+    }
+
+    /// One of every primitive, behind the camera.
+    fn primitives(scene: &mut Scene) {
+        let at = |x: f64| Vector::from((x, 0.0, -20.0));
+        let up = Vector::from((0.0, 1.0, 0.0));
+        let objects: Vec<Box<GObject>> = vec![
+            Box::new(Torus::new(material(0.5), at(0.0), up, 1.0, 0.25)),
+            Box::new(Frustum::new(material(0.5), at(3.0), up, 1.0, 0.5)),
+            Box::new(Cone::new(material(0.5), at(6.0), up, 1.0)),
+            Box::new(Capsule::new(material(0.5), at(9.0), up, 0.5)),
+            Box::new(Disc::new(material(0.5), at(12.0), up, 1.0)),
+            Box::new(Annulus::new(material(0.5), at(15.0), up, 0.5, 1.0)),
+            Box::new(Ellipsoid::new(
+                material(0.5),
+                at(18.0),
+                Vector::from((1.0, 0.5, 0.25)),
+            )),
+        ];
+        scene.objects.extend(objects);
+
+        let mut quadric = Quadric::new(
+            material(0.5),
+            [1.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        );
+        quadric.set_clip_box(Vector::from((-1.0, -1.0, -21.0)), Vector::from((1.0, 1.0, -19.0)));
+        scene.objects.push(Box::new(quadric));
+
+        let mut control = [[Vector::from(0.0); 4]; 4];
+        for (i, row) in control.iter_mut().enumerate() {
+            for (j, p) in row.iter_mut().enumerate() {
+                *p = at(-3.0) + Vector::from((j as f64, (i * j % 3) as f64 * 0.25, i as f64));
+            }
+        }
+        scene.objects.push(Box::new(BezierPatch::new(material(0.5), control)));
+        scene.objects.push(Box::new(HeightField::new(
+            material(0.5),
+            at(-6.0),
+            Vector::from((2.0, 1.0, 2.0)),
+            3,
+            2,
+            vec![0.0, 0.5, 1.0, 0.25, 0.75, 0.0],
+        )));
+        scene.objects.push(Box::new(Lathe::new(
+            material(0.5),
+            at(-9.0),
+            up,
+            &[(0.5, 0.0), (1.0, 0.5), (0.25, 1.0)],
+        )));
+        scene.objects.push(Box::new(Prism::new(
+            material(0.5),
+            at(-12.0),
+            up,
+            Vector::from((1.0, 0.0, 0.0)),
+            vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+        )));
+        let mut blob = Blob::new(material(0.5), 0.5);
+        blob.add_sphere(at(-15.0), 1.0, 1.0);
+        blob.add_cylinder(at(-16.0), Vector::from((2.0, 0.0, 0.0)), 0.5, 0.5);
+        scene.objects.push(Box::new(blob));
+        scene.objects.push(Box::new(Rect::new(
+            material(0.5),
+            at(-18.0),
+            Vector::from((1.0, 0.0, 0.0)),
+            up,
+        )));
+        scene.objects.push(Box::new(Triangle::new(
+            material(0.5),
+            at(-21.0),
+            Vector::from((1.0, 0.0, 0.0)),
+            up,
+        )));
+    }
+
+    fn written(scene: &Scene) -> String {
+        let mut text = Vec::new();
+        write_scene(scene, &mut text).unwrap();
+        String::from_utf8(text).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let scene = scene();
+        let text = written(&scene);
+        let read = read_scene(&text).unwrap();
+        assert_eq!(read.settings, scene.settings);
+        assert_eq!((read.lights.len(), read.objects.len()), (4, 20));
+        // normalized directions may move by an ulp once, then stay
+        let again = written(&read);
+        assert_eq!(written(&read_scene(&again).unwrap()), again);
+        for kind in &[
+            "\"torus\"",
+            "\"frustum\"",
+            "\"cone\"",
+            "\"capsule\"",
+            "\"disc\"",
+            "\"annulus\"",
+            "\"ellipsoid\"",
+            "\"quadric\"",
+            "\"clip_min\"",
+            "\"bezier\"",
+            "\"height_field\"",
+            "\"lathe\"",
+            "\"prism\"",
+            "\"blob\"",
+            "\"rect\"",
+            "\"triangle\"",
+            "\"spot\"",
+            "\"spheric\"",
+        ] {
+            assert!(again.contains(kind), "{} not written", kind);
+        }
+
+        let (a, b) = (scene.environment(), read.environment());
+        for &(x, y) in &[
+            (0.0, 0.0),
+            (-0.45, 0.0),
+            (0.5, -0.3),
+            (0.0, 0.4),
+            (0.9, 0.9),
+        ] {
+            let (ray_a, ray_b) = (a.camera(x, y), b.camera(x, y));
+            assert!(!(ray_a.dir - ray_b.dir) < 1e-12);
+            let hits = (a.intersect(&ray_a), b.intersect(&ray_a));
+            match hits {
+                (Some((object_a, hit_a)), Some((object_b, hit_b))) => {
+                    assert!((hit_a.t - hit_b.t).abs() < 1e-9);
+                    let (s_a, s_b) = (object_a.find_texture(&hit_a), object_b.find_texture(&hit_b));
+                    assert_eq!((s_a.k_d, s_a.color), (s_b.k_d, s_b.color));
+                }
+                (None, None) => {}
+                _ => panic!("hit differs at ({}, {})", x, y),
+            }
+        }
+    }
+
+    #[test]
+    fn instances_share_prototypes() {
+        let sphere: Rc<GObject> = Rc::new(Sphere::new(material(0.5), Vector::from(0.0), 1.0));
+        let half = Matrix::scale(&Vector::from(0.5));
+        let nested: Rc<GObject> = Rc::new(Instance::new(sphere.clone(), half));
+        let mut scene = Scene::new();
+        for x in 0..3 {
+            let shift = Matrix::translate(&Vector::from((x as f64, 0.0, 0.0)));
+            scene.objects.push(Box::new(Instance::new(sphere.clone(), shift)));
+            scene.objects.push(Box::new(Instance::new(nested.clone(), shift)));
+        }
+        let text = written(&scene);
+        assert_eq!(text.matches("\"sphere\"").count(), 1, "{}", text);
+        let json = Json::parse(&text).unwrap();
+        let prototypes = json.get("prototypes").and_then(Json::as_array).unwrap();
+        assert_eq!(prototypes.len(), 2);
+
+        // reading shares the prototypes again
+        let read = read_scene(&text).unwrap();
+        assert_eq!(read.objects.len(), 6);
+        assert_eq!(written(&read), text);
+
+        let dangling = r#"{"lights": [], "objects": [{"type": "instance", "prototype": 0,
+            "matrix": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1], "velocity": [0, 0, 0]}]}"#;
+        assert!(read_scene(dangling).is_err());
+    }
+
+    #[test]
+    fn cameras_round_trip() {
+        let eye = Vector::from((0.0, 1.0, -10.0));
+        let dir = Vector::from((0.0, 0.0, 1.0));
+        let up = Vector::from((0.0, 1.0, 0.0));
+        let mut shutter = Shutter::new(Orthographic::new(eye, dir, up, 4.0, 3.0), 0.0, 0.5);
+        shutter.set_motion(Vector::from((1.0, 0.0, 0.0)));
+        let cameras: Vec<(&str, Box<Camera>)> = vec![
+            ("orthographic", Box::new(Orthographic::new(eye, dir, up, 4.0, 3.0))),
+            ("fisheye", Box::new(Fisheye::new(eye, dir, up, 3.0))),
+            ("equirectangular", Box::new(Equirectangular::new(eye, dir, up))),
+            ("shutter", Box::new(shutter)),
+        ];
+        for (kind, camera) in cameras {
+            let mut scene = Scene::new();
+            scene.camera = Some(camera);
+            let text = written(&scene);
+            assert!(text.contains(&format!("\"{}\"", kind)), "{}", text);
+            let read = read_scene(&text).unwrap();
+            let again = written(&read);
+            assert_eq!(written(&read_scene(&again).unwrap()), again, "{}", kind);
+            if kind != "shutter" {
+                let (a, b) = (scene.environment(), read.environment());
+                let (ray_a, ray_b) = (a.camera(0.5, -0.3), b.camera(0.5, -0.3));
+                assert!(!(ray_a.org - ray_b.org) < 1e-12, "{}", kind);
+                assert!(!(ray_a.dir - ray_b.dir) < 1e-12, "{}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut scene = Scene::new();
+        scene.objects.push(Box::new(SdfObject::new(
+            Surface::new(),
+            sdf::Displace::new(sdf::Sphere::new(Vector::from(0.0), 1.0), |p: &Vector| p.x),
+            Vector::from(0.0),
+            2.0,
+        )));
+        let error = write_scene(&scene, Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let missing = r#"{"lights": [], "objects": [{"type": "sphere", "center": [0, 0, 0]}]}"#;
+        let error = match read_scene(missing) {
+            Err(error) => error,
+            Ok(_) => panic!("sphere without radius accepted"),
+        };
+        assert_eq!(error.to_string(), "sphere: missing or bad 'material'");
+        assert!(read_scene(r#"{"lights": [], "objects": [{"type": "teapot"}]}"#).is_err());
+
+        // width * depth overflows to the number of heights
+        let huge = format!(
+            r#"{{"lights": [], "objects": [{{"type": "height_field", "width": 4294967296,
+            "depth": 4294967296, "heights": [], "corner": [0, 0, 0], "size": [1, 1, 1],
+            "material": {}}}]}}"#,
+            Json::from(Surface::new())
+        );
+        assert!(read_scene(&huge).is_err());
+    }
+}
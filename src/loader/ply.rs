@@ -0,0 +1,416 @@
+use super::*;
+
+use std::io::Read;
+
+use super::super::geometry::Mesh;
+
+/// Reads an ASCII or binary PLY file.
+///
+/// Uses the `x`, `y`, `z` vertex properties, and `nx`, `ny`, `nz` and
+/// `red`, `green`, `blue` when present. Polygons are split into triangle
+/// fans, other elements are skipped.
+pub fn read_ply<R: Read>(mut reader: R, material: Surface) -> io::Result<Mesh> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let header_end =
+        find(&data, b"end_header").ok_or_else(|| invalid("PLY header without end_header"))?;
+    let mut body = header_end + b"end_header".len();
+    if data.get(body) == Some(&b'\r') {
+        body += 1;
+    }
+    body += 1;
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    let (format, elements) = parse_header(&header)?;
+
+    let mut values: Box<Values> = match format.as_str() {
+        "ascii" => Box::new(Ascii {
+            tokens: String::from_utf8_lossy(&data[body.min(data.len())..])
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }),
+        "binary_little_endian" | "binary_big_endian" => Box::new(Binary {
+            data: &data[body.min(data.len())..],
+            pos: 0,
+            big_endian: format == "binary_big_endian",
+        }),
+        _ => return Err(invalid("unknown PLY format")),
+    };
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+    for element in &elements {
+        let find = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|p| names.contains(&p.name.as_str()))
+        };
+        let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+        let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+        let color = [
+            find(&["red", "r", "diffuse_red"]),
+            find(&["green", "g", "diffuse_green"]),
+            find(&["blue", "b", "diffuse_blue"]),
+        ];
+        let indices = find(&["vertex_indices", "vertex_index"]);
+
+        for _ in 0..element.count {
+            let mut row = Vec::with_capacity(element.properties.len());
+            let mut list = Vec::new();
+            for (i, property) in element.properties.iter().enumerate() {
+                match property.list {
+                    Some(count_type) => {
+                        // the count is untrusted, items run out before memory does
+                        let count = values.next(count_type)? as usize;
+                        let mut items = Vec::new();
+                        for _ in 0..count {
+                            items.push(values.next(property.kind)?);
+                        }
+                        if Some(i) == indices {
+                            list = items;
+                        }
+                        row.push(0.0);
+                    }
+                    None => row.push(values.next(property.kind)?),
+                }
+            }
+
+            if element.name == "vertex" {
+                let get = |slots: &[Option<usize>; 3]| match *slots {
+                    [Some(a), Some(b), Some(c)] => Some(Vector::from((row[a], row[b], row[c]))),
+                    _ => None,
+                };
+                vertices.push(get(&position).ok_or_else(|| invalid("PLY vertex without x, y, z"))?);
+                if let Some(n) = get(&normal) {
+                    normals.push(n);
+                }
+                if let Some(c) = get(&color) {
+                    let scale = match element.properties[color[0].unwrap()].kind {
+                        Type::Float | Type::Double => 1.0,
+                        kind => 1.0 / kind.max_value(),
+                    };
+                    colors.push(c * scale);
+                }
+            } else if element.name == "face" {
+                if list.iter().any(|&i| i < 0.0 || i.fract() != 0.0) {
+                    return Err(invalid("PLY face index is not a vertex number"));
+                }
+                for k in 2..list.len() {
+                    triangles.push([list[0] as usize, list[k - 1] as usize, list[k] as usize]);
+                }
+            }
+        }
+    }
+
+    if vertices
+        .iter()
+        .any(|v| !(v.x.is_finite() && v.y.is_finite() && v.z.is_finite()))
+    {
+        return Err(invalid("PLY vertex is not finite"));
+    }
+    if triangles
+        .iter()
+        .any(|tri| tri.iter().any(|&i| i >= vertices.len()))
+    {
+        return Err(invalid("PLY face refers to a missing vertex"));
+    }
+    let count = vertices.len();
+    let mut mesh = Mesh::new(material, vertices, triangles);
+    if normals.len() == count && count > 0 {
+        mesh.set_normals(normals);
+    }
+    if colors.len() == count && count > 0 {
+        mesh.set_colors(colors);
+    }
+    Ok(mesh)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Type {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl Type {
+    fn parse(name: &str) -> io::Result<Type> {
+        Ok(match name {
+            "char" | "int8" => Type::Char,
+            "uchar" | "uint8" => Type::UChar,
+            "short" | "int16" => Type::Short,
+            "ushort" | "uint16" => Type::UShort,
+            "int" | "int32" => Type::Int,
+            "uint" | "uint32" => Type::UInt,
+            "float" | "float32" => Type::Float,
+            "double" | "float64" => Type::Double,
+            _ => return Err(invalid("unknown PLY property type")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float => 4,
+            Type::Double => 8,
+        }
+    }
+
+    /// Full intensity of a color stored with this type.
+    fn max_value(self) -> f64 {
+        match self {
+            Type::Char => 127.0,
+            Type::UChar => 255.0,
+            Type::Short => 32767.0,
+            Type::UShort => 65535.0,
+            Type::Int => 2147483647.0,
+            _ => 4294967295.0,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    kind: Type,
+    /// type of the item count for list properties
+    list: Option<Type>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+fn parse_header(header: &str) -> io::Result<(String, Vec<Element>)> {
+    let mut lines = header.lines();
+    if lines.next().map(|l| l.trim()) != Some("ply") {
+        return Err(invalid("not a PLY file"));
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", name, _] => format = Some(name.to_string()),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid("bad PLY element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .ok_or_else(|| invalid("PLY property before element"))?
+                .properties
+                .push(Property {
+                    name: name.to_string(),
+                    kind: Type::parse(item)?,
+                    list: Some(Type::parse(count)?),
+                }),
+            ["property", kind, name] => elements
+                .last_mut()
+                .ok_or_else(|| invalid("PLY property before element"))?
+                .properties
+                .push(Property {
+                    name: name.to_string(),
+                    kind: Type::parse(kind)?,
+                    list: None,
+                }),
+            _ => {}
+        }
+    }
+    let format = format.ok_or_else(|| invalid("PLY header without format"))?;
+    // rows without properties consume no input, their count is unbounded
+    if elements.iter().any(|e| e.properties.is_empty() && e.count > 0) {
+        return Err(invalid("PLY element without properties"));
+    }
+    Ok((format, elements))
+}
+
+/// Source of property values of the body.
+trait Values {
+    fn next(&mut self, kind: Type) -> io::Result<f64>;
+}
+
+struct Ascii {
+    tokens: ::std::vec::IntoIter<String>,
+}
+
+impl Values for Ascii {
+    fn next(&mut self, _: Type) -> io::Result<f64> {
+        self.tokens
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid("bad or missing PLY value"))
+    }
+}
+
+struct Binary<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Values for Binary<'a> {
+    fn next(&mut self, kind: Type) -> io::Result<f64> {
+        let size = kind.size();
+        if self.pos + size > self.data.len() {
+            return Err(invalid("truncated PLY data"));
+        }
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.data[self.pos..self.pos + size]);
+        if self.big_endian {
+            bytes[..size].reverse();
+        }
+        self.pos += size;
+
+        let mut b2 = [0; 2];
+        let mut b4 = [0; 4];
+        b2.copy_from_slice(&bytes[..2]);
+        b4.copy_from_slice(&bytes[..4]);
+        Ok(match kind {
+            Type::Char => bytes[0] as i8 as f64,
+            Type::UChar => bytes[0] as f64,
+            Type::Short => i16::from_le_bytes(b2) as f64,
+            Type::UShort => u16::from_le_bytes(b2) as f64,
+            Type::Int => i32::from_le_bytes(b4) as f64,
+            Type::UInt => u32::from_le_bytes(b4) as f64,
+            Type::Float => f32::from_le_bytes(b4) as f64,
+            Type::Double => f64::from_le_bytes(bytes),
+        })
+    }
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_ply_with_colors() {
+        let text = b"ply
+format ascii 1.0
+comment square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 255 0 0
+1 1 0 0 0 255
+0 1 0 0 0 255
+4 0 1 2 3
+";
+        let mesh = read_ply(&text[..], Surface::new()).unwrap();
+        assert_eq!(mesh.triangles(), &[[0, 1, 2], [0, 2, 3]]);
+
+        let ray = Ray::from(((0.5, 0.75, 1.0), (0.0, 0.0, -1.0)));
+        let hit = mesh.intersect(&ray).unwrap();
+        let color = mesh.find_texture(&hit).color;
+        assert!(!(color - Color::from((0.25, 0.0, 0.75))) < 1e-9);
+    }
+
+    #[test]
+    fn binary_ply_with_normals() {
+        for &big_endian in &[false, true] {
+            let mut data = format!(
+                "ply\nformat binary_{}_endian 1.0\nelement vertex 3\n\
+                 property float x\nproperty float y\nproperty float z\n\
+                 property float nx\nproperty float ny\nproperty float nz\n\
+                 element face 1\nproperty list uchar uint vertex_indices\nend_header\n",
+                if big_endian { "big" } else { "little" }
+            )
+            .into_bytes();
+            let floats = [
+                0.0f32, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0,
+                0.0, 1.0,
+            ];
+            for f in floats.iter() {
+                data.extend_from_slice(&if big_endian {
+                    f.to_be_bytes()
+                } else {
+                    f.to_le_bytes()
+                });
+            }
+            data.push(3);
+            for i in 0..3u32 {
+                data.extend_from_slice(&if big_endian {
+                    i.to_be_bytes()
+                } else {
+                    i.to_le_bytes()
+                });
+            }
+
+            let mesh = read_ply(&data[..], Surface::new()).unwrap();
+            assert_eq!(mesh.vertices()[2], Vector::from((0.0, 1.0, 0.0)));
+            let ray = Ray::from(((0.25, 0.25, 1.0), (0.0, 0.0, -1.0)));
+            assert_eq!(
+                mesh.intersect(&ray).unwrap().normal,
+                Vector::from((0.0, 0.0, 1.0))
+            );
+
+            data.truncate(data.len() - 2);
+            assert!(read_ply(&data[..], Surface::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn bad_faces_fail() {
+        let header = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uint int vertex_indices
+end_header
+0 0 0
+1 0 0
+0 1 0
+";
+        for face in &["4000000000 0 1 2", "3 -1 1 2", "3 0 1.5 2", "3 0 1 nan"] {
+            let text = format!("{}{}\n", header, face);
+            assert!(read_ply(text.as_bytes(), Surface::new()).is_err(), "{}", face);
+        }
+    }
+
+    #[test]
+    fn empty_elements_fail() {
+        let text = "ply\nformat ascii 1.0\nelement junk 18446744073709551615\nend_header\n";
+        assert!(read_ply(text.as_bytes(), Surface::new()).is_err());
+    }
+
+    #[test]
+    fn non_finite_vertices_fail() {
+        for vertex in &["nan 0 0", "0 inf 0", "0 0 -inf"] {
+            let text = format!(
+                "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n\
+                 property float y\nproperty float z\nend_header\n{}\n",
+                vertex
+            );
+            assert!(read_ply(text.as_bytes(), Surface::new()).is_err(), "{}", vertex);
+        }
+    }
+}
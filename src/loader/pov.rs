@@ -0,0 +1,1330 @@
+use super::*;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::camera::{Orthographic, Perspective};
+use super::super::geometry::{
+    self, Csg, CsgOperation, Cylinder, Frustum, Instance, Plane, Sphere, Torus,
+};
+use super::super::light::{DirectionalLight, PointLight, SpotLight};
+
+/// Reads a scene written in a subset of the POV-Ray language.
+///
+/// Supported are `camera`, `light_source`, `sphere`, `plane`, `box`,
+/// `cylinder`, `cone`, `torus`, `union`, `intersection`, `difference` and
+/// `object`, with `translate`, `rotate`, `scale` and `matrix`, pigment
+/// colours, `finish` and `interior { ior }`, and `#declare` of values,
+/// objects and textures. Anything else is skipped and reported in the
+/// returned warnings.
+///
+/// POV-Ray lights do not fade by default, lights without `fade_distance`
+/// fall off from the camera `look_at` point on, so that they keep their
+/// brightness in the middle of the view.
+pub fn read_pov(text: &str) -> io::Result<(Scene, Vec<String>)> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        warnings: Vec::new(),
+        declared: HashMap::new(),
+        objects: Vec::new(),
+        lights: Vec::new(),
+        camera: None,
+    };
+    parser.scene()?;
+
+    let mut scene = Scene::new();
+    let target = parser
+        .camera
+        .as_ref()
+        .map_or(Vector::from(0.0), |c| c.look_at);
+    for light in &parser.lights {
+        scene.lights.push(light.build(&target));
+    }
+    for object in &parser.objects {
+        scene.objects.push(object.build(None)?);
+    }
+    scene.camera = parser.camera.map(|camera| camera.build());
+    Ok((scene, parser.warnings))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Word(String),
+    Directive(String),
+    Str(String),
+    Symbol(char),
+}
+
+fn tokenize(text: &str) -> io::Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let sign = chars.get(i + 1).is_some_and(|&s| s == '+' || s == '-');
+                let digit = chars.get(i + if sign { 2 } else { 1 });
+                if digit.is_some_and(|d| d.is_ascii_digit()) {
+                    i += if sign { 2 } else { 1 };
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let number: String = chars[start..i].iter().collect();
+            let value = number
+                .parse()
+                .map_err(|_| invalid(&format!("line {}: bad number {}", line, number)))?;
+            tokens.push((Token::Number(value), line));
+        } else if c.is_alphabetic() || c == '_' || c == '#' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(if c == '#' {
+                (Token::Directive(word[1..].to_string()), line)
+            } else {
+                (Token::Word(word), line)
+            });
+        } else if c == '"' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != '"' && chars[i] != '\n' {
+                i += 1;
+            }
+            tokens.push((Token::Str(chars[start..i].iter().collect()), line));
+            i += 1;
+        } else {
+            tokens.push((Token::Symbol(c), line));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Float or vector, vectors have up to five components for colours.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Float(f64),
+    Vector(Vec<f64>),
+}
+
+impl Value {
+    fn components(&self, n: usize) -> Vec<f64> {
+        match *self {
+            Value::Float(f) => vec![f; n],
+            Value::Vector(ref v) => (0..n).map(|i| v.get(i).cloned().unwrap_or(0.0)).collect(),
+        }
+    }
+
+    fn vector(&self) -> Vector {
+        let v = self.components(3);
+        Vector::from((v[0], v[1], v[2]))
+    }
+
+    fn combine(self, other: Value, op: fn(f64, f64) -> f64) -> Value {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => Value::Float(op(a, b)),
+            (a, b) => {
+                let n = match (&a, &b) {
+                    (Value::Vector(a), Value::Vector(b)) => a.len().max(b.len()),
+                    (Value::Vector(v), _) | (_, Value::Vector(v)) => v.len(),
+                    _ => unreachable!(),
+                };
+                let (a, b) = (a.components(n), b.components(n));
+                Value::Vector(a.iter().zip(&b).map(|(&a, &b)| op(a, b)).collect())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Finish {
+    ambient: f64,
+    diffuse: f64,
+    specular: f64,
+    roughness: f64,
+    phong: f64,
+    phong_size: f64,
+    reflection: f64,
+}
+
+/// Pigment colour as red, green, blue, filter and transmit, finish and
+/// index of refraction.
+#[derive(Clone, Copy)]
+struct Texture {
+    pigment: [f64; 5],
+    finish: Finish,
+    ior: f64,
+}
+
+impl Texture {
+    fn new() -> Texture {
+        Texture {
+            pigment: [0.0; 5],
+            finish: Finish {
+                ambient: 0.1,
+                diffuse: 0.6,
+                specular: 0.0,
+                roughness: 0.05,
+                phong: 0.0,
+                phong_size: 40.0,
+                reflection: 0.0,
+            },
+            ior: 1.0,
+        }
+    }
+
+    fn surface(&self) -> Surface {
+        let (c, f) = (&self.pigment, &self.finish);
+        let mut surface = Surface::new();
+        surface.color = Color::from((c[0], c[1], c[2]));
+        surface.k_a = f.ambient;
+        surface.k_d = f.diffuse;
+        surface.k_s = f.phong.max(f.specular);
+        surface.p = if f.phong > 0.0 {
+            f.phong_size
+        } else {
+            1.0 / f.roughness
+        } as i32;
+        surface.k_r = f.reflection;
+        surface.k_t = c[3].max(c[4]);
+        surface.medium = Medium {
+            n_refr: self.ior,
+            betta: 0.0,
+        };
+        surface
+    }
+}
+
+#[derive(Clone)]
+enum Shape {
+    Sphere(Vector, f64),
+    Plane(Vector, f64),
+    Box(Vector, Vector),
+    Cylinder(Vector, Vector, f64),
+    Cone(Vector, f64, Vector, f64),
+    Torus(f64, f64),
+    Csg(CsgOperation, Vec<Object>),
+    /// object of an unsupported declaration, dropped from the scene
+    Dropped,
+}
+
+#[derive(Clone)]
+struct Object {
+    shape: Shape,
+    texture: Option<Texture>,
+    matrix: Matrix,
+}
+
+impl Object {
+    /// Children of CSG objects without texture take the one of the parent.
+    /// Fails for degenerate shapes.
+    fn build(&self, inherited: Option<&Texture>) -> io::Result<Box<GObject>> {
+        let texture = self.texture.as_ref().or(inherited);
+        let material = texture.map_or_else(|| Texture::new().surface(), Texture::surface);
+        let object: Box<GObject> = match self.shape {
+            Shape::Sphere(center, radius) => Box::new(Sphere::new(material, center, radius)),
+            Shape::Plane(normal, distance) => {
+                let length = !normal;
+                if length == 0.0 || !length.is_finite() {
+                    return Err(invalid("plane without a normal"));
+                }
+                Box::new(Plane::new(material, normal / length, -distance / length))
+            }
+            Shape::Box(a, b) => {
+                let min = Vector::from((a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)));
+                let size = Vector::from(((a.x - b.x).abs(), (a.y - b.y).abs(), (a.z - b.z).abs()));
+                Box::new(geometry::Box::new(
+                    material,
+                    min,
+                    Vector::from((size.x, 0.0, 0.0)),
+                    Vector::from((0.0, size.y, 0.0)),
+                    Vector::from((0.0, 0.0, size.z)),
+                ))
+            }
+            Shape::Cylinder(a, b, radius) => Box::new(Cylinder::new(material, a, b - a, radius)),
+            Shape::Cone(a, radius1, b, radius2) => {
+                Box::new(Frustum::new(material, a, b - a, radius1, radius2))
+            }
+            Shape::Torus(major, minor) => Box::new(Torus::new(
+                material,
+                Vector::from(0.0),
+                Vector::from((0.0, 1.0, 0.0)),
+                major,
+                minor,
+            )),
+            Shape::Csg(operation, ref children) => Box::new(Csg::new(
+                operation,
+                children
+                    .iter()
+                    .map(|c| c.build(texture))
+                    .collect::<io::Result<_>>()?,
+            )),
+            Shape::Dropped => unreachable!("dropped objects are left out of the scene"),
+        };
+        if self.matrix == Matrix::from_scalar(1.0) {
+            Ok(object)
+        } else {
+            Ok(Box::new(Instance::new(Rc::from(object), self.matrix)))
+        }
+    }
+}
+
+struct CameraSpec {
+    orthographic: bool,
+    location: Vector,
+    look_at: Vector,
+    direction: Vector,
+    up: Vector,
+    right: Vector,
+    sky: Vector,
+    angle: Option<f64>,
+    aimed: bool,
+}
+
+impl CameraSpec {
+    fn build(&self) -> Box<Camera> {
+        let (dir, up) = if self.aimed {
+            (
+                (self.look_at - self.location).normalize() * !self.direction,
+                self.sky,
+            )
+        } else {
+            (self.direction, self.up)
+        };
+        let aspect = !self.right / !self.up;
+        if self.orthographic {
+            return Box::new(Orthographic::new(
+                self.location,
+                dir,
+                up,
+                !self.right,
+                !self.up,
+            ));
+        }
+        let fov = match self.angle {
+            Some(angle) => angle.to_radians(),
+            None => 2.0 * (0.5 * !self.right / !self.direction).atan(),
+        };
+        Box::new(Perspective::new(self.location, dir, up, fov, aspect))
+    }
+}
+
+struct LightSpec {
+    position: Vector,
+    color: Color,
+    spot: bool,
+    parallel: bool,
+    point_at: Vector,
+    radius: f64,
+    falloff: f64,
+    tightness: f64,
+    fade_distance: Option<f64>,
+}
+
+impl LightSpec {
+    fn build(&self, target: &Vector) -> Box<LightSource> {
+        let scale = self
+            .fade_distance
+            .unwrap_or_else(|| !(*target - self.position));
+        let dir = (self.point_at - self.position).normalize();
+        if self.parallel {
+            Box::new(DirectionalLight::new(self.color, dir))
+        } else if self.spot {
+            Box::new(SpotLight::new(
+                self.color,
+                self.position,
+                dir,
+                self.radius.to_radians().cos(),
+                self.falloff.to_radians().cos(),
+                self.tightness as i32,
+                scale,
+            ))
+        } else {
+            Box::new(PointLight::new(self.color, self.position, scale))
+        }
+    }
+}
+
+enum Declared {
+    Value(Value),
+    Object(Box<Object>),
+    Texture(Texture),
+    Pigment([f64; 5]),
+    Finish(Finish),
+    /// unsupported object block, by its keyword
+    Unsupported(String),
+}
+
+/// Colours of `colors.inc` known without reading it.
+const COLORS: &[(&str, [f64; 5])] = &[
+    ("White", [1.0, 1.0, 1.0, 0.0, 0.0]),
+    ("Black", [0.0, 0.0, 0.0, 0.0, 0.0]),
+    ("Clear", [1.0, 1.0, 1.0, 1.0, 0.0]),
+    ("Red", [1.0, 0.0, 0.0, 0.0, 0.0]),
+    ("Green", [0.0, 1.0, 0.0, 0.0, 0.0]),
+    ("Blue", [0.0, 0.0, 1.0, 0.0, 0.0]),
+    ("Yellow", [1.0, 1.0, 0.0, 0.0, 0.0]),
+    ("Cyan", [0.0, 1.0, 1.0, 0.0, 0.0]),
+    ("Magenta", [1.0, 0.0, 1.0, 0.0, 0.0]),
+    ("Orange", [1.0, 0.5, 0.0, 0.0, 0.0]),
+    ("Gray", [0.752941, 0.752941, 0.752941, 0.0, 0.0]),
+    ("Grey", [0.752941, 0.752941, 0.752941, 0.0, 0.0]),
+    ("Gray25", [0.25, 0.25, 0.25, 0.0, 0.0]),
+    ("Gray50", [0.5, 0.5, 0.5, 0.0, 0.0]),
+    ("Gray75", [0.75, 0.75, 0.75, 0.0, 0.0]),
+];
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    warnings: Vec<String>,
+    declared: HashMap<String, Declared>,
+    objects: Vec<Object>,
+    lights: Vec<LightSpec>,
+    camera: Option<CameraSpec>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(0, |t| t.1)
+    }
+
+    fn error(&self, message: &str) -> io::Error {
+        invalid(&format!("line {}: {}", self.line(), message))
+    }
+
+    fn warn(&mut self, message: String) {
+        let warning = format!("line {}: {}", self.line(), message);
+        self.warnings.push(warning);
+    }
+
+    fn next(&mut self) -> io::Result<Token> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_symbol(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Symbol(c))
+    }
+
+    fn skip_symbol(&mut self, c: char) -> bool {
+        if self.is_symbol(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> io::Result<()> {
+        if self.skip_symbol(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    /// Skips a `{ ... }` block if one follows.
+    fn skip_block(&mut self) -> io::Result<()> {
+        if self.skip_symbol('{') {
+            self.skip_rest()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Skips the rest of a block up to and including its closing brace.
+    fn skip_rest(&mut self) -> io::Result<()> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Symbol('{') => depth += 1,
+                Token::Symbol('}') => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Warns about an unsupported word and skips its block.
+    fn unsupported(&mut self, word: &str, context: &str) -> io::Result<()> {
+        self.warn(format!("{} '{}' is not supported, ignored", context, word));
+        self.skip_block()
+    }
+
+    fn scene(&mut self) -> io::Result<()> {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Directive(directive) => {
+                    self.pos += 1;
+                    self.directive(&directive)?;
+                }
+                Token::Word(word) => {
+                    self.pos += 1;
+                    match word.as_str() {
+                        "camera" => {
+                            let camera = self.camera()?;
+                            if self.camera.is_some() {
+                                self.warn("only the last camera is used".to_string());
+                            }
+                            self.camera = Some(camera);
+                        }
+                        "light_source" => {
+                            let light = self.light()?;
+                            self.lights.push(light);
+                        }
+                        _ => match self.object(&word)? {
+                            Some(Object {
+                                shape: Shape::Dropped,
+                                ..
+                            }) => {}
+                            Some(object) => self.objects.push(object),
+                            None => self.unsupported(&word, "statement")?,
+                        },
+                    }
+                }
+                _ => {
+                    let message = format!("unexpected {:?}", token);
+                    return Err(self.error(&message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, directive: &str) -> io::Result<()> {
+        match directive {
+            "declare" | "local" => {
+                let name = match self.next()? {
+                    Token::Word(name) => name,
+                    _ => return Err(self.error("expected identifier after #declare")),
+                };
+                self.expect('=')?;
+                let value = self.declaration()?;
+                self.skip_symbol(';');
+                self.declared.insert(name, value);
+            }
+            "version" => {
+                self.expression()?;
+                self.skip_symbol(';');
+            }
+            "include" => {
+                let file = match self.next()? {
+                    Token::Str(file) => file,
+                    _ => return Err(self.error("expected file name after #include")),
+                };
+                if file != "colors.inc" {
+                    self.warn(format!("#include \"{}\" is not read", file));
+                }
+            }
+            "if" | "ifdef" | "ifndef" | "while" | "for" | "macro" | "switch" => {
+                // skip to the matching #end
+                self.warn(format!(
+                    "#{} is not supported, skipped to its #end",
+                    directive
+                ));
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.next()? {
+                        Token::Directive(ref d)
+                            if ["if", "ifdef", "ifndef", "while", "for", "macro", "switch"]
+                                .contains(&d.as_str()) =>
+                        {
+                            depth += 1
+                        }
+                        Token::Directive(ref d) if d == "end" => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {
+                // skip the arguments up to the end of the line
+                let line = self.line();
+                self.warn(format!("#{} is not supported, ignored", directive));
+                while self.peek().is_some() && self.tokens[self.pos].1 == line {
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn declaration(&mut self) -> io::Result<Declared> {
+        let word = match self.peek() {
+            Some(Token::Word(word)) => word.clone(),
+            _ => return Ok(Declared::Value(self.expression()?)),
+        };
+        match word.as_str() {
+            "texture" => {
+                self.pos += 1;
+                Ok(Declared::Texture(self.texture(None)?))
+            }
+            "pigment" => {
+                self.pos += 1;
+                Ok(Declared::Pigment(self.pigment(None)?))
+            }
+            "finish" => {
+                self.pos += 1;
+                Ok(Declared::Finish(self.finish(None)?))
+            }
+            "color" | "colour" | "rgb" | "rgbf" | "rgbt" | "rgbft" => {
+                let color = self.color()?;
+                Ok(Declared::Value(Value::Vector(color.to_vec())))
+            }
+            _ => {
+                let start = self.pos;
+                self.pos += 1;
+                if let Some(object) = self.object(&word)? {
+                    return Ok(Declared::Object(Box::new(object)));
+                }
+                // no expression is followed by a block
+                if self.peek() == Some(&Token::Symbol('{')) {
+                    self.unsupported(&word, "object")?;
+                    return Ok(Declared::Unsupported(word));
+                }
+                self.pos = start;
+                Ok(Declared::Value(self.expression()?))
+            }
+        }
+    }
+
+    fn float(&mut self) -> io::Result<f64> {
+        match self.expression()? {
+            Value::Float(f) => Ok(f),
+            Value::Vector(ref v) => Ok(v[0]),
+        }
+    }
+
+    fn vector(&mut self) -> io::Result<Vector> {
+        Ok(self.expression()?.vector())
+    }
+
+    fn expression(&mut self) -> io::Result<Value> {
+        let mut value = self.term()?;
+        loop {
+            if self.skip_symbol('+') {
+                value = value.combine(self.term()?, |a, b| a + b);
+            } else if self.skip_symbol('-') {
+                value = value.combine(self.term()?, |a, b| a - b);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> io::Result<Value> {
+        let mut value = self.unary()?;
+        loop {
+            if self.skip_symbol('*') {
+                value = value.combine(self.unary()?, |a, b| a * b);
+            } else if self.skip_symbol('/') {
+                value = value.combine(self.unary()?, |a, b| a / b);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> io::Result<Value> {
+        if self.skip_symbol('-') {
+            Ok(Value::Float(-1.0).combine(self.unary()?, |a, b| a * b))
+        } else if self.skip_symbol('+') {
+            self.unary()
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> io::Result<Value> {
+        match self.next()? {
+            Token::Number(n) => Ok(Value::Float(n)),
+            Token::Symbol('<') => {
+                let mut items = vec![self.float()?];
+                while self.skip_symbol(',') {
+                    items.push(self.float()?);
+                }
+                self.expect('>')?;
+                Ok(Value::Vector(items))
+            }
+            Token::Symbol('(') => {
+                let value = self.expression()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Token::Word(word) => {
+                let unit = |i: usize| {
+                    let mut v = vec![0.0; 3];
+                    v[i] = 1.0;
+                    Ok(Value::Vector(v))
+                };
+                match word.as_str() {
+                    "x" => unit(0),
+                    "y" => unit(1),
+                    "z" => unit(2),
+                    "pi" => Ok(Value::Float(::std::f64::consts::PI)),
+                    "true" | "yes" | "on" => Ok(Value::Float(1.0)),
+                    "false" | "no" | "off" => Ok(Value::Float(0.0)),
+                    "sqrt" | "sin" | "cos" | "tan" | "abs" | "radians" | "degrees" | "pow"
+                    | "min" | "max" => {
+                        self.expect('(')?;
+                        let a = self.float()?;
+                        let b = if self.skip_symbol(',') {
+                            self.float()?
+                        } else {
+                            0.0
+                        };
+                        self.expect(')')?;
+                        Ok(Value::Float(match word.as_str() {
+                            "sqrt" => a.sqrt(),
+                            "sin" => a.sin(),
+                            "cos" => a.cos(),
+                            "tan" => a.tan(),
+                            "abs" => a.abs(),
+                            "radians" => a.to_radians(),
+                            "degrees" => a.to_degrees(),
+                            "pow" => a.powf(b),
+                            "min" => a.min(b),
+                            _ => a.max(b),
+                        }))
+                    }
+                    _ => match self.declared.get(&word) {
+                        Some(Declared::Value(value)) => Ok(value.clone()),
+                        _ => match COLORS.iter().find(|c| c.0 == word) {
+                            Some(color) => Ok(Value::Vector(color.1.to_vec())),
+                            None => Err(self.error(&format!("unknown identifier '{}'", word))),
+                        },
+                    },
+                }
+            }
+            token => Err(self.error(&format!("unexpected {:?} in expression", token))),
+        }
+    }
+
+    /// Colour in any of the `color` forms, as rgbft.
+    fn color(&mut self) -> io::Result<[f64; 5]> {
+        let mut color = [0.0; 5];
+        let mut any = false;
+        loop {
+            let word = match self.peek() {
+                Some(Token::Word(word)) => word.clone(),
+                Some(&Token::Symbol('}')) | None => break,
+                _ if !any => {
+                    let v = self.expression()?.components(5);
+                    color.copy_from_slice(&v);
+                    return Ok(color);
+                }
+                _ => break,
+            };
+            let slot = ["red", "green", "blue", "filter", "transmit"]
+                .iter()
+                .position(|&c| c == word);
+            match word.as_str() {
+                "color" | "colour" => self.pos += 1,
+                "rgb" | "rgbf" | "rgbt" | "rgbft" => {
+                    self.pos += 1;
+                    let v = self.expression()?;
+                    let n = word.len();
+                    let v = v.components(n);
+                    color[..3].copy_from_slice(&v[..3]);
+                    match word.as_str() {
+                        "rgbf" => color[3] = v[3],
+                        "rgbt" => color[4] = v[3],
+                        "rgbft" => {
+                            color[3] = v[3];
+                            color[4] = v[4];
+                        }
+                        _ => {}
+                    }
+                    any = true;
+                }
+                _ if slot.is_some() => {
+                    self.pos += 1;
+                    color[slot.unwrap()] = self.float()?;
+                    any = true;
+                }
+                _ if !any => {
+                    let v = self.expression()?.components(5);
+                    color.copy_from_slice(&v);
+                    return Ok(color);
+                }
+                _ => break,
+            }
+        }
+        Ok(color)
+    }
+
+    fn camera(&mut self) -> io::Result<CameraSpec> {
+        self.expect('{')?;
+        let mut camera = CameraSpec {
+            orthographic: false,
+            location: Vector::from(0.0),
+            look_at: Vector::from((0.0, 0.0, 1.0)),
+            direction: Vector::from((0.0, 0.0, 1.0)),
+            up: Vector::from((0.0, 1.0, 0.0)),
+            right: Vector::from((1.33, 0.0, 0.0)),
+            sky: Vector::from((0.0, 1.0, 0.0)),
+            angle: None,
+            aimed: false,
+        };
+        while !self.skip_symbol('}') {
+            let word = match self.next()? {
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in camera", token))),
+            };
+            match word.as_str() {
+                "perspective" => camera.orthographic = false,
+                "orthographic" => camera.orthographic = true,
+                "location" => camera.location = self.vector()?,
+                "look_at" => {
+                    camera.look_at = self.vector()?;
+                    camera.aimed = true;
+                }
+                "direction" => camera.direction = self.vector()?,
+                "up" => camera.up = self.vector()?,
+                "right" => camera.right = self.vector()?,
+                "sky" => camera.sky = self.vector()?,
+                "angle" => camera.angle = Some(self.float()?),
+                _ => {
+                    self.warn(format!("camera '{}' is not supported, ignored", word));
+                    if !self.is_symbol('{') && !self.is_symbol('}') && !self.peek_is_word() {
+                        self.expression()?;
+                    }
+                    self.skip_block()?;
+                }
+            }
+        }
+        Ok(camera)
+    }
+
+    fn peek_is_word(&self) -> bool {
+        matches!(self.peek(), Some(&Token::Word(_)))
+    }
+
+    fn light(&mut self) -> io::Result<LightSpec> {
+        self.expect('{')?;
+        let position = self.vector()?;
+        self.skip_symbol(',');
+        let color = self.color()?;
+        let mut light = LightSpec {
+            position,
+            color: Color::from((color[0], color[1], color[2])),
+            spot: false,
+            parallel: false,
+            point_at: Vector::from((0.0, 0.0, 1.0)),
+            radius: 30.0,
+            falloff: 45.0,
+            tightness: 0.0,
+            fade_distance: None,
+        };
+        let mut matrix = Matrix::from_scalar(1.0);
+        while !self.skip_symbol('}') {
+            let word = match self.next()? {
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in light", token))),
+            };
+            match word.as_str() {
+                "spotlight" => light.spot = true,
+                "parallel" => light.parallel = true,
+                "point_at" => light.point_at = self.vector()?,
+                "radius" => light.radius = self.float()?,
+                "falloff" => light.falloff = self.float()?,
+                "tightness" => light.tightness = self.float()?,
+                "fade_distance" => light.fade_distance = Some(self.float()?),
+                "fade_power" => {
+                    self.float()?;
+                }
+                "color" | "colour" => {
+                    self.pos -= 1;
+                    let c = self.color()?;
+                    light.color = Color::from((c[0], c[1], c[2]));
+                }
+                _ if self.transform(&word, &mut matrix)? => {}
+                _ => {
+                    self.warn(format!("light_source '{}' is not supported, ignored", word));
+                    self.skip_arguments()?;
+                }
+            }
+        }
+        light.position = matrix * light.position;
+        light.point_at = matrix * light.point_at;
+        Ok(light)
+    }
+
+    /// Skips values and a block following an unsupported keyword.
+    fn skip_arguments(&mut self) -> io::Result<()> {
+        while !self.is_symbol('{') && !self.is_symbol('}') && !self.peek_is_word() {
+            self.expression()?;
+            self.skip_symbol(',');
+        }
+        self.skip_block()
+    }
+
+    /// Applies a transformation keyword, returns false for other words.
+    fn transform(&mut self, word: &str, matrix: &mut Matrix) -> io::Result<bool> {
+        let m = match word {
+            "translate" => Matrix::translate(&self.vector()?),
+            "scale" => {
+                let s = self.vector()?;
+                let fix = |v: f64| if v == 0.0 { 1.0 } else { v };
+                Matrix::scale(&Vector::from((fix(s.x), fix(s.y), fix(s.z))))
+            }
+            "rotate" => {
+                let r = self.vector()?;
+                // POV-Ray turns about y the other way than Matrix::rotate_y
+                Matrix::rotate_x(r.x.to_radians())
+                    * Matrix::rotate_y(-r.y.to_radians())
+                    * Matrix::rotate_z(r.z.to_radians())
+            }
+            "matrix" => {
+                let v = self.expression()?.components(12);
+                Matrix([
+                    [v[0], v[1], v[2], 0.0],
+                    [v[3], v[4], v[5], 0.0],
+                    [v[6], v[7], v[8], 0.0],
+                    [v[9], v[10], v[11], 1.0],
+                ])
+            }
+            _ => return Ok(false),
+        };
+        *matrix *= m;
+        Ok(true)
+    }
+
+    /// Parses an object statement after its keyword, `None` if `word` is no
+    /// object.
+    fn object(&mut self, word: &str) -> io::Result<Option<Object>> {
+        let shape = match word {
+            "sphere" | "plane" | "box" | "cylinder" | "cone" | "torus" | "union"
+            | "intersection" | "difference" | "merge" | "object" => {
+                self.expect('{')?;
+                match word {
+                    "sphere" => {
+                        let center = self.vector()?;
+                        self.expect(',')?;
+                        Shape::Sphere(center, self.float()?)
+                    }
+                    "plane" => {
+                        let normal = self.vector()?;
+                        self.expect(',')?;
+                        Shape::Plane(normal, self.float()?)
+                    }
+                    "box" => {
+                        let a = self.vector()?;
+                        self.expect(',')?;
+                        Shape::Box(a, self.vector()?)
+                    }
+                    "cylinder" => {
+                        let a = self.vector()?;
+                        self.expect(',')?;
+                        let b = self.vector()?;
+                        self.expect(',')?;
+                        Shape::Cylinder(a, b, self.float()?)
+                    }
+                    "cone" => {
+                        let a = self.vector()?;
+                        self.expect(',')?;
+                        let radius1 = self.float()?;
+                        self.expect(',')?;
+                        let b = self.vector()?;
+                        self.expect(',')?;
+                        Shape::Cone(a, radius1, b, self.float()?)
+                    }
+                    "torus" => {
+                        let major = self.float()?;
+                        self.expect(',')?;
+                        Shape::Torus(major, self.float()?)
+                    }
+                    "object" => {
+                        let name = match self.next()? {
+                            Token::Word(name) => name,
+                            _ => return Err(self.error("expected object identifier")),
+                        };
+                        match self.declared.get(&name) {
+                            Some(Declared::Object(object)) => {
+                                let object = (**object).clone();
+                                return self.modifiers(object).map(Some);
+                            }
+                            Some(Declared::Unsupported(keyword)) => {
+                                let message =
+                                    format!("object '{}' is an unsupported {}, dropped", name, keyword);
+                                self.warn(message);
+                                self.skip_rest()?;
+                                return Ok(Some(Object {
+                                    shape: Shape::Dropped,
+                                    texture: None,
+                                    matrix: Matrix::from_scalar(1.0),
+                                }));
+                            }
+                            _ => return Err(self.error(&format!("unknown object '{}'", name))),
+                        }
+                    }
+                    _ => {
+                        if word == "merge" {
+                            self.warn("merge is read as union".to_string());
+                        }
+                        let operation = match word {
+                            "intersection" => CsgOperation::Intersection,
+                            "difference" => CsgOperation::Difference,
+                            _ => CsgOperation::Union,
+                        };
+                        Shape::Csg(operation, Vec::new())
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+        let object = Object {
+            shape,
+            texture: None,
+            matrix: Matrix::from_scalar(1.0),
+        };
+        let mut object = self.modifiers(object)?;
+        if let Shape::Csg(operation, ref mut children) = object.shape {
+            if children.is_empty() {
+                return Err(self.error(&format!("{} without objects", word)));
+            }
+            // a difference without its base is empty
+            let base_dropped = match children[0].shape {
+                Shape::Dropped => operation == CsgOperation::Difference,
+                _ => false,
+            };
+            children.retain(|child| !matches!(child.shape, Shape::Dropped));
+            if base_dropped {
+                children.clear();
+            }
+        }
+        if let Shape::Csg(_, ref children) = object.shape {
+            if children.is_empty() {
+                object.shape = Shape::Dropped;
+            }
+        }
+        Ok(Some(object))
+    }
+
+    /// Object modifiers up to the closing brace.
+    fn modifiers(&mut self, mut object: Object) -> io::Result<Object> {
+        loop {
+            let word = match self.next()? {
+                Token::Symbol('}') => return Ok(object),
+                Token::Symbol(',') => continue,
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in object", token))),
+            };
+            if self.transform(&word, &mut object.matrix)? {
+                continue;
+            }
+            match word.as_str() {
+                "texture" => {
+                    let texture = self.texture(object.texture)?;
+                    object.texture = Some(texture);
+                }
+                "pigment" => {
+                    let mut texture = object.texture.unwrap_or_else(Texture::new);
+                    texture.pigment = self.pigment(Some(texture.pigment))?;
+                    object.texture = Some(texture);
+                }
+                "finish" => {
+                    let mut texture = object.texture.unwrap_or_else(Texture::new);
+                    texture.finish = self.finish(Some(texture.finish))?;
+                    object.texture = Some(texture);
+                }
+                "interior" => {
+                    let mut texture = object.texture.unwrap_or_else(Texture::new);
+                    texture.ior = self.interior(texture.ior)?;
+                    object.texture = Some(texture);
+                }
+                "open" => self.warn("open objects are not supported, kept closed".to_string()),
+                _ => {
+                    let start = self.pos;
+                    match self.object(&word)? {
+                        Some(child) => match object.shape {
+                            Shape::Csg(_, ref mut children) => children.push(child),
+                            _ => {
+                                self.pos = start;
+                                self.unsupported(&word, "object modifier")?;
+                            }
+                        },
+                        None => {
+                            self.warn(format!(
+                                "object modifier '{}' is not supported, ignored",
+                                word
+                            ));
+                            self.skip_arguments()?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn texture(&mut self, base: Option<Texture>) -> io::Result<Texture> {
+        self.expect('{')?;
+        let mut texture = base.unwrap_or_else(Texture::new);
+        loop {
+            let word = match self.next()? {
+                Token::Symbol('}') => return Ok(texture),
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in texture", token))),
+            };
+            match word.as_str() {
+                "pigment" => texture.pigment = self.pigment(Some(texture.pigment))?,
+                "finish" => texture.finish = self.finish(Some(texture.finish))?,
+                _ => match self.declared.get(&word) {
+                    Some(&Declared::Texture(declared)) => texture = declared,
+                    _ => {
+                        self.warn(format!("texture '{}' is not supported, ignored", word));
+                        self.skip_arguments()?;
+                    }
+                },
+            }
+        }
+    }
+
+    fn pigment(&mut self, base: Option<[f64; 5]>) -> io::Result<[f64; 5]> {
+        self.expect('{')?;
+        let mut pigment = base.unwrap_or([0.0; 5]);
+        if let Some(Token::Word(word)) = self.peek() {
+            if let Some(&Declared::Pigment(declared)) = self.declared.get(word) {
+                pigment = declared;
+                self.pos += 1;
+            }
+        }
+        if self.skip_symbol('}') {
+            return Ok(pigment);
+        }
+        let start = self.pos;
+        match self.color() {
+            Ok(color) if self.is_symbol('}') => {
+                self.pos += 1;
+                Ok(color)
+            }
+            _ => {
+                self.pos = start;
+                self.warn("pigment patterns are not supported, ignored".to_string());
+                self.skip_rest()?;
+                Ok(pigment)
+            }
+        }
+    }
+
+    fn finish(&mut self, base: Option<Finish>) -> io::Result<Finish> {
+        self.expect('{')?;
+        let mut finish = base.unwrap_or(Texture::new().finish);
+        loop {
+            let word = match self.next()? {
+                Token::Symbol('}') => return Ok(finish),
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in finish", token))),
+            };
+            match word.as_str() {
+                "ambient" => finish.ambient = self.color()?[0],
+                "diffuse" => finish.diffuse = self.float()?,
+                "specular" => finish.specular = self.float()?,
+                "roughness" => finish.roughness = self.float()?,
+                "phong" => finish.phong = self.float()?,
+                "phong_size" => finish.phong_size = self.float()?,
+                "reflection" => {
+                    if self.skip_symbol('{') {
+                        finish.reflection = self.color()?[0];
+                        self.skip_rest()?;
+                    } else {
+                        finish.reflection = self.color()?[0];
+                    }
+                }
+                _ => match self.declared.get(&word) {
+                    Some(&Declared::Finish(declared)) => finish = declared,
+                    _ => {
+                        self.warn(format!("finish '{}' is not supported, ignored", word));
+                        self.skip_arguments()?;
+                    }
+                },
+            }
+        }
+    }
+
+    fn interior(&mut self, mut ior: f64) -> io::Result<f64> {
+        self.expect('{')?;
+        loop {
+            let word = match self.next()? {
+                Token::Symbol('}') => return Ok(ior),
+                Token::Word(word) => word,
+                token => return Err(self.error(&format!("unexpected {:?} in interior", token))),
+            };
+            if word == "ior" {
+                ior = self.float()?;
+            } else {
+                self.warn(format!("interior '{}' is not supported, ignored", word));
+                self.skip_arguments()?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"
+#include "colors.inc"
+#version 3.7;
+#declare Radius = 2 * 0.5;
+#declare Shiny = finish { phong 0.8 phong_size 60 reflection { 0.3 } }
+#declare Ball = sphere { <0, 0, 0>, Radius pigment { color Red } finish { Shiny } }
+
+/* legacy scene */
+global_settings { assumed_gamma 1.0 }
+camera {
+  location <0, 0, -10>
+  look_at <0, 0, 0>
+  angle 60
+}
+light_source { <0, 10, -10> color rgb <1, 1, 1> }
+light_source { <0, 10, 0>, White spotlight point_at <0, 0, 0> radius 10 falloff 20 }
+
+object { Ball translate 3*x }
+plane { y, -1 pigment { checker color White color Black } }
+difference {
+  box { <-1, -1, -1>, <1, 1, 1> }
+  cylinder { -2*z, 2*z, 0.5 open }
+  texture { pigment { rgbf <1, 1, 1, 0.9> } finish { ambient 0.2 diffuse 0.7 } }
+  interior { ior 1.5 }
+  no_shadow
+  translate <-3, 0, 0>
+}
+#while (I < 10)
+  sphere { <I, 0, 0>, 1 }
+#end
+union { cone { 0, 1, y, 0 } torus { 1, 0.25 rotate 90*x } }
+"#;
+
+    #[test]
+    fn should_read_scene() {
+        let (scene, warnings) = read_pov(SCENE).unwrap();
+        assert_eq!(scene.objects.len(), 4);
+        assert_eq!(scene.lights.len(), 2);
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+        assert!(warnings[0].contains("global_settings"));
+        assert!(warnings.iter().any(|w| w.contains("#while")));
+        assert!(warnings.iter().any(|w| w.contains("no_shadow")));
+
+        let environment = scene.environment();
+        let ray = environment.camera(0.0, 0.0);
+        assert_eq!(ray.org, Vector::from((0.0, 0.0, -10.0)));
+        assert!(!(ray.dir - Vector::from((0.0, 0.0, 1.0))) < 1e-9);
+        let edge = environment.camera(1.0, 0.0);
+        assert!(((edge.dir.x / edge.dir.z) - (30.0f64).to_radians().tan()).abs() < 1e-9);
+
+        // declared and translated ball
+        let ray = Ray::from(((3.0, 0.0, -10.0), (0.0, 0.0, 1.0)));
+        let (object, hit) = environment.intersect(&ray).unwrap();
+        assert!((hit.t - 9.0).abs() < 1e-9);
+        let texture = object.find_texture(&hit);
+        assert_eq!(texture.color, Color::from((1.0, 0.0, 0.0)));
+        assert_eq!((texture.k_s, texture.p, texture.k_r), (0.8, 60, 0.3));
+
+        // box with a hole drilled along z
+        let ray = Ray::from(((-3.0, 0.0, -10.0), (0.0, 0.0, 1.0)));
+        assert!(environment.intersect(&ray).is_none());
+        let ray = Ray::from(((-3.7, 0.0, -10.0), (0.0, 0.0, 1.0)));
+        let (object, hit) = environment.intersect(&ray).unwrap();
+        assert!((hit.t - 9.0).abs() < 1e-9);
+        let texture = object.find_texture(&hit);
+        assert_eq!((texture.k_a, texture.k_d, texture.k_t), (0.2, 0.7, 0.9));
+        assert_eq!(texture.medium.n_refr, 1.5);
+
+        // plane y = -1 keeps the default pigment
+        let ray = Ray::from(((0.0, 5.0, 5.0), (0.0, -1.0, 0.0)));
+        let (object, hit) = environment.intersect(&ray).unwrap();
+        assert!((hit.t - 6.0).abs() < 1e-9);
+        assert_eq!(object.find_texture(&hit).k_d, 0.6);
+
+        // torus rotated into the xy plane
+        let ray = Ray::from(((1.0, 0.0, -10.0), (0.0, 0.0, 1.0)));
+        let (_, hit) = environment.intersect(&ray).unwrap();
+        assert!((hit.t - 9.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn should_report_errors() {
+        assert!(read_pov("sphere { <0, 0, 0> 1 }").is_err());
+        assert!(read_pov("sphere { <0, 0, 0>, Unknown }").is_err());
+        assert!(read_pov("union { }").is_err());
+        assert!(read_pov("plane { <0, 0, 0>, 1 }").is_err());
+        let error = match read_pov("camera {\n location <0, 0, 0\n}") {
+            Err(error) => error,
+            Ok(_) => panic!("unterminated vector accepted"),
+        };
+        assert!(error.to_string().starts_with("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn should_render_ambient_and_transmission() {
+        use super::super::super::integrator::Whitted;
+
+        // no lights, the sphere is lit by its ambient term alone and seen
+        // through a clear pane
+        let text = "sphere { <0, 0, 5>, 1 pigment { rgb <1, 0.5, 0> } finish { ambient 0.5 } }
+plane { <0, 0, 1>, 2 pigment { rgbt <1, 1, 1, 0.6> } finish { ambient 0 diffuse 0 } }
+";
+        let (scene, _) = read_pov(text).unwrap();
+        let environment = scene.environment();
+        let ray = Ray::from(((0.0, 0.0, 0.0), (0.0, 0.0, 1.0)));
+        let color = Whitted::new().radiance(&environment, &ray);
+        assert!(!(color - Color::from((0.3, 0.15, 0.0))) < 1e-9, "{:?}", color);
+    }
+
+    #[test]
+    fn should_drop_unsupported_declarations() {
+        let text = "#declare M = mesh { triangle { <0, 0, 0>, <1, 0, 0>, <0, 1, 0> } }
+object { M translate <1, 0, 0> }
+union { object { M } sphere { <0, 0, 0>, 1 } }
+difference { object { M } sphere { <0, 0, 0>, 1 } }
+";
+        let (scene, warnings) = read_pov(text).unwrap();
+        assert_eq!(scene.objects.len(), 1);
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+        assert!(warnings[0].contains("mesh"));
+        assert!(warnings[1].contains("'M'"));
+    }
+}
@@ -0,0 +1,125 @@
+use super::*;
+
+use std::io::Read;
+
+use super::super::geometry::Mesh;
+
+/// Reads a binary or ASCII STL file. Facets do not share vertices, the
+/// normals are taken from the vertex order.
+pub fn read_stl<R: Read>(mut reader: R, material: Surface) -> io::Result<Mesh> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    // binary files may start with "solid" too, the size tells them apart
+    let vertices = if data.len() >= 84 && data.len() == 84 + 50 * facet_count(&data) {
+        read_binary(&data)
+    } else if data.starts_with(b"solid") {
+        read_ascii(&data)?
+    } else {
+        return Err(invalid("not an STL file"));
+    };
+    if vertices
+        .iter()
+        .any(|v| !(v.x.is_finite() && v.y.is_finite() && v.z.is_finite()))
+    {
+        return Err(invalid("STL vertex is not finite"));
+    }
+
+    let triangles = (0..vertices.len() / 3)
+        .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+    Ok(Mesh::new(material, vertices, triangles))
+}
+
+fn facet_count(data: &[u8]) -> usize {
+    (data[80] as usize)
+        | (data[81] as usize) << 8
+        | (data[82] as usize) << 16
+        | (data[83] as usize) << 24
+}
+
+fn read_binary(data: &[u8]) -> Vec<Vector> {
+    let float = |at: usize| {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&data[at..at + 4]);
+        f32::from_le_bytes(bytes) as f64
+    };
+    let mut vertices = Vec::with_capacity(3 * facet_count(data));
+    for facet in 0..facet_count(data) {
+        // skip the normal
+        let at = 84 + 50 * facet + 12;
+        for corner in 0..3 {
+            let at = at + 12 * corner;
+            vertices.push(Vector::from((float(at), float(at + 4), float(at + 8))));
+        }
+    }
+    vertices
+}
+
+fn read_ascii(data: &[u8]) -> io::Result<Vec<Vector>> {
+    let text = String::from_utf8_lossy(data);
+    let mut tokens = text.split_whitespace();
+    let mut vertices = Vec::new();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut coordinate = || {
+            tokens
+                .next()
+                .and_then(|s| s.parse::<f64>().ok())
+                .ok_or_else(|| invalid("bad STL vertex"))
+        };
+        vertices.push(Vector::from((coordinate()?, coordinate()?, coordinate()?)));
+    }
+    if vertices.len() % 3 != 0 {
+        return Err(invalid("STL facet without three vertices"));
+    }
+    Ok(vertices)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_stl() {
+        let text = b"solid part
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid part
+";
+        let mesh = read_stl(&text[..], Surface::new()).unwrap();
+        assert_eq!(mesh.triangles().len(), 1);
+        assert_eq!(mesh.vertices()[1], Vector::from((1.0, 0.0, 0.0)));
+
+        assert!(read_stl(&b"solid x\nvertex 0 0\n"[..], Surface::new()).is_err());
+        let nan = b"solid x\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 nan 0\n";
+        assert!(read_stl(&nan[..], Surface::new()).is_err());
+    }
+
+    #[test]
+    fn binary_stl() {
+        // header starting with "solid" like some exporters write
+        let mut data = b"solid binary".to_vec();
+        data.resize(80, 0);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for &v in &[
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0,
+        ] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&[0, 0]);
+
+        let mesh = read_stl(&data[..], Surface::new()).unwrap();
+        assert_eq!(mesh.triangles(), &[[0, 1, 2]]);
+        assert_eq!(mesh.vertices()[2], Vector::from((0.0, 2.0, 0.0)));
+        let ray = Ray::from(((0.5, 0.5, 1.0), (0.0, 0.0, -1.0)));
+        assert!(mesh.intersect(&ray).is_some());
+    }
+}
@@ -72,6 +72,15 @@ impl Matrix {
         }
     }
 
+    /// Transforms direction `v`, ignoring translation.
+    pub fn transform_dir(&self, v: &Vector) -> Vector {
+        Vector {
+            x: v.x * self.0[0][0] + v.y * self.0[1][0] + v.z * self.0[2][0],
+            y: v.x * self.0[0][1] + v.y * self.0[1][1] + v.z * self.0[2][1],
+            z: v.x * self.0[0][2] + v.y * self.0[1][2] + v.z * self.0[2][2],
+        }
+    }
+
     pub fn translate(v: &Vector) -> Matrix {
         Matrix([
             [1.0, 0.0, 0.0, 0.0],
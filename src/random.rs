@@ -0,0 +1,33 @@
+use std::cell::Cell;
+
+thread_local! {
+    static STATE: Cell<u64> = const { Cell::new(0x2545_f491_4f6c_dd1d) };
+}
+
+/// Uniformly distributed pseudo random number in `[0, 1)`.
+///
+/// Uses a per-thread xorshift generator, so the sequence is repeatable
+/// between runs.
+pub fn rnd() -> f64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn rnd_in_unit_interval() {
+        for _ in 0..1000 {
+            let r = rnd();
+            assert!(r >= 0.0 && r < 1.0);
+        }
+    }
+}
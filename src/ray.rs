@@ -1,14 +1,37 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+/// Default start of the ray interval, keeps surfaces from occluding
+/// themselves.
+const RAY_T_MIN: f64 = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Ray {
     pub org: Vector,
     pub dir: Vector,
+    /// moment within the camera shutter interval the ray samples
+    pub time: f64,
+    /// only intersections with t_min < t < t_max count
+    pub t_min: f64,
+    pub t_max: f64,
 }
 
 impl Ray {
     pub fn new(org: Vector, dir: Vector) -> Ray {
-        Ray { org, dir }
+        Ray::with_time(org, dir, 0.0)
+    }
+
+    pub fn with_time(org: Vector, dir: Vector, time: f64) -> Ray {
+        Ray {
+            org,
+            dir,
+            time,
+            t_min: RAY_T_MIN,
+            t_max: INFINITY,
+        }
+    }
+
+    pub fn contains(&self, t: f64) -> bool {
+        t > self.t_min && t < self.t_max
     }
 
     pub fn point(&self, t: f64) -> Vector {
@@ -1,62 +1,100 @@
 use super::*;
 
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Instant;
+
+use super::random::rnd;
+use super::stats::RenderStats;
 
 pub mod backend;
+pub mod options;
+pub mod progressive;
 
 pub use self::backend::RenderBackend;
+pub use self::options::{CancelToken, Progress, RenderOptions};
+pub use self::progressive::{render_scene_progressive, ProgressiveTarget};
+
+/// Takes the counters of a render started at `start`.
+fn finish_stats(environment: &Environment, start: Instant, nx: u32, ny: u32) -> RenderStats {
+    let mut stats = environment.take_stats();
+    stats.pixels = nx as u64 * ny as u64;
+    stats.wall_time = start.elapsed();
+    stats
+}
 
-use super::mediums::AIR;
+/// Stops a cancelled render before its next row, leaving the backend
+/// finished.
+fn check_cancel(options: &RenderOptions, backend: &mut RenderBackend) -> Result<()> {
+    if options.is_cancelled() {
+        backend.finish_render()?;
+        return Err(Error::new(ErrorKind::Interrupted, "render cancelled"));
+    }
+    Ok(())
+}
+
+/// Reports `rows_done` finished rows of a pass started at `start`.
+fn finish_row(options: &RenderOptions, pass: u32, rows_done: u32, ny: u32, start: Instant) {
+    options.report(&Progress {
+        pass,
+        rows_done,
+        rows: ny,
+        elapsed: start.elapsed(),
+    });
+}
 
 pub fn render_scene(
     environment: &Environment,
-    half_width: f64,
-    half_height: f64,
+    integrator: &Integrator,
     nx: u32,
     ny: u32,
+    options: &RenderOptions,
     backend: &mut RenderBackend,
-) -> Result<()> {
-    let pixel_width = 2.0 * half_width / nx as f64;
-    let pixel_height = 2.0 * half_height / ny as f64;
+) -> Result<RenderStats> {
+    let start = Instant::now();
+    environment.take_stats();
+
+    let pixel_width = 2.0 / nx as f64;
+    let pixel_height = 2.0 / ny as f64;
 
     backend.render_size(nx, ny);
 
     backend.start_render()?;
 
     // top left corner setup
-    let mut y = half_height;
+    let mut y = 1.0;
 
     for y_index in 0..ny {
-        let mut x = -half_width;
+        check_cancel(options, backend)?;
+        let mut x = -1.0;
         for x_index in 0..nx {
-            let mut ray = environment.camera(x, y);
-            let color = environment.trace(&AIR, 1.0, &mut ray).clip();
+            let ray = environment.camera(x, y);
+            let color = integrator.radiance(environment, &ray).clip();
             backend.put_pixel(x_index, y_index, &color)?;
             x += pixel_width;
         }
         y -= pixel_height;
+        finish_row(options, 1, y_index + 1, ny, start);
     }
 
     backend.finish_render()?;
-    Ok(())
-}
-
-fn rnd() -> f64 {
-    0.5
+    Ok(finish_stats(environment, start, nx, ny))
 }
 
 pub fn render_scene_supersampling_grid(
     environment: &Environment,
-    half_width: f64,
-    half_height: f64,
+    integrator: &Integrator,
     nx: u32,
     ny: u32,
     nx_sub: u32,
     ny_sub: u32,
+    options: &RenderOptions,
     backend: &mut RenderBackend,
-) -> Result<()> {
-    let pixel_width = 2.0 * half_width / nx as f64;
-    let pixel_height = 2.0 * half_height / ny as f64;
+) -> Result<RenderStats> {
+    let start = Instant::now();
+    environment.take_stats();
+
+    let pixel_width = 2.0 / nx as f64;
+    let pixel_height = 2.0 / ny as f64;
 
     let pixel_sub_width = pixel_width / nx_sub as f64;
     let pixel_sub_height = pixel_height / ny_sub as f64;
@@ -67,10 +105,11 @@ pub fn render_scene_supersampling_grid(
 
     backend.start_render()?;
 
-    let mut y = half_height;
+    let mut y = 1.0;
 
     for y_index in 0..ny {
-        let mut x = -half_width;
+        check_cancel(options, backend)?;
+        let mut x = -1.0;
         for x_index in 0..nx {
             let x1 = x - 0.5 * pixel_width;
             let y1 = y - 0.5 * pixel_height;
@@ -79,11 +118,11 @@ pub fn render_scene_supersampling_grid(
 
             for i_sub in 0..nx_sub {
                 for j_sub in 0..ny_sub {
-                    let mut ray = environment.camera(
+                    let ray = environment.camera(
                         x1 + pixel_sub_width * (i_sub as f64 + rnd()),
                         y1 + pixel_sub_height * (j_sub as f64 + rnd()),
                     );
-                    color += environment.trace(&AIR, 1.0, &mut ray);
+                    color += integrator.radiance(environment, &ray);
                 }
             }
             color /= primary_samples;
@@ -91,27 +130,31 @@ pub fn render_scene_supersampling_grid(
             x += pixel_width;
         }
         y -= pixel_height;
+        finish_row(options, 1, y_index + 1, ny, start);
     }
 
     backend.finish_render()?;
-    Ok(())
+    Ok(finish_stats(environment, start, nx, ny))
 }
 
 const MAX_ADAPTIVE_COUNT: u32 = 99;
 
 pub fn render_scene_supersampling_grid_adaptive(
     environment: &Environment,
-    half_width: f64,
-    half_height: f64,
+    integrator: &Integrator,
     nx: u32,
     ny: u32,
     nx_sub: u32,
     ny_sub: u32,
     variance: f64,
+    options: &RenderOptions,
     backend: &mut RenderBackend,
-) -> Result<()> {
-    let pixel_width = 2.0 * half_width / nx as f64;
-    let pixel_height = 2.0 * half_height / ny as f64;
+) -> Result<RenderStats> {
+    let start = Instant::now();
+    environment.take_stats();
+
+    let pixel_width = 2.0 / nx as f64;
+    let pixel_height = 2.0 / ny as f64;
 
     let pixel_sub_width = pixel_width / nx_sub as f64;
     let pixel_sub_height = pixel_height / ny_sub as f64;
@@ -120,10 +163,11 @@ pub fn render_scene_supersampling_grid_adaptive(
 
     backend.start_render()?;
 
-    let mut y = half_height;
+    let mut y = 1.0;
 
     for y_index in 0..ny {
-        let mut x = -half_width;
+        check_cancel(options, backend)?;
+        let mut x = -1.0;
         for x_index in 0..nx {
             let x1 = x - 0.5 * pixel_width;
             let y1 = y - 0.5 * pixel_height;
@@ -136,11 +180,11 @@ pub fn render_scene_supersampling_grid_adaptive(
             loop {
                 for i_sub in 0..nx_sub {
                     for j_sub in 0..ny_sub {
-                        let mut ray = environment.camera(
+                        let ray = environment.camera(
                             x1 + pixel_sub_width * (i_sub as f64 + rnd()),
                             y1 + pixel_sub_height * (j_sub as f64 + rnd()),
                         );
-                        let color = environment.trace(&AIR, 1.0, &mut ray);
+                        let color = integrator.radiance(environment, &ray);
                         sum += color;
                         disp += color & color;
                         count += 1;
@@ -157,8 +201,112 @@ pub fn render_scene_supersampling_grid_adaptive(
             x += pixel_width;
         }
         y -= pixel_height;
+        finish_row(options, 1, y_index + 1, ny, start);
     }
 
     backend.finish_render()?;
-    Ok(())
+    Ok(finish_stats(environment, start, nx, ny))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    use super::super::integrator::Depth;
+
+    /// Counts pixels and remembers whether the render was finished.
+    struct Recorder {
+        pixels: u32,
+        finished: bool,
+    }
+
+    impl RenderBackend for Recorder {
+        fn render_size(&mut self, _: u32, _: u32) {}
+
+        fn start_render(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn finish_render(&mut self) -> Result<()> {
+            self.finished = true;
+            Ok(())
+        }
+
+        fn put_pixel(&mut self, _: u32, _: u32, _: &Color) -> Result<()> {
+            self.pixels += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn progress_and_cancel() {
+        let environment = Environment::new();
+        let integrator = Depth::new(0.0, 10.0);
+        let rows = Cell::new(0);
+        let mut options = RenderOptions::new();
+        options.set_progress(|progress| {
+            rows.set(rows.get() + 1);
+            assert_eq!(progress.rows_done, rows.get());
+            assert_eq!(progress.rows, 8);
+        });
+        let mut backend = Recorder {
+            pixels: 0,
+            finished: false,
+        };
+        render_scene(&environment, &integrator, 4, 8, &options, &mut backend).unwrap();
+        assert_eq!(rows.get(), 8);
+        assert_eq!(backend.pixels, 32);
+
+        let cancel = options.cancel_token();
+        let mut options = RenderOptions::new();
+        options.set_cancel_token(cancel.clone());
+        options.set_progress(move |progress| {
+            if progress.rows_done == 3 {
+                cancel.cancel();
+            }
+        });
+        let mut backend = Recorder {
+            pixels: 0,
+            finished: false,
+        };
+        let error = render_scene_supersampling_grid(
+            &environment,
+            &integrator,
+            4,
+            8,
+            2,
+            2,
+            &options,
+            &mut backend,
+        ).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Interrupted);
+        assert_eq!(backend.pixels, 12);
+        assert!(backend.finished);
+
+        // cancelled after the last row the render is complete
+        let cancel = CancelToken::new();
+        let mut options = RenderOptions::new();
+        options.set_cancel_token(cancel.clone());
+        options.set_progress(move |progress| {
+            if progress.rows_done == progress.rows {
+                cancel.cancel();
+            }
+        });
+        render_scene(&environment, &integrator, 4, 8, &options, &mut backend).unwrap();
+    }
+
+    #[test]
+    fn remaining_time() {
+        let progress = Progress {
+            pass: 1,
+            rows_done: 1,
+            rows: 4,
+            elapsed: Duration::from_millis(500),
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(progress.remaining(), Some(Duration::from_millis(1500)));
+    }
 }
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far a render has come, passed to the progress callback after every
+/// finished row.
+///
+/// Progressive renders report the rows of every pass separately, their
+/// preview pass counts rows of blocks. Other renders have a single pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// pass counted from 1
+    pub pass: u32,
+    pub rows_done: u32,
+    pub rows: u32,
+    /// time since the pass started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        self.rows_done as f64 / self.rows as f64
+    }
+
+    /// Time left in the pass if the remaining rows take as long as the
+    /// finished ones, `None` before the first row.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.rows_done == 0 {
+            return None;
+        }
+        let left = (self.rows - self.rows_done) as f64 / self.rows_done as f64;
+        let nanos = self.elapsed.as_secs() as f64 * 1e9 + self.elapsed.subsec_nanos() as f64;
+        let nanos = (nanos * left) as u64;
+        Some(Duration::new(
+            nanos / 1_000_000_000,
+            (nanos % 1_000_000_000) as u32,
+        ))
+    }
+}
+
+/// Shared flag stopping a render, clones cancel the same render.
+#[derive(Clone, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> CancelToken {
+        CancelToken::new()
+    }
+}
+
+/// Callback receiving the progress of a render.
+type ProgressCallback<'a> = Box<Fn(&Progress) + 'a>;
+
+/// Progress reporting and cancellation of the render functions.
+///
+/// The token is checked before every row. A cancelled render finishes the
+/// backend with the rows done so far and fails with
+/// `io::ErrorKind::Interrupted`.
+pub struct RenderOptions<'a> {
+    progress: Option<ProgressCallback<'a>>,
+    cancel: CancelToken,
+}
+
+impl<'a> RenderOptions<'a> {
+    pub fn new() -> RenderOptions<'a> {
+        RenderOptions {
+            progress: None,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    pub fn set_progress<F: Fn(&Progress) + 'a>(&mut self, callback: F) {
+        self.progress = Some(Box::new(callback));
+    }
+
+    pub fn set_cancel_token(&mut self, cancel: CancelToken) {
+        self.cancel = cancel;
+    }
+
+    /// Token cancelling renders with these options.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    pub fn report(&self, progress: &Progress) {
+        if let Some(ref callback) = self.progress {
+            callback(progress);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+impl<'a> Default for RenderOptions<'a> {
+    fn default() -> RenderOptions<'a> {
+        RenderOptions::new()
+    }
+}
@@ -0,0 +1,258 @@
+use super::*;
+
+use std::time::Duration;
+
+use super::super::random::rnd;
+
+/// Side of the square pixel blocks of the preview pass.
+const PREVIEW_BLOCK: u32 = 8;
+
+/// When a progressive render stops. It stops at the first limit reached,
+/// without any it runs until cancelled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressiveTarget {
+    /// wall time, checked after every row and every pass
+    pub time: Option<Duration>,
+    /// samples per pixel, without the preview
+    pub samples: Option<u32>,
+    /// largest standard error of a pixel color, checked after every pass
+    pub noise: Option<f64>,
+}
+
+impl ProgressiveTarget {
+    pub fn new() -> ProgressiveTarget {
+        ProgressiveTarget {
+            time: None,
+            samples: None,
+            noise: None,
+        }
+    }
+}
+
+impl Default for ProgressiveTarget {
+    fn default() -> ProgressiveTarget {
+        ProgressiveTarget::new()
+    }
+}
+
+/// Renders the image in passes of random samples per pixel, every pass
+/// pushes all refined pixels to the backend again.
+///
+/// The first pass is a quick preview with one sample per block of
+/// `PREVIEW_BLOCK` pixels square, filling the whole block. The second pass
+/// takes one sample per pixel, every later pass doubles the samples taken
+/// so far. Pixels keep the sum of their samples and of their squares, the
+/// latter estimate the noise. Preview samples are not kept.
+pub fn render_scene_progressive(
+    environment: &Environment,
+    integrator: &Integrator,
+    nx: u32,
+    ny: u32,
+    target: &ProgressiveTarget,
+    options: &RenderOptions,
+    backend: &mut RenderBackend,
+) -> Result<RenderStats> {
+    let start = Instant::now();
+    environment.take_stats();
+
+    let pixel_width = 2.0 / nx as f64;
+    let pixel_height = 2.0 / ny as f64;
+
+    let pixels = nx as usize * ny as usize;
+    let mut sums = vec![Color::from(0.0); pixels];
+    let mut squares = vec![0.0; pixels];
+    let mut counts = vec![0u32; pixels];
+
+    backend.render_size(nx, ny);
+
+    backend.start_render()?;
+
+    let pass_start = Instant::now();
+    let block_rows = ny.div_ceil(PREVIEW_BLOCK);
+    let mut out_of_time = false;
+    for block_row in 0..block_rows {
+        check_cancel(options, backend)?;
+        let (y0, y1) = (
+            block_row * PREVIEW_BLOCK,
+            (block_row * PREVIEW_BLOCK + PREVIEW_BLOCK).min(ny),
+        );
+        for x0 in (0..nx).step_by(PREVIEW_BLOCK as usize) {
+            let x1 = (x0 + PREVIEW_BLOCK).min(nx);
+            // through the center of the block
+            let ray = environment.camera(
+                -1.0 + pixel_width * (x0 + x1 - 1) as f64 * 0.5,
+                1.0 - pixel_height * (y0 + y1 - 1) as f64 * 0.5,
+            );
+            let color = integrator.radiance(environment, &ray).clip();
+            for y_index in y0..y1 {
+                for x_index in x0..x1 {
+                    backend.put_pixel(x_index, y_index, &color)?;
+                }
+            }
+        }
+        finish_row(options, 1, block_row + 1, block_rows, pass_start);
+        if out_of(target, start) {
+            out_of_time = true;
+            break;
+        }
+    }
+
+    let mut pass = 2;
+    let mut taken = 0;
+    if !out_of_time {
+        'passes: loop {
+            let mut samples = taken.max(1);
+            if let Some(limit) = target.samples {
+                samples = samples.min(limit.max(1) - taken);
+            }
+            let pass_start = Instant::now();
+
+            // images without rows still stop when cancelled
+            check_cancel(options, backend)?;
+            for y_index in 0..ny {
+                check_cancel(options, backend)?;
+                for x_index in 0..nx {
+                    let i = (y_index * nx + x_index) as usize;
+                    for _ in 0..samples {
+                        let ray = environment.camera(
+                            -1.0 + pixel_width * (x_index as f64 + rnd() - 0.5),
+                            1.0 - pixel_height * (y_index as f64 + rnd() - 0.5),
+                        );
+                        let color = integrator.radiance(environment, &ray);
+                        sums[i] += color;
+                        squares[i] += color & color;
+                        counts[i] += 1;
+                    }
+                    let mean = sums[i] / counts[i] as f64;
+                    backend.put_pixel(x_index, y_index, &mean.clip())?;
+                }
+                finish_row(options, pass, y_index + 1, ny, pass_start);
+                if out_of(target, start) {
+                    break 'passes;
+                }
+            }
+            // passes over empty images are instant and double forever
+            taken = taken.saturating_add(samples);
+
+            if out_of(target, start) || target.samples.is_some_and(|limit| taken >= limit) {
+                break;
+            }
+            if let Some(noise) = target.noise {
+                if taken > 1 && max_error(&sums, &squares, taken) <= noise {
+                    break;
+                }
+            }
+            pass += 1;
+        }
+    }
+
+    backend.finish_render()?;
+    Ok(finish_stats(environment, start, nx, ny))
+}
+
+/// Whether the time target of a render started at `start` is reached.
+fn out_of(target: &ProgressiveTarget, start: Instant) -> bool {
+    target.time.is_some_and(|time| start.elapsed() >= time)
+}
+
+/// Largest standard error of the pixel means, all pixels having `n`
+/// samples.
+fn max_error(sums: &[Color], squares: &[f64], n: u32) -> f64 {
+    let n = n as f64;
+    let variance = sums
+        .iter()
+        .zip(squares)
+        .map(|(&sum, &square)| {
+            let mean = sum / n;
+            (square / n - (mean & mean)) / (n - 1.0)
+        })
+        .fold(0.0, f64::max);
+    variance.max(0.0).sqrt()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use super::super::super::geometry::Sphere;
+    use super::super::super::integrator::Depth;
+    use super::super::backend::NullRenderBackend;
+
+    #[test]
+    fn stops_at_targets() {
+        let sphere = Sphere::new(Surface::new(), Vector::from((0.0, 0.0, 3.0)), 1.0);
+        let mut environment = Environment::new();
+        environment.add_solid(&sphere);
+        let integrator = Depth::new(0.0, 10.0);
+        let passes = Cell::new(0);
+        let mut options = RenderOptions::new();
+        options.set_progress(|progress| passes.set(progress.pass));
+        let mut backend = NullRenderBackend::new();
+        let mut render = |target: &ProgressiveTarget| {
+            render_scene_progressive(
+                &environment,
+                &integrator,
+                8,
+                8,
+                target,
+                &options,
+                &mut backend,
+            )
+            .unwrap()
+        };
+
+        // a preview of one block, then 1, 1, 2 and 3 samples
+        let mut target = ProgressiveTarget::new();
+        target.samples = Some(7);
+        let stats = render(&target);
+        assert_eq!(stats.primary_rays, 1 + 7 * 64);
+        assert_eq!(passes.get(), 5);
+
+        // the edge of the sphere stays noisy
+        target.noise = Some(1e-9);
+        assert_eq!(render(&target).primary_rays, 1 + 7 * 64);
+
+        target.samples = None;
+        target.time = Some(Duration::from_secs(0));
+        let stats = render(&target);
+        assert_eq!(stats.primary_rays, 1);
+        assert_eq!(passes.get(), 1);
+    }
+
+    #[test]
+    fn uniform_image_has_no_noise() {
+        let environment = Environment::new();
+        let mut target = ProgressiveTarget::new();
+        target.noise = Some(1e-6);
+        let stats = render_scene_progressive(
+            &environment,
+            &Depth::new(0.0, 10.0),
+            4,
+            4,
+            &target,
+            &RenderOptions::new(),
+            &mut NullRenderBackend::new(),
+        )
+        .unwrap();
+        assert_eq!(stats.primary_rays, 1 + 2 * 16);
+    }
+
+    #[test]
+    fn empty_image_stops_in_time() {
+        let mut target = ProgressiveTarget::new();
+        target.time = Some(Duration::from_millis(10));
+        let stats = render_scene_progressive(
+            &Environment::new(),
+            &Depth::new(0.0, 10.0),
+            0,
+            0,
+            &target,
+            &RenderOptions::new(),
+            &mut NullRenderBackend::new(),
+        )
+        .unwrap();
+        assert_eq!(stats.primary_rays, 0);
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+
+use super::environment::{BACKGROUND, MAX_LEVEL, THRESHOLD};
+
+/// Image size and tracing limits a scene is rendered with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub max_level: u32,
+    pub threshold: f64,
+    pub background: Color,
+}
+
+impl RenderSettings {
+    pub fn new() -> RenderSettings {
+        RenderSettings {
+            width: 640,
+            height: 480,
+            max_level: MAX_LEVEL,
+            threshold: THRESHOLD,
+            background: BACKGROUND,
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings::new()
+    }
+}
+
+/// Objects, lights and camera owned together, as produced by the scene
+/// loaders.
+pub struct Scene {
+    pub objects: Vec<Box<GObject>>,
+    pub lights: Vec<Box<LightSource>>,
+    pub camera: Option<Box<Camera>>,
+    pub settings: RenderSettings,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            camera: None,
+            settings: RenderSettings::new(),
+        }
+    }
+
+    /// Environment with all objects and lights of the scene, its camera if
+    /// it has one and its tracing limits.
+    pub fn environment<'a>(&'a self) -> Environment<'a> {
+        let mut environment = Environment::new();
+        for object in &self.objects {
+            environment.add_solid(&**object);
+        }
+        for light in &self.lights {
+            environment.add_light(&**light);
+        }
+        if let Some(ref camera) = self.camera {
+            environment.set_camera_model(Box::new(&**camera));
+        }
+        environment.set_max_level(self.settings.max_level);
+        environment.set_threshold(self.settings.threshold);
+        environment.set_background(self.settings.background);
+        environment
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
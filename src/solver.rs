@@ -0,0 +1,234 @@
+//! Real roots of low degree polynomials, for surfaces given by polynomial
+//! equations in the ray parameter.
+//!
+//! Coefficients go from the highest power down, so `solve_quadratic(a, b, c)`
+//! solves `a x^2 + b x + c = 0`.
+
+use std::f64::consts::PI;
+use std::ops::Deref;
+
+/// Below this magnitude a leading coefficient counts as zero and the degree
+/// is lowered.
+const SOLVER_EPS: f64 = 1e-12;
+
+/// Real roots in ascending order, repeated roots may appear more than once.
+#[derive(Clone, Copy, Debug)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn new() -> Roots {
+        Roots {
+            values: [0.0; 4],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, x: f64) {
+        if x.is_finite() {
+            self.values[self.len] = x;
+            self.len += 1;
+        }
+    }
+
+    fn sort(mut self) -> Roots {
+        self.values[..self.len].sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+pub fn solve_linear(a: f64, b: f64) -> Roots {
+    let mut roots = Roots::new();
+    if a.abs() > SOLVER_EPS {
+        roots.push(-b / a);
+    }
+    roots
+}
+
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    if a.abs() <= SOLVER_EPS {
+        return solve_linear(b, c);
+    }
+
+    let mut roots = Roots::new();
+    let d = b * b - 4.0 * a * c;
+    if d < 0.0 {
+        return roots;
+    }
+
+    // avoid cancellation between -b and the root of discriminant
+    let q = -0.5 * (b + d.sqrt().copysign(b));
+    if q == 0.0 {
+        roots.push(0.0);
+        roots.push(0.0);
+    } else {
+        roots.push(q / a);
+        roots.push(c / q);
+    }
+    roots.sort()
+}
+
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    if a.abs() <= SOLVER_EPS {
+        return solve_quadratic(b, c, d);
+    }
+
+    // x^3 + b x^2 + c x + d = 0, then x = y - b / 3 gives y^3 + p y + q = 0
+    let (b, c, d) = (b / a, c / a, d / a);
+    let shift = b / 3.0;
+    let p = c - b * shift;
+    let q = 2.0 * shift * shift * shift - shift * c + d;
+
+    let mut roots = Roots::new();
+    let disc = 0.25 * q * q + p * p * p / 27.0;
+    if disc > 0.0 {
+        let s = disc.sqrt();
+        roots.push((-0.5 * q + s).cbrt() + (-0.5 * q - s).cbrt() - shift);
+    } else if p == 0.0 {
+        roots.push(-shift);
+    } else {
+        let r = (-p / 3.0).sqrt();
+        let phi = (-0.5 * q / (r * r * r)).clamp(-1.0, 1.0).acos();
+        for k in 0..3 {
+            roots.push(2.0 * r * ((phi + 2.0 * PI * k as f64) / 3.0).cos() - shift);
+        }
+    }
+
+    for x in roots.values[..roots.len].iter_mut() {
+        *x = polish(&[1.0, b, c, d], *x);
+    }
+    roots.sort()
+}
+
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    if a.abs() <= SOLVER_EPS {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // x^4 + b x^3 + c x^2 + d x + e = 0, then x = y - b / 4 gives
+    // y^4 + p y^2 + q y + r = 0
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let shift = 0.25 * b;
+    let b2 = b * b;
+    let p = c - 0.375 * b2;
+    let q = d - 0.5 * b * c + 0.125 * b2 * b;
+    let r = e - 0.25 * b * d + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = Roots::new();
+    if q.abs() <= SOLVER_EPS {
+        // biquadratic: z = y^2
+        for &z in solve_quadratic(1.0, p, r).iter() {
+            if z >= 0.0 {
+                let y = z.sqrt();
+                roots.push(y - shift);
+                roots.push(-y - shift);
+            }
+        }
+    } else {
+        // Ferrari: split into two quadratics using a positive root m of the
+        // resolvent cubic
+        let m = solve_cubic(1.0, p, 0.25 * p * p - r, -0.125 * q * q)
+            .iter()
+            .cloned()
+            .fold(0.0, f64::max);
+        if m <= 0.0 {
+            return roots;
+        }
+        let s = (2.0 * m).sqrt();
+        let h = 0.5 * q / s;
+        for &y in solve_quadratic(1.0, s, 0.5 * p + m - h)
+            .iter()
+            .chain(solve_quadratic(1.0, -s, 0.5 * p + m + h).iter())
+        {
+            roots.push(y - shift);
+        }
+    }
+
+    for x in roots.values[..roots.len].iter_mut() {
+        *x = polish(&[1.0, b, c, d, e], *x);
+    }
+    roots.sort()
+}
+
+/// Refines root `x` of polynomial `coeffs` with a few Newton steps, keeping
+/// the original value if they do not improve it.
+fn polish(coeffs: &[f64], mut x: f64) -> f64 {
+    let mut best = (x, evaluate(coeffs, x).0.abs());
+    for _ in 0..4 {
+        let (f, df) = evaluate(coeffs, x);
+        if df == 0.0 {
+            break;
+        }
+        x -= f / df;
+        let error = evaluate(coeffs, x).0.abs();
+        if error < best.1 {
+            best = (x, error);
+        }
+    }
+    best.0
+}
+
+/// Value and derivative of the polynomial at `x`, by Horner's rule.
+fn evaluate(coeffs: &[f64], x: f64) -> (f64, f64) {
+    let mut f = 0.0;
+    let mut df = 0.0;
+    for &c in coeffs {
+        df = df * x + f;
+        f = f * x + c;
+    }
+    (f, df)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (x, y) in roots.iter().zip(expected) {
+            assert!((x - y).abs() < 1e-9, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(&solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(&solve_quadratic(1.0, 0.0, 1.0), &[]);
+        assert_roots(&solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn cubic() {
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(&solve_cubic(1.0, 0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+        // (x - 2)(x^2 + 1)
+        assert_roots(&solve_cubic(2.0, -4.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            &solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x^2 - 4)(x^2 - 9), biquadratic
+        assert_roots(
+            &solve_quartic(1.0, 0.0, -13.0, 0.0, 36.0),
+            &[-3.0, -2.0, 2.0, 3.0],
+        );
+        // (x^2 + 1)(x - 5)(x + 0.5)
+        assert_roots(&solve_quartic(2.0, -9.0, -3.0, -9.0, -5.0), &[-0.5, 5.0]);
+        assert_roots(&solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+    }
+}
@@ -0,0 +1,161 @@
+use super::*;
+
+use std::fmt;
+use std::time::Duration;
+
+/// Counters collected while rendering, returned by the render functions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderStats {
+    pub primary_rays: u64,
+    pub reflection_rays: u64,
+    pub refraction_rays: u64,
+    pub shadow_rays: u64,
+    /// object intersection tests by full type name, in order of the first
+    /// object of each type
+    pub intersection_tests: Vec<(&'static str, u64)>,
+    /// deepest recursion level reached, 1 for primary rays
+    pub max_depth: u32,
+    pub pixels: u64,
+    pub wall_time: Duration,
+}
+
+impl RenderStats {
+    pub fn new() -> RenderStats {
+        RenderStats {
+            primary_rays: 0,
+            reflection_rays: 0,
+            refraction_rays: 0,
+            shadow_rays: 0,
+            intersection_tests: Vec::new(),
+            max_depth: 0,
+            pixels: 0,
+            wall_time: Duration::from_secs(0),
+        }
+    }
+
+    /// Adds `count` intersection tests with `object` to its type.
+    pub fn add_tests(&mut self, object: &GObject, count: u64) {
+        let name = object.type_name();
+        match self
+            .intersection_tests
+            .iter_mut()
+            .find(|entry| entry.0 == name)
+        {
+            Some(entry) => entry.1 += count,
+            None => self.intersection_tests.push((name, count)),
+        }
+    }
+
+    pub fn total_tests(&self) -> u64 {
+        self.intersection_tests
+            .iter()
+            .map(|&(_, count)| count)
+            .sum()
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.primary_rays + self.reflection_rays + self.refraction_rays + self.shadow_rays
+    }
+
+    /// Average number of camera rays per pixel, above 1 when supersampling.
+    pub fn samples_per_pixel(&self) -> f64 {
+        if self.pixels == 0 {
+            0.0
+        } else {
+            self.primary_rays as f64 / self.pixels as f64
+        }
+    }
+}
+
+impl Default for RenderStats {
+    fn default() -> RenderStats {
+        RenderStats::new()
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "pixels:             {}", self.pixels)?;
+        writeln!(f, "samples per pixel:  {:.2}", self.samples_per_pixel())?;
+        writeln!(f, "primary rays:       {}", self.primary_rays)?;
+        writeln!(f, "reflection rays:    {}", self.reflection_rays)?;
+        writeln!(f, "refraction rays:    {}", self.refraction_rays)?;
+        writeln!(f, "shadow rays:        {}", self.shadow_rays)?;
+        writeln!(f, "max depth:          {}", self.max_depth)?;
+        writeln!(f, "intersection tests: {}", self.total_tests())?;
+        for &(name, count) in &self.intersection_tests {
+            writeln!(f, "  {:<18}{}", short_name(name), count)?;
+        }
+        let time = self.wall_time.as_secs() as f64 + self.wall_time.subsec_nanos() as f64 * 1e-9;
+        write!(f, "wall time:          {:.3} s", time)
+    }
+}
+
+/// Type name without module path and type parameters.
+fn short_name(name: &str) -> &str {
+    let name = name.split('<').next().unwrap();
+    name.rsplit("::").next().unwrap()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use super::super::geometry::{Plane, Sphere};
+    use super::super::integrator::Whitted;
+    use super::super::light::PointLight;
+    use super::super::render::backend::NullRenderBackend;
+    use super::super::render::RenderOptions;
+
+    #[test]
+    fn counts_rays() {
+        let mirror = Surface {
+            k_r: 0.5,
+            ..Surface::new()
+        };
+        let sphere = Sphere::new(mirror, Vector::from((0.0, 0.0, 5.0)), 1.0);
+        let floor = Plane::new(Surface::new(), Vector::from((0.0, 1.0, 0.0)), 2.0);
+        let light = PointLight::new(Vector::from(1.0), Vector::from((0.0, 5.0, 0.0)), 1.0);
+        let mut environment = Environment::new();
+        environment.add_solid(&sphere);
+        environment.add_solid(&floor);
+        environment.add_light(&light);
+
+        let mut backend = NullRenderBackend::new();
+        let stats = render_scene(
+            &environment,
+            &Whitted::new(),
+            20,
+            10,
+            &RenderOptions::new(),
+            &mut backend,
+        ).unwrap();
+        assert_eq!(stats.pixels, 200);
+        assert_eq!(stats.primary_rays, 200);
+        assert_eq!(stats.samples_per_pixel(), 1.0);
+        assert!(stats.reflection_rays > 0);
+        assert_eq!(stats.refraction_rays, 0);
+        assert!(stats.shadow_rays > 0);
+        assert!(stats.max_depth >= 2);
+        assert_eq!(stats.intersection_tests.len(), 2);
+        let names: Vec<&str> = stats
+            .intersection_tests
+            .iter()
+            .map(|&(name, _)| short_name(name))
+            .collect();
+        assert_eq!(names, ["Sphere", "Plane"]);
+        // every ray tests both objects at least once
+        assert!(stats.total_tests() >= 2 * stats.total_rays());
+
+        // counting restarts with every render
+        let again = render_scene(
+            &environment,
+            &Whitted::new(),
+            20,
+            10,
+            &RenderOptions::new(),
+            &mut backend,
+        ).unwrap();
+        assert_eq!(again.primary_rays, 200);
+    }
+}
@@ -1,9 +1,17 @@
 use super::*;
 
+use super::loader::{Json, Prototypes};
+
 pub trait LightSource {
     fn color(&self) -> &Vector;
 
-    fn shadow(&self, p: &Vector, l: &mut Vector, environment: &Environment) -> f64;
+    fn shadow(&self, p: &Vector, l: &mut Vector, environment: &Environment, time: f64) -> f64;
+
+    /// Description in the native scene format, `None` if the light cannot be
+    /// written.
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
 
     fn shadow_trace(
         &self,
@@ -12,19 +20,17 @@ pub trait LightSource {
         environment: &Environment,
         mut distance: f64,
         mut attenuation: f64,
+        time: f64,
     ) -> f64 {
-        let mut ray = Ray::new(p, *l); // shadow ray
-
-        let mut t = INFINITY;
+        let mut ray = Ray::with_time(p, *l, time); // shadow ray
+        ray.t_max = distance;
 
+        environment.stats().shadow_rays += 1;
         let threshold = environment.threshold();
-        while let Some(occlude) = environment.intersect(&ray, &mut t) {
-            if distance <= t {
-                break;
-            }
+        while let Some((occlude, hit)) = environment.intersect(&ray) {
             // adjust ray origin and get transparency koeff.
-            ray.org = ray.point(t);
-            let texture = occlude.find_texture(&ray.org);
+            ray.org = hit.point;
+            let texture = occlude.find_texture(&hit);
 
             if texture.k_t < threshold {
                 return 0.0;
@@ -36,7 +42,8 @@ pub trait LightSource {
                 return 0.0;
             }
 
-            distance -= t;
+            distance -= hit.t;
+            ray.t_max = distance;
         }
 
         attenuation
@@ -46,13 +53,44 @@ pub trait LightSource {
 pub trait GObject {
     fn material(&self) -> &Surface;
 
-    fn find_texture(&self, p: &Vector) -> Surface {
+    /// Surface at `hit`, lit with the hit normal.
+    fn find_texture(&self, hit: &Hit) -> Surface {
         let mut result = *self.material();
-        result.n = self.find_normal(p);
+        result.n = hit.normal;
         result
     }
 
-    fn intersect(&self, ray: &Ray, t: &mut f64) -> bool;
+    /// Closest intersection inside of the ray interval.
+    fn intersect(&self, ray: &Ray) -> Option<Hit>;
+
+    /// Whether `p` lies inside of a closed object. By default a ray is cast
+    /// from `p` and checked to leave the object first.
+    fn inside(&self, p: &Vector) -> bool {
+        let mut ray = Ray::new(*p, Vector::from((0.267, 0.535, 0.802)).normalize());
+        ray.t_min = 0.0;
+        self.intersect(&ray).is_some_and(|hit| !hit.front_face)
+    }
+
+    /// Full name of the object type, render statistics are grouped by it.
+    fn type_name(&self) -> &'static str {
+        ::std::any::type_name::<Self>()
+    }
+
+    /// Box around the object, `None` if it is infinite or its extent is not
+    /// known.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
 
-    fn find_normal(&self, p: &Vector) -> Vector;
+    /// Description in the native scene format, `None` if the object cannot
+    /// be written.
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
+
+    /// Like `to_json`, objects shared by instances are written once to
+    /// `prototypes` and referred to by index.
+    fn to_json_shared(&self, _: &mut Prototypes) -> Option<Json> {
+        self.to_json()
+    }
 }
@@ -35,6 +35,15 @@ impl Vector {
         *self / !*self
     }
 
+    /// Unit vector orthogonal to this one.
+    pub fn perpendicular(&self) -> Vector {
+        if self.x.abs() > 0.5 * !*self {
+            Vector::from((self.y, -self.x, 0.0)).normalize()
+        } else {
+            Vector::from((0.0, self.z, -self.y)).normalize()
+        }
+    }
+
     pub fn clip(self) -> Vector {
         Vector {
             x: self.x.min(1.0).max(0.0),
@@ -579,6 +588,16 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn vector_perpendicular() {
+        for &v in &[(1.0, 0.0, 0.0), (0.0, 2.0, 0.0), (0.0, 0.0, -3.0), (1.0, 2.0, 3.0)] {
+            let v = Vector::from(v);
+            let p = v.perpendicular();
+            assert!((v & p).abs() < 1e-12);
+            assert!((!p - 1.0).abs() < 1e-12);
+        }
+    }
+
     #[test]
     fn vector_clip() {
         let mut v = Vector {